@@ -0,0 +1,33 @@
+//! Exposes the versions of dependencies reported to Lua, which Cargo doesn't provide on its own,
+//! and exports the vendored Lua from test binaries, so that tests can load the library into it.
+use std::path::Path;
+
+fn main() {
+    let lock_path = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+
+    let ftldat_version = std::fs::read_to_string(&lock_path).ok()
+        .and_then(|lock| locked_version(&lock, "ftldat"))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ITB_RS_FTLDAT_VERSION={}", ftldat_version);
+
+    // Libraries built with the `module` feature look Lua's C API up in the process loading them
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("linux") {
+        println!("cargo:rustc-link-arg-tests=-rdynamic");
+    }
+}
+
+/// Finds the version of the named package in the contents of a Cargo.lock file.
+fn locked_version(lock: &str, name: &str) -> Option<String> {
+    let name_line = format!("name = \"{}\"", name);
+    let mut lines = lock.lines();
+    for line in lines.by_ref() {
+        if line == name_line {
+            break;
+        }
+    }
+    lines.next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+        .map(str::to_string)
+}
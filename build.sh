@@ -4,4 +4,4 @@ export LUA_INC=./lua/include
 export LUA_LIB=./lua/lua5.1
 export LUA_LIB_NAME=./lua/lua5.1
 
-cargo build --lib --release --target=i686-pc-windows-msvc
+cargo build --lib --release --no-default-features --features module,full --target=i686-pc-windows-msvc
@@ -0,0 +1,796 @@
+---@meta itb_rs
+-- Generated by `cargo run --bin itb_rs_docs`, do not edit.
+
+---Lua bindings of utilities used in development of Into the Breach mods.
+---@class itb_rs
+---@field version { itb_rs: string, ftldat: string, major: integer, minor: integer, patch: integer } Version of this library, and of the ftldat library it was built with.
+---@field features string[] Names of the optional modules compiled into this library, matching the cargo features that enable them.
+---@field io itb_rs.io File system access, confined to a sandbox made up of the game directory, which relative paths are resolved against, and the save data directory.
+---@field ftldat itb_rs.ftldat Reading and writing of the .dat (Into the Breach, Faster than Light before 1.6.1) and .pkg (Faster than Light since 1.6.1) package formats.
+---@field buffer itb_rs.buffer Growable containers of binary data, with cursor-based reading and writing.
+---@field compress itb_rs.compress Compression and decompression of binary data.
+---@field encoding itb_rs.encoding Base64, hex and percent-encoding of binary data.
+---@field task itb_rs.task Long-running IO and packaging operations, run on a background thread.
+---@field mods itb_rs.mods Discovery of mods and resolution of the order to load them in, from the metadata their `scripts/init.lua` returns, which is read without executing the script.
+---@field semver itb_rs.semver Semantic versions, compared by precedence, and requirements they can be matched against.
+---@field error itb_rs.error Inspection of errors raised by the other modules.
+itb_rs = {}
+
+---Fails with `Unsupported` unless this library's version satisfies the requirement, eg. `>=0.2` or `^0.1, <0.1.5`, read as Cargo does. Returns the version.
+---@param requirement string
+---@return string
+function itb_rs.require_version(requirement) end
+
+---Whether this library has a module, function or field under the dot-separated path, eg. `io.watch`, or a method, eg. `Package:remove`.
+---@param name string
+---@return boolean
+function itb_rs.has(name) end
+
+---File system access, confined to a sandbox made up of the game directory, which relative paths are resolved against, and the save data directory. Paths outside of it fail with `NotAllowed`.
+---@class itb_rs.io
+---@field safe table<string, function> Non-throwing variants of this module's functions, which return `nil`, the error's kind and its message on failure, and `true` instead of nothing on success. Methods can be called the same way with `safe.call(object, method_name, ...)`.
+---@field audit itb_rs.io.audit Log of file system changes, recording which mod deleted or overwrote what.
+itb_rs.io = {}
+
+---File at the specified path, relative to the game directory.
+---@param path string?
+---@return File
+function itb_rs.io.file(path) end
+
+---Directory at the specified path, relative to the game directory; the game directory itself by default.
+---@param path string?
+---@return Directory
+function itb_rs.io.directory(path) end
+
+---Directory the game stores save data in.
+---@return Directory
+function itb_rs.io.save_data_directory() end
+
+---Switches to another valid location among `save_data_candidates`, eg. when several installations exist, or restores discovery if `nil`. Other directories are rejected with `NotAllowed`.
+---@param path string?
+---@return Directory
+function itb_rs.io.set_save_data_directory(path) end
+
+---Locations the save data directory is looked for in, in order.
+---@return { path: string, source: string, valid: boolean }[]
+function itb_rs.io.save_data_candidates() end
+
+---Watches the directory for changes, recursively and with a debounce of 100 milliseconds unless specified otherwise.
+---@param directory Directory
+---@param options { recursive: boolean?, debounce: integer?, filters: string|string[]? }?
+---@return Watcher
+function itb_rs.io.watch(directory, options) end
+
+---Log of file system changes, recording which mod deleted or overwrote what.
+---@class itb_rs.io.audit
+---@field safe table<string, function> Non-throwing variants of this module's functions, which return `nil`, the error's kind and its message on failure, and `true` instead of nothing on success. Methods can be called the same way with `safe.call(object, method_name, ...)`.
+itb_rs.io.audit = {}
+
+---Starts recording every write, delete, move and copy to `itb_rs_audit.log` in the save data directory, rotating the file once it exceeds the size limit. Returns the log file.
+---@param options { max_file_size: integer?, max_files: integer? }?
+---@return File
+function itb_rs.io.audit.enable(options) end
+
+---Stops recording.
+function itb_rs.io.audit.disable() end
+
+---Whether changes are being recorded.
+---@return boolean
+function itb_rs.io.audit.is_enabled() end
+
+---Sets the mod that subsequent changes are attributed to.
+---@param id string?
+function itb_rs.io.audit.set_mod(id) end
+
+---Mod that changes are currently attributed to.
+---@return string?
+function itb_rs.io.audit.current_mod() end
+
+---Most recent entries of the log, 50 unless specified otherwise, oldest first.
+---@param count integer?
+---@return { time: number, operation: string, path: string, destination: string?, mod: string? }[]
+function itb_rs.io.audit.recent(count) end
+
+---Reading and writing of the .dat (Into the Breach, Faster than Light before 1.6.1) and .pkg (Faster than Light since 1.6.1) package formats. Formats are named `dat` or `itb`, and `pkg` or `ftl`; `auto` detects them. Paths are confined to the sandbox, and relative ones resolved against the game directory, as in the io module.
+---@class itb_rs.ftldat
+---@field safe table<string, function> Non-throwing variants of this module's functions, which return `nil`, the error's kind and its message on failure, and `true` instead of nothing on success. Methods can be called the same way with `safe.call(object, method_name, ...)`.
+itb_rs.ftldat = {}
+
+---Reads the package at the specified path, detecting its format unless specified otherwise.
+---@param path string
+---@param options { format: string? }?
+---@return Package
+function itb_rs.ftldat.read_package(path, options) end
+
+---Creates an empty package, written out in the specified format (`dat` by default).
+---@param options { format: string? }?
+---@return Package
+function itb_rs.ftldat.new_package(options) end
+
+---Detects the format of the package at the specified path, returning `dat` or `pkg`.
+---@param path string
+---@return string
+function itb_rs.ftldat.detect_format(path) end
+
+---Lists inner paths present only in `b`, only in `a`, and in both but with different content.
+---@param a Package
+---@param b Package
+---@return { added: string[], removed: string[], modified: string[] }
+function itb_rs.ftldat.diff(a, b) end
+
+---Copies entries of `other` into `base`, resolving entries that differ according to the strategy. With `error`, the default, `base` is left untouched if any entries conflict.
+---@param base Package
+---@param other Package
+---@param strategy "error"|"keep_base"|"take_other"?
+---@return { added: string[], replaced: string[], kept: string[] }
+function itb_rs.ftldat.merge(base, other, strategy) end
+
+---Checks the package at the specified path for corruption. If `repair` is set to a path, all readable entries are salvaged into a new package written to that path.
+---@param path string
+---@param options { format: string?, repair: string? }?
+---@return { ok: boolean, format: string, entry_count: integer, readable_count: integer, problems: { kind: string, entry: integer?, path: string?, message: string }[], repaired_count: integer? }
+function itb_rs.ftldat.verify(path, options) end
+
+---Growable containers of binary data, with cursor-based reading and writing.
+---@class itb_rs.buffer
+itb_rs.buffer = {}
+
+---Creates a buffer holding a copy of the specified content, or an empty one.
+---@param content string|Buffer|integer[]?
+---@return Buffer
+function itb_rs.buffer.new(content) end
+
+---Compression and decompression of binary data.
+---@class itb_rs.compress
+---@field default_size_limit integer Size limit of decompressed data, in bytes, guarding against zip bombs.
+itb_rs.compress = {}
+
+---Compresses the data with the specified algorithm. The level ranges from 0 to 9, and is ignored by LZ4.
+---@param data string|Buffer|integer[]
+---@param algorithm "deflate"|"zlib"|"gzip"|"lz4"
+---@param level integer?
+---@return Buffer
+function itb_rs.compress.compress(data, algorithm, level) end
+
+---Decompresses the data, failing once the output exceeds the size limit, `default_size_limit` unless specified otherwise.
+---@param data string|Buffer|integer[]
+---@param algorithm "deflate"|"zlib"|"gzip"|"lz4"
+---@param size_limit integer?
+---@return Buffer
+function itb_rs.compress.decompress(data, algorithm, size_limit) end
+
+---Base64, hex and percent-encoding of binary data.
+---@class itb_rs.encoding
+itb_rs.encoding = {}
+
+---Encodes the data in base64, with the standard alphabet and padding unless specified otherwise.
+---@param data string|Buffer|integer[]
+---@param options { url_safe: boolean?, padding: boolean? }?
+---@return string
+function itb_rs.encoding.base64_encode(data, options) end
+
+---Decodes base64 text, which has to use the same alphabet and padding as specified.
+---@param text string|Buffer|integer[]
+---@param options { url_safe: boolean?, padding: boolean? }?
+---@return string
+function itb_rs.encoding.base64_decode(text, options) end
+
+---Encodes the data as hex digits, lowercase unless specified otherwise.
+---@param data string|Buffer|integer[]
+---@param options { uppercase: boolean? }?
+---@return string
+function itb_rs.encoding.hex_encode(data, options) end
+
+---Decodes hex digits of either case.
+---@param text string|Buffer|integer[]
+---@return string
+function itb_rs.encoding.hex_decode(text) end
+
+---Percent-encodes all bytes except unreserved characters.
+---@param data string|Buffer|integer[]
+---@return string
+function itb_rs.encoding.percent_encode(data) end
+
+---Decodes percent-encoded text, leaving invalid escapes as they are.
+---@param text string|Buffer|integer[]
+---@return string
+function itb_rs.encoding.percent_decode(text) end
+
+---Long-running IO and packaging operations, run on a background thread.
+---@class itb_rs.task
+itb_rs.task = {}
+
+---Reads the file; the task's result is a `Buffer`.
+---@param file File
+---@return Task
+function itb_rs.task.spawn_read(file) end
+
+---Writes the data to the file, creating its parent directories.
+---@param file File
+---@param data string|Buffer|integer[]
+---@return Task
+function itb_rs.task.spawn_write(file, data) end
+
+---Copies the file to the destination, which has to be within the sandbox.
+---@param file File
+---@param destination File
+---@return Task
+function itb_rs.task.spawn_copy(file, destination) end
+
+---Packs the files within the directory matching the filters into a package, under the prefix; the task's result is the number of entries.
+---@param directory Directory
+---@param destination File
+---@param options { format: string?, prefix: string?, filters: string|string[]? }?
+---@return Task
+function itb_rs.task.spawn_pack(directory, destination, options) end
+
+---Extracts the entries of a package matching the filters into the directory; the task's result is the number of extracted entries.
+---@param file File
+---@param destination Directory
+---@param options { format: string?, filters: string|string[]? }?
+---@return Task
+function itb_rs.task.spawn_extract(file, destination, options) end
+
+---Discovery of mods and resolution of the order to load them in, from the metadata their `scripts/init.lua` returns, which is read without executing the script.
+---@class itb_rs.mods
+---@field safe table<string, function> Non-throwing variants of this module's functions, which return `nil`, the error's kind and its message on failure, and `true` instead of nothing on success. Methods can be called the same way with `safe.call(object, method_name, ...)`.
+itb_rs.mods = {}
+
+---Reads the metadata of the mods in the subdirectories of the mods directory, ordered by name. Mods whose metadata can't be read statically, such as those computing their `id`, are listed as invalid.
+---@param directory Directory
+---@return { mods: { id: string, name: string?, version: string?, semver: string?, directory: Directory?, dependencies: { id: string, requirement: string?, optional: boolean }[], conflicts: string[] }[], invalid: { directory: Directory, error: Error }[] }
+function itb_rs.mods.scan(directory) end
+
+---Reads the metadata of a mod from the source of its init script: its `id`, `name` and `version`, required dependencies from `requirements` and `dependencies`, optional ones from `optionalDependencies`, and `conflicts`. Dependencies are listed as ids, or as a table of version requirements by id, where a bare version is the minimum version. `semver` is the version parsed as semver, if it can be.
+---@param source string
+---@return { id: string, name: string?, version: string?, semver: string?, directory: Directory?, dependencies: { id: string, requirement: string?, optional: boolean }[], conflicts: string[] }
+function itb_rs.mods.read_metadata(source) end
+
+---Resolves the order to load the mods in, so that each one is loaded after its dependencies. Mods are excluded if a required dependency is missing or excluded itself, if they're in a cycle of required dependencies, or if they conflict with another mod; the report lists why.
+---@param mods { id: string, name: string?, version: string?, semver: string?, directory: Directory?, dependencies: { id: string, requirement: string?, optional: boolean }[], conflicts: string[] }[]
+---@return { order: string[], excluded: string[], missing: { id: string, dependency: string, requirement: string?, installed: string? }[], cycles: string[][], conflicts: { id: string, conflict: string }[], duplicates: string[] }
+function itb_rs.mods.load_order(mods) end
+
+---Semantic versions, compared by precedence, and requirements they can be matched against. Versions passed as strings are parsed leniently, to accept the irregular versions mods use.
+---@class itb_rs.semver
+---@field safe table<string, function> Non-throwing variants of this module's functions, which return `nil`, the error's kind and its message on failure, and `true` instead of nothing on success. Methods can be called the same way with `safe.call(object, method_name, ...)`.
+itb_rs.semver = {}
+
+---Parses a version. Unless `strict` is set, irregular versions are read as closely to semver as possible: a leading `v` is skipped, missing minor and patch components are zero, components past the patch become build metadata, and whatever follows the numbers becomes the pre-release, eg. `v3` is `3.0.0` and `1.2b` is `1.2.0-b`. Fails with `InvalidInput` if the text doesn't start with a number.
+---@param text string
+---@param options { strict: boolean? }?
+---@return Version
+function itb_rs.semver.parse(text, options) end
+
+---Creates a release version from its components, which default to zero.
+---@param major integer
+---@param minor integer?
+---@param patch integer?
+---@return Version
+function itb_rs.semver.new(major, minor, patch) end
+
+---Parses a version requirement: comma-separated comparators, eg. `>=1.2, <2`, `^1.4` (compatible with 1.4, below 2.0) or `~1.2` (1.2.x). A bare version is the same as `^`, as in Cargo, unless `minimum` is set, in which case it's the minimum version, the way the mod loader reads dependencies, eg. `1.2` is `>=1.2`.
+---@param text string
+---@param options { minimum: boolean? }?
+---@return VersionReq
+function itb_rs.semver.requirement(text, options) end
+
+---Compares two versions by precedence, returning -1, 0 or 1. Pre-releases precede their release, and build metadata only breaks ties.
+---@param a string|Version
+---@param b string|Version
+---@return integer
+function itb_rs.semver.compare(a, b) end
+
+---Whether the version satisfies the requirement. Requirements passed as strings are parsed like `requirement` does by default. Pre-releases only satisfy requirements that mention a pre-release of the same version.
+---@param requirement string|VersionReq
+---@param version string|Version
+---@return boolean
+function itb_rs.semver.matches(requirement, version) end
+
+---Inspection of errors raised by the other modules.
+---@class itb_rs.error
+itb_rs.error = {}
+
+---Turns an error caught with `pcall` into an `Error`. Errors that didn't come from this library are of kind `Lua`.
+---@param error any
+---@return Error
+function itb_rs.error.inspect(error) end
+
+---Works like `pcall`, but returns an `Error` if the call failed.
+---@param f function
+---@param ... any
+---@return boolean
+---@return any ...
+function itb_rs.error.pcall(f, ...) end
+
+---File within the sandbox, which may or may not exist.
+---@class File
+local File = {}
+
+---Absolute path of the file.
+---@return string
+function File:path() end
+
+---Path of the file relative to the game or save data directory it's in.
+---@return string
+function File:relative_path() end
+
+---Name of the file, including its extension.
+---@return string
+function File:name() end
+
+---Name of the file, without its extension.
+---@return string
+function File:name_without_extension() end
+
+---Extension of the file, without the dot, or `nil` if it has none.
+---@return string?
+function File:extension() end
+
+---Directory the file is in. Fails with `NotAllowed` outside of the sandbox.
+---@return Directory
+function File:parent() end
+
+---Game or save data directory the file is in.
+---@return Directory
+function File:root() end
+
+---Reads the file as UTF-8 text.
+---@return string
+function File:read_to_string() end
+
+---Reads the file as a table of bytes.
+---@return integer[]
+function File:read_to_byte_array() end
+
+---Reads the file into a buffer.
+---@return Buffer
+function File:read_to_buffer() end
+
+---Writes the text to the file, creating its parent directories and replacing its content.
+---@param content string
+function File:write_string(content) end
+
+---Appends the text to the file, creating it if it doesn't exist.
+---@param content string
+function File:append_string(content) end
+
+---Writes the bytes to the file, creating its parent directories and replacing its content.
+---@param content string|Buffer|integer[]
+function File:write_byte_array(content) end
+
+---Reads and decompresses a gzipped file, failing once the output exceeds the size limit.
+---@param size_limit integer?
+---@return Buffer
+function File:read_gzip(size_limit) end
+
+---Compresses the content with gzip, at the specified level from 0 to 9, and writes it to the file.
+---@param content string|Buffer|integer[]
+---@param level integer?
+function File:write_gzip(content, level) end
+
+---Copies the file to the destination path, which has to be within the sandbox.
+---@param destination string
+function File:copy(destination) end
+
+---Moves the file to the destination path, which has to be within the sandbox.
+---@param destination string
+function File:move(destination) end
+
+---Whether the file exists.
+---@return boolean
+function File:exists() end
+
+---Deletes the file, if it exists.
+function File:delete() end
+
+---Directory within the sandbox, which may or may not exist.
+---@class Directory
+local Directory = {}
+
+---Absolute path of the directory, with a trailing slash.
+---@return string
+function Directory:path() end
+
+---Path of the directory relative to the game or save data directory it's in.
+---@return string
+function Directory:relative_path() end
+
+---Name of the directory.
+---@return string
+function Directory:name() end
+
+---Directory this one is in. Fails with `NotAllowed` outside of the sandbox.
+---@return Directory
+function Directory:parent() end
+
+---Game or save data directory this one is in.
+---@return Directory
+function Directory:root() end
+
+---Path relative to this directory, or `nil` if it can't be expressed as one.
+---@param path string
+---@return string?
+function Directory:relativize(path) end
+
+---File at the path made of the specified segments, relative to this directory.
+---@param ... string
+---@return File
+function Directory:file(...) end
+
+---Directory at the path made of the specified segments, relative to this directory.
+---@param ... string
+---@return Directory
+function Directory:directory(...) end
+
+---Files directly within this directory.
+---@return File[]
+function Directory:files() end
+
+---Directories directly within this directory.
+---@return Directory[]
+function Directory:directories() end
+
+---Creates this directory and all of its missing parents.
+function Directory:make_directories() end
+
+---Whether the directory exists.
+---@return boolean
+function Directory:exists() end
+
+---Whether the absolute path lies within this directory.
+---@param path string
+---@return boolean
+function Directory:is_ancestor(path) end
+
+---Deletes the directory with all of its content. If aborted through the callback, files already deleted stay that way.
+---@param callback fun(done: integer, total: integer, path: string): boolean??
+function Directory:delete(callback) end
+
+---Watch over a directory, collecting changes on a background thread.
+---@class Watcher
+local Watcher = {}
+
+---Changes collected since the last poll, with paths relative to the watched directory. Rapid successive changes to the same path are merged.
+---@return { kind: "create"|"modify"|"delete"|"rename", path: string, to: string? }[]
+function Watcher:poll() end
+
+---Stops watching.
+function Watcher:close() end
+
+---Whether the watcher has been closed.
+---@return boolean
+function Watcher:is_closed() end
+
+---Package held in memory. Entries read from a file are only read once their content is needed. Writing the package out closes it, after which its methods raise `Closed` errors.
+---@class Package
+local Package = {}
+
+---Writes the package to the specified path, in the format it was read or created in unless specified otherwise, and closes it. Progress is reported as the content of each entry is read; aborting through the callback keeps the package open.
+---@param path string
+---@param options { format: string? }?
+---@param callback fun(done: integer, total: integer, path: string): boolean??
+function Package:to_file(path, options, callback) end
+
+---Format the package is written out in by default, `dat` or `pkg`.
+---@return string
+function Package:format() end
+
+---Closes the package, releasing its content.
+function Package:destroy() end
+
+---Adds an entry, failing with `AlreadyExists` if there's one under the same path already.
+---@param path string
+---@param content string
+function Package:add_entry_from_string(path, content) end
+
+---Same as `add_entry_from_string`, for binary content.
+---@param path string
+---@param content string|Buffer|integer[]
+function Package:add_entry_from_byte_array(path, content) end
+
+---Adds an entry with the content of the file at `source_path`, which is read once the package is written out.
+---@param path string
+---@param source_path string
+function Package:add_entry_from_file(path, source_path) end
+
+---Adds an entry, replacing the one under the same path, if any.
+---@param path string
+---@param content string
+function Package:put_entry_from_string(path, content) end
+
+---Same as `put_entry_from_string`, for binary content.
+---@param path string
+---@param content string|Buffer|integer[]
+function Package:put_entry_from_byte_array(path, content) end
+
+---Same as `add_entry_from_file`, but replaces the entry under the same path, if any.
+---@param path string
+---@param source_path string
+function Package:put_entry_from_file(path, source_path) end
+
+---Content of the entry under the specified path as UTF-8 text, or `nil` if there's no such entry.
+---@param path string
+---@return string?
+function Package:read_content_as_string(path) end
+
+---Content of the entry under the specified path as a table of bytes, or `nil` if there's no such entry.
+---@param path string
+---@return integer[]?
+function Package:read_content_as_byte_array(path) end
+
+---Content of the entry under the specified path, or `nil` if there's no such entry.
+---@param path string
+---@return Buffer?
+function Package:read_content_as_buffer(path) end
+
+---Removes the entry under the specified path, returning whether there was one.
+---@param path string
+---@return boolean
+function Package:remove(path) end
+
+---Whether there's an entry under the specified path.
+---@param path string
+---@return boolean
+function Package:exists(path) end
+
+---Removes all entries.
+function Package:clear() end
+
+---Paths of all entries, or of those matching the filter: a prefix, or a glob pattern such as `**/*.lua`.
+---@param filter string?
+---@return string[]
+function Package:inner_paths(filter) end
+
+---Size of the entry's content in bytes, or `nil` if there's no such entry.
+---@param path string
+---@return integer?
+function Package:entry_size(path) end
+
+---Iterator over the path, size and offset of each entry. The offset is `nil` for entries that weren't read from the package's file.
+---@return fun(): string?
+---@return integer?
+---@return integer?
+function Package:entries() end
+
+---Number of entries.
+---@return integer
+function Package:len() end
+
+---Same as `len`.
+---@return integer
+function Package:entry_count() end
+
+---Extracts all entries into the directory at the specified path, relative to the game directory, returning their number.
+---@param path string
+---@return integer
+function Package:extract(path) end
+
+---Puts the files within the directory that match the filters into the package, under the prefix, returning the numbers of added and replaced entries. Files are only read once the package is written out. If aborted, the package is left unchanged.
+---@param directory Directory
+---@param prefix string?
+---@param filters string|string[]?
+---@param callback fun(done: integer, total: integer, path: string): boolean??
+---@return integer
+---@return integer
+function Package:add_directory(directory, prefix, filters, callback) end
+
+---Extracts the entries matching the filters into the directory, returning their number.
+---@param directory Directory
+---@param filters string|string[]?
+---@param callback fun(done: integer, total: integer, path: string): boolean??
+---@return integer
+function Package:extract_all(directory, filters, callback) end
+
+---Binary data with a cursor, which reads without a position and all writes start at, and advance. Positions are 1-based, like those of the string library. Buffers are accepted anywhere binary content is expected.
+---@class Buffer
+---@operator len: integer
+---@operator concat(string|Buffer): Buffer
+local Buffer = {}
+
+---Number of bytes in the buffer.
+---@return integer
+function Buffer:len() end
+
+---Copies the bytes between positions `i` and `j` into a new buffer, following the rules of `string.sub`.
+---@param i integer
+---@param j integer?
+---@return Buffer
+function Buffer:sub(i, j) end
+
+---Returns the bytes between positions `i` and `j`, following the rules of `string.byte`.
+---@param i integer?
+---@param j integer?
+---@return integer ...
+function Buffer:byte(i, j) end
+
+---Returns the buffer's content as a Lua string, which is binary-safe.
+---@return string
+function Buffer:tostring() end
+
+---Same as `tostring`.
+---@return string
+function Buffer:to_string() end
+
+---Position of the cursor.
+---@return integer
+function Buffer:tell() end
+
+---Moves the cursor to the specified position.
+---@param position integer
+function Buffer:seek(position) end
+
+---Moves the cursor by the specified number of bytes, backwards if negative.
+---@param count integer
+function Buffer:skip(count) end
+
+---Reads an unsigned 8-bit integer at the specified position, or at the cursor, in little endian unless specified otherwise.
+---@param position integer?
+---@param endian "le"|"little"|"be"|"big"?
+---@return integer
+function Buffer:read_u8(position, endian) end
+
+---Writes an unsigned 8-bit integer at the cursor, in little endian unless specified otherwise.
+---@param value integer
+---@param endian "le"|"little"|"be"|"big"?
+function Buffer:write_u8(value, endian) end
+
+---Reads a signed 8-bit integer at the specified position, or at the cursor, in little endian unless specified otherwise.
+---@param position integer?
+---@param endian "le"|"little"|"be"|"big"?
+---@return integer
+function Buffer:read_i8(position, endian) end
+
+---Writes a signed 8-bit integer at the cursor, in little endian unless specified otherwise.
+---@param value integer
+---@param endian "le"|"little"|"be"|"big"?
+function Buffer:write_i8(value, endian) end
+
+---Reads an unsigned 16-bit integer at the specified position, or at the cursor, in little endian unless specified otherwise.
+---@param position integer?
+---@param endian "le"|"little"|"be"|"big"?
+---@return integer
+function Buffer:read_u16(position, endian) end
+
+---Writes an unsigned 16-bit integer at the cursor, in little endian unless specified otherwise.
+---@param value integer
+---@param endian "le"|"little"|"be"|"big"?
+function Buffer:write_u16(value, endian) end
+
+---Reads a signed 16-bit integer at the specified position, or at the cursor, in little endian unless specified otherwise.
+---@param position integer?
+---@param endian "le"|"little"|"be"|"big"?
+---@return integer
+function Buffer:read_i16(position, endian) end
+
+---Writes a signed 16-bit integer at the cursor, in little endian unless specified otherwise.
+---@param value integer
+---@param endian "le"|"little"|"be"|"big"?
+function Buffer:write_i16(value, endian) end
+
+---Reads an unsigned 32-bit integer at the specified position, or at the cursor, in little endian unless specified otherwise.
+---@param position integer?
+---@param endian "le"|"little"|"be"|"big"?
+---@return integer
+function Buffer:read_u32(position, endian) end
+
+---Writes an unsigned 32-bit integer at the cursor, in little endian unless specified otherwise.
+---@param value integer
+---@param endian "le"|"little"|"be"|"big"?
+function Buffer:write_u32(value, endian) end
+
+---Reads a signed 32-bit integer at the specified position, or at the cursor, in little endian unless specified otherwise.
+---@param position integer?
+---@param endian "le"|"little"|"be"|"big"?
+---@return integer
+function Buffer:read_i32(position, endian) end
+
+---Writes a signed 32-bit integer at the cursor, in little endian unless specified otherwise.
+---@param value integer
+---@param endian "le"|"little"|"be"|"big"?
+function Buffer:write_i32(value, endian) end
+
+---Reads a 32-bit float at the specified position, or at the cursor, in little endian unless specified otherwise.
+---@param position integer?
+---@param endian "le"|"little"|"be"|"big"?
+---@return number
+function Buffer:read_f32(position, endian) end
+
+---Writes a 32-bit float at the cursor, in little endian unless specified otherwise.
+---@param value number
+---@param endian "le"|"little"|"be"|"big"?
+function Buffer:write_f32(value, endian) end
+
+---Reads a 64-bit float at the specified position, or at the cursor, in little endian unless specified otherwise.
+---@param position integer?
+---@param endian "le"|"little"|"be"|"big"?
+---@return number
+function Buffer:read_f64(position, endian) end
+
+---Writes a 64-bit float at the cursor, in little endian unless specified otherwise.
+---@param value number
+---@param endian "le"|"little"|"be"|"big"?
+function Buffer:write_f64(value, endian) end
+
+---Reads the specified number of bytes at the specified position, or at the cursor, into a new buffer.
+---@param len integer
+---@param position integer?
+---@return Buffer
+function Buffer:read_bytes(len, position) end
+
+---Reads the specified number of bytes at the specified position, or at the cursor, into a string.
+---@param len integer
+---@param position integer?
+---@return string
+function Buffer:read_string(len, position) end
+
+---Writes the specified bytes at the cursor.
+---@param content string|Buffer|integer[]
+function Buffer:write_bytes(content) end
+
+---Returns the start and end positions of the first occurrence of `needle` at or after position `init`.
+---@param needle string|Buffer|integer[]
+---@param init integer?
+---@return integer?
+---@return integer?
+function Buffer:find(needle, init) end
+
+---Handle of an operation running on a background thread, to be polled from Lua.
+---@class Task
+local Task = {}
+
+---Whether the operation has finished, successfully or not.
+---@return boolean
+function Task:is_done() end
+
+---Numbers of completed and total steps of the operation.
+---@return integer
+---@return integer
+function Task:progress() end
+
+---Stops the operation at its next step; files written up to that point are left in place.
+function Task:cancel() end
+
+---Result of the finished operation, or `nil` if it hasn't finished yet. Raises the operation's error if it failed.
+---@return any
+function Task:result() end
+
+---Semantic version. Versions can be compared with `==`, `<` and `<=` against other versions, and converted to strings with `tostring`.
+---@class Version
+---@field major integer Major version, incremented by incompatible changes.
+---@field minor integer Minor version, incremented by compatible additions.
+---@field patch integer Patch version, incremented by fixes.
+---@field pre string Pre-release identifiers, eg. `beta.2`, or an empty string for releases.
+---@field build string Build metadata, which doesn't affect precedence, or an empty string.
+local Version = {}
+
+---Returns the next release after incrementing the part, and resetting the ones after it. Pre-releases are bumped to their release if it's the next version of that part, eg. bumping the patch of `1.2.3-beta` gives `1.2.3`. Fails with `InvalidInput` if the part can't be incremented any further.
+---@param part "major"|"minor"|"patch"
+---@return Version
+function Version:bump(part) end
+
+---Whether this version satisfies the requirement.
+---@param requirement string|VersionReq
+---@return boolean
+function Version:satisfies(requirement) end
+
+---Whether this version has pre-release identifiers.
+---@return boolean
+function Version:is_prerelease() end
+
+---Version requirement, made of comparators all versions matching it satisfy.
+---@class VersionReq
+local VersionReq = {}
+
+---Whether the version satisfies this requirement.
+---@param version string|Version
+---@return boolean
+function VersionReq:matches(version) end
+
+---Error raised by this library. Its kind is one of `NotFound`, `NotAllowed`, `AlreadyExists`, `InvalidInput`, `InvalidData`, `Aborted`, `Closed`, `Unsupported`, `Lua` and `Io`.
+---@class Error
+---@field kind string Category of the error, to tell failures apart without matching on messages.
+---@field message string Description of the error.
+---@field path string? Path the error concerns, if any.
+---@field causes string[] Messages of the errors that led to this one, starting with the most immediate.
+local Error = {}
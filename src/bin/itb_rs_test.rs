@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(itb_rs_lua::runner::main());
+}
@@ -0,0 +1,324 @@
+use std::ops::Range;
+
+use crate::error::{Error, ErrorKind};
+
+/// Byte order used when reading or writing numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    pub fn parse<S: AsRef<str>>(name: S) -> crate::error::Result<Endian> {
+        match name.as_ref() {
+            "le" | "little" => Ok(Endian::Little),
+            "be" | "big" => Ok(Endian::Big),
+            other => Err(Error::new(ErrorKind::InvalidInput, format!(
+                "Unknown endianness '{}', expected one of: le, little, be, big", other
+            )))
+        }
+    }
+}
+
+/// Numbers that can be read from and written to a [Buffer].
+pub trait Number: Sized {
+    const SIZE: usize;
+
+    fn from_bytes(bytes: &[u8], endian: Endian) -> Self;
+
+    fn to_bytes(&self, endian: Endian) -> Vec<u8>;
+}
+
+macro_rules! impl_number {
+    ($($number:ty),*) => {
+        $(
+            impl Number for $number {
+                const SIZE: usize = std::mem::size_of::<$number>();
+
+                fn from_bytes(bytes: &[u8], endian: Endian) -> Self {
+                    let bytes = bytes.try_into().unwrap();
+                    match endian {
+                        Endian::Little => <$number>::from_le_bytes(bytes),
+                        Endian::Big => <$number>::from_be_bytes(bytes),
+                    }
+                }
+
+                fn to_bytes(&self, endian: Endian) -> Vec<u8> {
+                    match endian {
+                        Endian::Little => self.to_le_bytes().to_vec(),
+                        Endian::Big => self.to_be_bytes().to_vec(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(u8, i8, u16, i16, u32, i32, f32, f64);
+
+/// Largest size a buffer can be grown to by writes, well below what the game's 32-bit process can
+/// allocate, so that a stray position fails the write instead of aborting the game.
+pub const MAX_LEN: usize = 256 * 1024 * 1024;
+
+/// Growable container of binary data, exposed to Lua in place of strings or tables of bytes, so
+/// that content read from files or packages doesn't have to be converted into Lua values byte by
+/// byte.
+///
+/// Besides explicit offsets, the buffer keeps a cursor which reads and writes can advance through
+/// the data, like a file handle.
+#[derive(Debug, Clone, Default)]
+pub struct Buffer {
+    bytes: Vec<u8>,
+    /// Offset at which the next cursor-based read or write will happen.
+    position: usize,
+}
+
+impl Buffer {
+    pub fn new() -> Buffer {
+        Buffer::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves the cursor to the specified offset. The cursor may be placed past the end of the
+    /// buffer, in which case the next write pads the gap with zeroes, but not past [MAX_LEN].
+    pub fn seek(&mut self, position: usize) -> crate::error::Result<()> {
+        if position > MAX_LEN {
+            return Err(Error::new(ErrorKind::InvalidInput, format!(
+                "Attempted to seek to offset {}, but buffers can't be longer than {} bytes", position, MAX_LEN
+            )));
+        }
+        self.position = position;
+        Ok(())
+    }
+
+    pub fn read_bytes(&self, offset: usize, len: usize) -> crate::error::Result<&[u8]> {
+        self.bytes.get(offset..offset.saturating_add(len))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!(
+                "Attempted to read {} bytes at offset {}, but buffer is only {} bytes long",
+                len, offset, self.bytes.len()
+            )))
+    }
+
+    /// Writes the bytes at the specified offset, overwriting existing content and growing the
+    /// buffer as needed, up to [MAX_LEN].
+    pub fn write_bytes(&mut self, offset: usize, bytes: &[u8]) -> crate::error::Result<()> {
+        let end = offset.checked_add(bytes.len())
+            .filter(|end| *end <= MAX_LEN)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!(
+                "Attempted to write {} bytes at offset {}, but buffers can't be longer than {} bytes",
+                bytes.len(), offset, MAX_LEN
+            )))?;
+        if end > self.bytes.len() {
+            self.bytes.resize(end, 0);
+        }
+        self.bytes[offset..end].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    pub fn read<T: Number>(&self, offset: usize, endian: Endian) -> crate::error::Result<T> {
+        self.read_bytes(offset, T::SIZE)
+            .map(|bytes| T::from_bytes(bytes, endian))
+    }
+
+    pub fn write<T: Number>(&mut self, offset: usize, value: T, endian: Endian) -> crate::error::Result<()> {
+        self.write_bytes(offset, &value.to_bytes(endian))
+    }
+
+    /// Reads a number at the cursor, and advances the cursor past it.
+    pub fn read_next<T: Number>(&mut self, endian: Endian) -> crate::error::Result<T> {
+        let value = self.read(self.position, endian)?;
+        self.position += T::SIZE;
+        Ok(value)
+    }
+
+    /// Writes a number at the cursor, and advances the cursor past it.
+    pub fn write_next<T: Number>(&mut self, value: T, endian: Endian) -> crate::error::Result<()> {
+        self.write(self.position, value, endian)?;
+        self.position += T::SIZE;
+        Ok(())
+    }
+
+    /// Reads bytes at the cursor, and advances the cursor past them.
+    pub fn read_bytes_next(&mut self, len: usize) -> crate::error::Result<Vec<u8>> {
+        let bytes = self.read_bytes(self.position, len)?.to_vec();
+        self.position += len;
+        Ok(bytes)
+    }
+
+    /// Writes bytes at the cursor, and advances the cursor past them.
+    pub fn write_bytes_next(&mut self, bytes: &[u8]) -> crate::error::Result<()> {
+        self.write_bytes(self.position, bytes)?;
+        self.position += bytes.len();
+        Ok(())
+    }
+
+    /// Returns the offset of the first occurrence of `needle` at or after offset `from`.
+    pub fn find(&self, needle: &[u8], from: usize) -> Option<usize> {
+        if from > self.bytes.len() {
+            return None;
+        }
+        if needle.is_empty() {
+            return Some(from);
+        }
+
+        self.bytes[from..].windows(needle.len())
+            .position(|window| window == needle)
+            .map(|index| from + index)
+    }
+
+    /// Returns a copy of the bytes between positions `i` and `j`, following the same rules as
+    /// Lua's `string.sub`: positions are 1-based and inclusive, negative positions count from
+    /// the end, and out-of-range positions are clamped.
+    pub fn sub(&self, i: i64, j: Option<i64>) -> Buffer {
+        let range = self.lua_range(i, j.unwrap_or(-1));
+        Buffer::from(self.bytes[range].to_vec())
+    }
+
+    /// Returns the bytes between positions `i` and `j`, following the same rules as Lua's
+    /// `string.byte`. `j` defaults to `i`.
+    pub fn byte(&self, i: Option<i64>, j: Option<i64>) -> &[u8] {
+        let i = i.unwrap_or(1);
+        let range = self.lua_range(i, j.unwrap_or(i));
+        &self.bytes[range]
+    }
+
+    /// Converts Lua-style 1-based inclusive positions into a range of indices into this buffer.
+    pub(crate) fn lua_range(&self, i: i64, j: i64) -> Range<usize> {
+        let len = self.bytes.len() as i64;
+        let relative = |position: i64| if position < 0 { (len + position + 1).max(0) } else { position };
+
+        let start = relative(i).max(1);
+        let end = relative(j).min(len);
+        if start > end {
+            0..0
+        } else {
+            (start - 1) as usize..end as usize
+        }
+    }
+}
+
+impl From<Vec<u8>> for Buffer {
+    fn from(bytes: Vec<u8>) -> Self {
+        Buffer { bytes, position: 0 }
+    }
+}
+
+impl From<&[u8]> for Buffer {
+    fn from(bytes: &[u8]) -> Self {
+        Buffer::from(bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::{Buffer, Endian, MAX_LEN};
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn sub_should_follow_lua_string_sub_rules() {
+        let buffer = Buffer::from(b"abcdef".as_slice());
+
+        assert_eq!(b"bcd", buffer.sub(2, Some(4)).as_bytes());
+        assert_eq!(b"def", buffer.sub(-3, None).as_bytes());
+        assert_eq!(b"abcdef", buffer.sub(-100, Some(100)).as_bytes());
+        assert_eq!(b"", buffer.sub(4, Some(2)).as_bytes());
+        assert_eq!(b"a", buffer.sub(0, Some(1)).as_bytes());
+    }
+
+    #[test]
+    fn byte_should_default_to_single_position() {
+        let buffer = Buffer::from(b"abc".as_slice());
+
+        assert_eq!(b"a", buffer.byte(None, None));
+        assert_eq!(b"c", buffer.byte(Some(-1), None));
+        assert_eq!(b"bc", buffer.byte(Some(2), Some(10)));
+        assert_eq!(b"", buffer.byte(Some(10), None));
+    }
+
+    #[test]
+    fn binary_content_should_survive_round_trip() {
+        let bytes = vec![0u8, 255, 10, 13, 0];
+        let buffer = Buffer::from(bytes.clone());
+
+        assert_eq!(bytes.as_slice(), buffer.as_bytes());
+    }
+
+    #[test]
+    fn numbers_should_be_read_with_selected_endianness() {
+        let buffer = Buffer::from(vec![0x01, 0x02, 0x03, 0x04]);
+
+        assert_eq!(0x0201, buffer.read::<u16>(0, Endian::Little).unwrap());
+        assert_eq!(0x0102, buffer.read::<u16>(0, Endian::Big).unwrap());
+        assert_eq!(0x04030201, buffer.read::<u32>(0, Endian::Little).unwrap());
+        assert_eq!(0x03, buffer.read::<u8>(2, Endian::Big).unwrap());
+    }
+
+    #[test]
+    fn read_past_end_should_fail() {
+        let buffer = Buffer::from(vec![0x01, 0x02, 0x03]);
+
+        assert!(buffer.read::<u32>(0, Endian::Little).is_err());
+        assert!(buffer.read::<u8>(usize::MAX, Endian::Little).is_err());
+    }
+
+    #[test]
+    fn cursor_writes_should_grow_buffer_and_read_back() {
+        let mut buffer = Buffer::new();
+        buffer.write_next(-2i32, Endian::Big).unwrap();
+        buffer.write_next(1.5f32, Endian::Little).unwrap();
+        buffer.write_bytes_next(b"ab").unwrap();
+
+        assert_eq!(10, buffer.len());
+        assert_eq!(10, buffer.position());
+
+        buffer.seek(0).unwrap();
+        assert_eq!(-2, buffer.read_next::<i32>(Endian::Big).unwrap());
+        assert_eq!(1.5, buffer.read_next::<f32>(Endian::Little).unwrap());
+        assert_eq!(b"ab".to_vec(), buffer.read_bytes_next(2).unwrap());
+    }
+
+    #[test]
+    fn write_past_end_should_pad_with_zeroes() {
+        let mut buffer = Buffer::from(vec![0xFF]);
+        buffer.seek(3).unwrap();
+        buffer.write_next(0xAAu8, Endian::Little).unwrap();
+
+        assert_eq!(&[0xFF, 0, 0, 0xAA], buffer.as_bytes());
+    }
+
+    #[test]
+    fn writes_past_max_len_should_fail_without_growing() {
+        let mut buffer = Buffer::from(vec![0xFF]);
+
+        assert_eq!(ErrorKind::InvalidInput, buffer.seek(MAX_LEN + 1).unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, buffer.write_bytes(usize::MAX, b"ab").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, buffer.write::<u32>(MAX_LEN - 2, 1, Endian::Little).unwrap_err().kind());
+
+        buffer.seek(MAX_LEN).unwrap();
+        assert_eq!(ErrorKind::InvalidInput, buffer.write_next(0xAAu8, Endian::Little).unwrap_err().kind());
+        assert_eq!(MAX_LEN, buffer.position());
+        assert_eq!(1, buffer.len());
+    }
+
+    #[test]
+    fn find_should_return_offset_of_first_occurrence() {
+        let buffer = Buffer::from(b"RIFF....WAVEfmt WAVE".as_slice());
+
+        assert_eq!(Some(8), buffer.find(b"WAVE", 0));
+        assert_eq!(Some(16), buffer.find(b"WAVE", 9));
+        assert_eq!(None, buffer.find(b"data", 0));
+        assert_eq!(None, buffer.find(b"WAVE", 100));
+    }
+}
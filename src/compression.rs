@@ -0,0 +1,150 @@
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+use lz4_flex::frame::{FrameDecoder, FrameEncoder};
+
+use crate::error::{Error, ErrorKind};
+
+/// Limit on the size of decompressed data, used unless specified otherwise, to guard against
+/// small inputs that decompress into huge outputs.
+pub const DEFAULT_DECOMPRESSED_SIZE_LIMIT: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Raw DEFLATE stream, without any header.
+    Deflate,
+    /// DEFLATE stream with zlib header and checksum.
+    Zlib,
+    /// DEFLATE stream in gzip format, as produced by the `gzip` tool.
+    Gzip,
+    /// LZ4 frame format, as produced by the `lz4` tool.
+    Lz4,
+}
+
+impl Algorithm {
+    pub fn parse<S: AsRef<str>>(name: S) -> crate::error::Result<Algorithm> {
+        match name.as_ref() {
+            "deflate" => Ok(Algorithm::Deflate),
+            "zlib" => Ok(Algorithm::Zlib),
+            "gzip" => Ok(Algorithm::Gzip),
+            "lz4" => Ok(Algorithm::Lz4),
+            other => Err(Error::new(ErrorKind::InvalidInput, format!(
+                "Unknown compression algorithm '{}', expected one of: deflate, zlib, gzip, lz4", other
+            )))
+        }
+    }
+}
+
+/// Compresses the data with the specified algorithm.
+///
+/// `level` ranges from 0 (no compression) to 9 (best compression), and defaults to 6.
+/// LZ4 has a single compression level, so `level` is ignored for it.
+pub fn compress(data: &[u8], algorithm: Algorithm, level: Option<u32>) -> crate::error::Result<Vec<u8>> {
+    let level = match level {
+        None => Compression::default(),
+        Some(level) if level <= 9 => Compression::new(level),
+        Some(level) => return Err(Error::new(ErrorKind::InvalidInput, format!(
+            "Compression level must be between 0 and 9, but was {}", level
+        )))
+    };
+
+    match algorithm {
+        Algorithm::Deflate => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), level);
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        Algorithm::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), level);
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        Algorithm::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), level);
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        Algorithm::Lz4 => {
+            let mut encoder = FrameEncoder::new(Vec::new());
+            encoder.write_all(data)?;
+            encoder.finish().map_err(|error| Error::new(ErrorKind::Io, error))
+        }
+    }
+}
+
+/// Decompresses the data with the specified algorithm, failing if the decompressed data would
+/// be larger than `size_limit` bytes.
+pub fn decompress(data: &[u8], algorithm: Algorithm, size_limit: usize) -> crate::error::Result<Vec<u8>> {
+    let decoder: Box<dyn Read + '_> = match algorithm {
+        Algorithm::Deflate => Box::new(DeflateDecoder::new(data)),
+        Algorithm::Zlib => Box::new(ZlibDecoder::new(data)),
+        Algorithm::Gzip => Box::new(MultiGzDecoder::new(data)),
+        Algorithm::Lz4 => Box::new(FrameDecoder::new(data)),
+    };
+
+    // Read one byte past the limit to tell data that fits exactly from data that doesn't fit.
+    let mut result = Vec::new();
+    decoder.take(size_limit as u64 + 1)
+        .read_to_end(&mut result)?;
+
+    if result.len() > size_limit {
+        Err(Error::new(ErrorKind::InvalidData, format!(
+            "Decompressed data exceeds the limit of {} bytes", size_limit
+        )))
+    } else {
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compression::{Algorithm, compress, decompress};
+
+    const ALGORITHMS: [Algorithm; 4] = [Algorithm::Deflate, Algorithm::Zlib, Algorithm::Gzip, Algorithm::Lz4];
+
+    #[test]
+    fn data_should_survive_round_trip() {
+        let data = b"Lorem ipsum dolor sit amet, lorem ipsum dolor sit amet.\x00\xFF".repeat(10);
+
+        for algorithm in ALGORITHMS {
+            let compressed = compress(&data, algorithm, None).unwrap();
+            let decompressed = decompress(&compressed, algorithm, data.len()).unwrap();
+
+            assert_eq!(data, decompressed, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn repetitive_data_should_shrink() {
+        let data = vec![0u8; 10_000];
+
+        for algorithm in ALGORITHMS {
+            let compressed = compress(&data, algorithm, Some(9)).unwrap();
+
+            assert!(compressed.len() < data.len() / 10, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn decompression_past_size_limit_should_fail() {
+        let data = vec![0u8; 10_000];
+
+        for algorithm in ALGORITHMS {
+            let compressed = compress(&data, algorithm, None).unwrap();
+
+            assert!(decompress(&compressed, algorithm, 9_999).is_err(), "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn invalid_level_should_be_rejected() {
+        assert!(compress(b"qwe", Algorithm::Zlib, Some(10)).is_err());
+    }
+
+    #[test]
+    fn garbage_should_fail_to_decompress() {
+        assert!(decompress(b"definitely not compressed", Algorithm::Gzip, 1024).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+//! LuaLS annotations, see https://luals.github.io/wiki/annotations.
+use std::fmt::Write;
+
+use crate::lua::api::{ClassDoc, FieldDoc, FunctionDoc, ModuleDoc};
+
+/// Metamethods that LuaLS has an `---@operator` annotation for, along with the operator's name.
+const OPERATORS: &[(&str, &str)] = &[
+    ("__add", "add"), ("__sub", "sub"), ("__mul", "mul"), ("__div", "div"), ("__mod", "mod"),
+    ("__pow", "pow"), ("__unm", "unm"), ("__idiv", "idiv"), ("__concat", "concat"), ("__len", "len"),
+];
+
+pub fn annotations(doc: &ModuleDoc) -> String {
+    let mut out = String::new();
+    out.push_str("---@meta itb_rs\n");
+    out.push_str("-- Generated by `cargo run --bin itb_rs_docs`, do not edit.\n");
+
+    doc.walk(&mut |path, module| write_module(&mut out, path, module));
+    doc.walk(&mut |_, module| {
+        for class in &module.classes {
+            write_class(&mut out, class);
+        }
+    });
+    out
+}
+
+fn write_module(out: &mut String, path: &str, module: &ModuleDoc) {
+    out.push('\n');
+    write_doc(out, &module.doc);
+    writeln!(out, "---@class {}", path).unwrap();
+    for field in &module.fields {
+        write_field(out, field);
+    }
+    for nested in &module.modules {
+        writeln!(out, "---@field {} {}.{} {}", nested.name, path, nested.name, first_sentence(&nested.doc)).unwrap();
+    }
+    writeln!(out, "{} = {{}}", path).unwrap();
+
+    for function in &module.functions {
+        out.push('\n');
+        write_function(out, &format!("{}.{}", path, function.name), function);
+    }
+}
+
+fn write_class(out: &mut String, class: &ClassDoc) {
+    out.push('\n');
+    write_doc(out, &class.doc);
+    writeln!(out, "---@class {}", class.name).unwrap();
+    for field in &class.fields {
+        write_field(out, field);
+    }
+    for meta in &class.meta_methods {
+        let Some((_, operator)) = OPERATORS.iter().find(|(name, _)| *name == meta.name) else {
+            continue;
+        };
+        let result = meta.returns.first().map(String::as_str).unwrap_or("nil");
+        match meta.params.last() {
+            // Unary operators don't take an operand other than the instance itself
+            Some(operand) if !matches!(*operator, "len" | "unm") => {
+                writeln!(out, "---@operator {}({}): {}", operator, operand.ty, result).unwrap()
+            }
+            _ => writeln!(out, "---@operator {}: {}", operator, result).unwrap(),
+        }
+    }
+    writeln!(out, "local {} = {{}}", class.name).unwrap();
+
+    for method in &class.methods {
+        out.push('\n');
+        write_function(out, &format!("{}:{}", class.name, method.name), method);
+    }
+}
+
+fn write_function(out: &mut String, path: &str, function: &FunctionDoc) {
+    write_doc(out, &function.doc);
+    for param in &function.params {
+        writeln!(out, "---@param {} {}", param.name, param.ty).unwrap();
+    }
+    for ty in &function.returns {
+        match ty.strip_suffix("...") {
+            Some(ty) => writeln!(out, "---@return {} ...", ty).unwrap(),
+            None => writeln!(out, "---@return {}", ty).unwrap(),
+        }
+    }
+    let params: Vec<&str> = function.params.iter().map(|param| param.name.as_str()).collect();
+    writeln!(out, "function {}({}) end", path, params.join(", ")).unwrap();
+}
+
+fn write_field(out: &mut String, field: &FieldDoc) {
+    writeln!(out, "---@field {} {} {}", field.name, field.ty, field.doc).unwrap();
+}
+
+fn first_sentence(doc: &str) -> &str {
+    doc.split_inclusive(". ").next().unwrap_or(doc).trim_end()
+}
+
+fn write_doc(out: &mut String, doc: &str) {
+    for line in doc.lines() {
+        writeln!(out, "---{}", line).unwrap();
+    }
+}
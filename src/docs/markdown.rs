@@ -0,0 +1,68 @@
+//! Markdown reference of the API.
+use std::fmt::Write;
+
+use crate::lua::api::{ClassDoc, FieldDoc, FunctionDoc, ModuleDoc};
+
+pub fn reference(doc: &ModuleDoc) -> String {
+    let mut out = String::new();
+    out.push_str("# itb_rs API reference\n\n");
+    out.push_str("<!-- Generated by `cargo run --bin itb_rs_docs`, do not edit. -->\n");
+
+    doc.walk(&mut |path, module| write_module(&mut out, path, module));
+    out
+}
+
+fn write_module(out: &mut String, path: &str, module: &ModuleDoc) {
+    writeln!(out, "\n## `{}`\n\n{}", path, module.doc).unwrap();
+
+    if !module.fields.is_empty() {
+        out.push_str("\n### Fields\n\n");
+        for field in &module.fields {
+            write_field(out, &format!("{}.{}", path, field.name), field);
+        }
+    }
+
+    if !module.functions.is_empty() {
+        out.push_str("\n### Functions\n");
+        for function in &module.functions {
+            write_function(out, &format!("{}.{}", path, function.name), function);
+        }
+    }
+
+    for class in &module.classes {
+        write_class(out, class);
+    }
+}
+
+fn write_class(out: &mut String, class: &ClassDoc) {
+    writeln!(out, "\n### Class `{}`\n\n{}", class.name, class.doc).unwrap();
+
+    if !class.fields.is_empty() {
+        out.push('\n');
+        for field in &class.fields {
+            write_field(out, &format!("{}.{}", class.name, field.name), field);
+        }
+    }
+    for method in &class.methods {
+        write_function(out, &format!("{}:{}", class.name, method.name), method);
+    }
+    for meta in &class.meta_methods {
+        write_function(out, meta.name.as_str(), meta);
+    }
+}
+
+fn write_function(out: &mut String, path: &str, function: &FunctionDoc) {
+    let params: Vec<String> = function.params.iter()
+        .map(|param| format!("{}: {}", param.name, param.ty))
+        .collect();
+    let returns = if function.returns.is_empty() {
+        String::new()
+    } else {
+        format!(" -> {}", function.returns.join(", "))
+    };
+    writeln!(out, "\n#### `{}({}){}`\n\n{}", path, params.join(", "), returns, function.doc).unwrap();
+}
+
+fn write_field(out: &mut String, path: &str, field: &FieldDoc) {
+    writeln!(out, "- `{}`: `{}` — {}", path, field.ty, field.doc).unwrap();
+}
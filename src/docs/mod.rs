@@ -0,0 +1,130 @@
+//! Generator of the LuaLS annotations and the Markdown reference of the API exposed to Lua, from
+//! the descriptions recorded when it is registered.
+use std::path::{Path, PathBuf};
+
+use mlua::Lua;
+
+use crate::error::{Error, ErrorKind, WithPath};
+use crate::lua;
+use crate::lua::api::ModuleDoc;
+
+mod luals;
+mod markdown;
+
+/// Name of the LuaLS annotations file.
+pub const ANNOTATIONS_FILE: &str = "itb_rs.d.lua";
+/// Name of the Markdown reference file.
+pub const REFERENCE_FILE: &str = "api.md";
+
+pub const USAGE: &str = "\
+Usage: itb_rs_docs [options] [<directory>]
+
+Writes LuaLS annotations (itb_rs.d.lua) and a Markdown reference (api.md) of the Lua API to the
+directory (default: docs).
+
+Options:
+  --check    Fail if the files in the directory are out of date, instead of writing them
+  --help     Print this message";
+
+/// Entry point of the docs generator binary, returning its exit code.
+pub fn main() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let check = args.iter().any(|arg| arg == "--check");
+    let directories: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    let directory = match directories.as_slice() {
+        [] => PathBuf::from("docs"),
+        [directory] => PathBuf::from(directory),
+        _ => {
+            eprintln!("Too many arguments\n\n{}", USAGE);
+            return 2;
+        }
+    };
+
+    let result = if check { check_files(&directory) } else { write_files(&directory) };
+    match result {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
+        }
+    }
+}
+
+/// Describes everything exposed to Lua by the library, as built with the current features.
+pub(crate) fn describe() -> crate::error::Result<ModuleDoc> {
+    let lua = Lua::new();
+    let exports = lua::exports::module(&lua)
+        .map_err(|error| Error::new(ErrorKind::Lua, error))?;
+    Ok(exports.doc().clone())
+}
+
+/// Generates the contents of the annotations and reference files, in that order.
+pub fn generate() -> crate::error::Result<(String, String)> {
+    let doc = describe()?;
+    Ok((luals::annotations(&doc), markdown::reference(&doc)))
+}
+
+pub fn write_files(directory: &Path) -> crate::error::Result<()> {
+    let (annotations, reference) = generate()?;
+    std::fs::create_dir_all(directory).with_path(directory)?;
+    for (name, content) in [(ANNOTATIONS_FILE, annotations), (REFERENCE_FILE, reference)] {
+        let path = directory.join(name);
+        std::fs::write(&path, content).with_path(&path)?;
+    }
+    Ok(())
+}
+
+/// Fails if the files in the directory differ from freshly generated ones, ignoring line endings.
+pub fn check_files(directory: &Path) -> crate::error::Result<()> {
+    let (annotations, reference) = generate()?;
+    for (name, content) in [(ANNOTATIONS_FILE, annotations), (REFERENCE_FILE, reference)] {
+        let path = directory.join(name);
+        let existing = std::fs::read_to_string(&path).with_path(&path)?;
+        if existing.replace("\r\n", "\n") != content {
+            return Err(Error::new(ErrorKind::InvalidData, "File is out of date, regenerate it with `cargo run --bin itb_rs_docs`")
+                .with_path(&path));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::docs::{describe, generate};
+
+    #[test]
+    fn description_should_cover_classes_of_all_modules() {
+        let doc = describe().unwrap();
+
+        let mut classes = Vec::new();
+        doc.walk(&mut |_, module| classes.extend(module.classes.iter().map(|class| class.name)));
+
+        assert!(classes.contains(&"Error"));
+        assert_eq!(cfg!(feature = "io"), classes.contains(&"File"));
+        assert_eq!(cfg!(feature = "ftldat"), classes.contains(&"Package"));
+    }
+
+    #[test]
+    fn generated_files_should_mention_every_function() {
+        let (annotations, reference) = generate().unwrap();
+
+        assert!(annotations.contains("function itb_rs.error.pcall(f, ...) end"));
+        assert!(reference.contains("`itb_rs.error.pcall(f: function, ...: any) -> boolean, any...`"));
+    }
+
+    // The committed files describe the library with all modules
+    #[test]
+    #[cfg(feature = "full")]
+    fn committed_files_should_be_up_to_date() {
+        use std::path::Path;
+
+        use crate::docs::check_files;
+
+        check_files(&Path::new(env!("CARGO_MANIFEST_DIR")).join("docs")).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+use crate::error::{Error, ErrorKind};
+
+use base64::alphabet::{Alphabet, STANDARD, URL_SAFE};
+use base64::Engine;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC};
+
+/// Characters that are left as-is by percent-encoding; the unreserved characters of RFC 3986.
+const PERCENT_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base64Options {
+    /// Use the URL and filename safe alphabet (`-` and `_`) instead of the standard one (`+` and `/`).
+    pub url_safe: bool,
+    /// Pad encoded output with `=` to a multiple of 4 characters.
+    /// Decoding accepts input both with and without padding, regardless of this option.
+    pub padding: bool,
+}
+
+impl Default for Base64Options {
+    fn default() -> Self {
+        Base64Options {
+            url_safe: false,
+            padding: true,
+        }
+    }
+}
+
+impl Base64Options {
+    fn engine(&self) -> GeneralPurpose {
+        let alphabet: &Alphabet = if self.url_safe { &URL_SAFE } else { &STANDARD };
+        let config = GeneralPurposeConfig::new()
+            .with_encode_padding(self.padding)
+            .with_decode_padding_mode(DecodePaddingMode::Indifferent);
+
+        GeneralPurpose::new(alphabet, config)
+    }
+}
+
+pub fn base64_encode(data: &[u8], options: Base64Options) -> String {
+    options.engine().encode(data)
+}
+
+pub fn base64_decode(text: &[u8], options: Base64Options) -> crate::error::Result<Vec<u8>> {
+    options.engine().decode(text)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+pub fn hex_encode(data: &[u8], uppercase: bool) -> String {
+    if uppercase {
+        hex::encode_upper(data)
+    } else {
+        hex::encode(data)
+    }
+}
+
+/// Decodes hexadecimal text, accepting both lowercase and uppercase digits.
+pub fn hex_decode(text: &[u8]) -> crate::error::Result<Vec<u8>> {
+    hex::decode(text)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+/// Percent-encodes all bytes except ASCII letters, digits, and `-._~`.
+pub fn percent_encode(data: &[u8]) -> String {
+    percent_encoding::percent_encode(data, PERCENT_ENCODE_SET).to_string()
+}
+
+/// Decodes percent-encoded text. Malformed escape sequences are left as-is.
+pub fn percent_decode(text: &[u8]) -> Vec<u8> {
+    percent_encoding::percent_decode(text).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::{Base64Options, base64_decode, base64_encode, hex_decode, hex_encode, percent_decode, percent_encode};
+
+    #[test]
+    fn base64_should_support_all_variants() {
+        let data = [0xFBu8, 0xFF, 0xBF, 0x01];
+
+        let standard = Base64Options::default();
+        let standard_no_pad = Base64Options { url_safe: false, padding: false };
+        let url_safe = Base64Options { url_safe: true, padding: true };
+        let url_safe_no_pad = Base64Options { url_safe: true, padding: false };
+
+        assert_eq!("+/+/AQ==", base64_encode(&data, standard));
+        assert_eq!("+/+/AQ", base64_encode(&data, standard_no_pad));
+        assert_eq!("-_-_AQ==", base64_encode(&data, url_safe));
+        assert_eq!("-_-_AQ", base64_encode(&data, url_safe_no_pad));
+    }
+
+    #[test]
+    fn base64_decode_should_accept_missing_padding() {
+        let options = Base64Options::default();
+
+        assert_eq!(b"qw".to_vec(), base64_decode(b"cXc=", options).unwrap());
+        assert_eq!(b"qw".to_vec(), base64_decode(b"cXc", options).unwrap());
+    }
+
+    #[test]
+    fn base64_decode_should_reject_wrong_alphabet() {
+        assert!(base64_decode(b"-_-_AQ==", Base64Options::default()).is_err());
+    }
+
+    #[test]
+    fn hex_should_round_trip_binary_data() {
+        let data = [0x00u8, 0xAB, 0xFF];
+
+        assert_eq!("00abff", hex_encode(&data, false));
+        assert_eq!("00ABFF", hex_encode(&data, true));
+        assert_eq!(data.to_vec(), hex_decode(b"00AbfF").unwrap());
+        assert!(hex_decode(b"abc").is_err());
+    }
+
+    #[test]
+    fn percent_encoding_should_keep_unreserved_characters() {
+        assert_eq!("a-b_c.d~e%20f%2Fg%00", percent_encode(b"a-b_c.d~e f/g\x00"));
+        assert_eq!(b"a-b_c.d~e f/g\x00".to_vec(), percent_decode(b"a-b_c.d~e%20f%2Fg%00"));
+    }
+}
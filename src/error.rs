@@ -0,0 +1,288 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "ftldat")]
+use ftldat::error::{InnerPathAlreadyExistsError, PackageReadError, PackageWriteError};
+
+/// Category of an [Error], so that Lua code can tell failures apart without matching on
+/// messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// File, directory, or package entry doesn't exist.
+    NotFound,
+    /// Path lies outside of the directories Lua is allowed to access.
+    NotAllowed,
+    AlreadyExists,
+    /// Argument that couldn't be understood, eg. an unknown format name or a malformed pattern.
+    InvalidInput,
+    /// Content that couldn't be understood, eg. a corrupt package or undecodable text.
+    InvalidData,
+    /// Operation was aborted by a progress callback, or its task was cancelled.
+    Aborted,
+    /// Object was used after it had been closed, consumed, or destroyed.
+    Closed,
+    /// Library doesn't provide what was asked of it, eg. a version it doesn't satisfy.
+    Unsupported,
+    /// Error raised by Lua code, or by the Lua runtime itself.
+    Lua,
+    /// Any other failure reported by the operating system.
+    Io,
+}
+
+impl ErrorKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "NotFound",
+            ErrorKind::NotAllowed => "NotAllowed",
+            ErrorKind::AlreadyExists => "AlreadyExists",
+            ErrorKind::InvalidInput => "InvalidInput",
+            ErrorKind::InvalidData => "InvalidData",
+            ErrorKind::Aborted => "Aborted",
+            ErrorKind::Closed => "Closed",
+            ErrorKind::Unsupported => "Unsupported",
+            ErrorKind::Lua => "Lua",
+            ErrorKind::Io => "Io",
+        }
+    }
+}
+
+impl From<std::io::ErrorKind> for ErrorKind {
+    fn from(kind: std::io::ErrorKind) -> Self {
+        match kind {
+            std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+            std::io::ErrorKind::PermissionDenied => ErrorKind::NotAllowed,
+            std::io::ErrorKind::AlreadyExists => ErrorKind::AlreadyExists,
+            std::io::ErrorKind::InvalidInput => ErrorKind::InvalidInput,
+            std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof => ErrorKind::InvalidData,
+            std::io::ErrorKind::Interrupted => ErrorKind::Aborted,
+            std::io::ErrorKind::Unsupported => ErrorKind::Unsupported,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+/// Error type shared by the whole crate.
+///
+/// Causes are kept as messages rather than error values, since some errors from dependencies
+/// are neither [Send] nor [Sync], which Lua requires of errors passed through it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+    path: Option<PathBuf>,
+    /// Messages of the errors that led to this one, starting with the most immediate.
+    causes: Vec<String>,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn new<M: ToString>(kind: ErrorKind, message: M) -> Error {
+        Error {
+            kind,
+            message: message.to_string(),
+            path: None,
+            causes: Vec::new(),
+        }
+    }
+
+    /// Attaches the path the error concerns, unless one is attached already.
+    pub fn with_path<P: AsRef<Path>>(mut self, path: P) -> Error {
+        if self.path.is_none() {
+            self.path = Some(path.as_ref().to_path_buf());
+        }
+        self
+    }
+
+    /// Records `cause`, and whatever caused it in turn, as the reason for this error.
+    pub fn caused_by(mut self, cause: &dyn std::error::Error) -> Error {
+        self.causes.push(cause.to_string());
+        self.causes.extend(source_messages(cause));
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn causes(&self) -> &[String] {
+        &self.causes
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(path) = &self.path {
+            write!(f, " ({})", path.display())?;
+        }
+        for cause in &self.causes {
+            write!(f, ": {}", cause)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn source_messages(error: &dyn std::error::Error) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut maybe_source = error.source();
+    while let Some(source) = maybe_source {
+        result.push(source.to_string());
+        maybe_source = source.source();
+    }
+    result
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        // Errors of this crate can end up wrapped in io::Errors by code in between, eg. readers
+        if let Some(inner) = error.get_ref().and_then(|inner| inner.downcast_ref::<Error>()) {
+            return inner.clone();
+        }
+
+        Error {
+            kind: ErrorKind::from(error.kind()),
+            message: error.to_string(),
+            path: None,
+            causes: source_messages(&error),
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+impl From<walkdir::Error> for Error {
+    fn from(error: walkdir::Error) -> Self {
+        let kind = error.io_error()
+            .map(|io_error| ErrorKind::from(io_error.kind()))
+            .unwrap_or(ErrorKind::Io);
+        let result = Error::new(kind, &error);
+        match error.path() {
+            None => result,
+            Some(path) => result.with_path(path)
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+impl From<notify::Error> for Error {
+    fn from(error: notify::Error) -> Self {
+        let kind = match &error.kind {
+            notify::ErrorKind::Io(io_error) => ErrorKind::from(io_error.kind()),
+            notify::ErrorKind::PathNotFound | notify::ErrorKind::WatchNotFound => ErrorKind::NotFound,
+            _ => ErrorKind::Io,
+        };
+        let result = Error::new(kind, &error);
+        match error.paths.first() {
+            None => result,
+            Some(path) => result.with_path(path)
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+impl From<glob::PatternError> for Error {
+    fn from(error: glob::PatternError) -> Self {
+        Error::new(ErrorKind::InvalidInput, error)
+    }
+}
+
+#[cfg(feature = "semver")]
+impl From<semver::Error> for Error {
+    fn from(error: semver::Error) -> Self {
+        Error::new(ErrorKind::InvalidInput, error)
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(error: std::string::FromUtf8Error) -> Self {
+        Error::new(ErrorKind::InvalidData, error)
+    }
+}
+
+#[cfg(feature = "ftldat")]
+impl From<PackageReadError> for Error {
+    fn from(error: PackageReadError) -> Self {
+        Error::new(ErrorKind::InvalidData, "Failed to read package")
+            .caused_by(&error)
+    }
+}
+
+#[cfg(feature = "ftldat")]
+impl From<PackageWriteError> for Error {
+    fn from(error: PackageWriteError) -> Self {
+        Error::new(ErrorKind::Io, "Failed to write package")
+            .caused_by(&error)
+    }
+}
+
+#[cfg(feature = "ftldat")]
+impl From<InnerPathAlreadyExistsError> for Error {
+    fn from(error: InnerPathAlreadyExistsError) -> Self {
+        Error::new(ErrorKind::AlreadyExists, error)
+    }
+}
+
+/// Shorthand for attaching a path to the error of a failed operation.
+// Only the modules working with files need it.
+#[cfg_attr(not(any(feature = "io", feature = "vendored")), allow(dead_code))]
+pub trait WithPath<T> {
+    fn with_path<P: AsRef<Path>>(self, path: P) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> WithPath<T> for std::result::Result<T, E> {
+    fn with_path<P: AsRef<Path>>(self, path: P) -> Result<T> {
+        self.map_err(|error| error.into().with_path(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::error::{Error, ErrorKind, WithPath};
+
+    #[test]
+    fn io_errors_should_map_to_matching_kinds() {
+        let not_found = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+
+        assert_eq!(ErrorKind::NotFound, Error::from(not_found).kind());
+        assert_eq!(ErrorKind::NotAllowed, Error::from(denied).kind());
+    }
+
+    #[test]
+    fn crate_errors_should_survive_wrapping_in_io_errors() {
+        let error = Error::new(ErrorKind::Closed, "closed").with_path("a.txt");
+        let wrapped = std::io::Error::other(error.clone());
+
+        assert_eq!(error, Error::from(wrapped));
+    }
+
+    #[test]
+    fn first_attached_path_should_be_kept() {
+        let result: Result<(), std::io::Error> = Err(std::io::Error::other("failed"));
+        let error = result.with_path("inner.txt").unwrap_err().with_path("outer.txt");
+
+        assert_eq!(Some(Path::new("inner.txt")), error.path());
+    }
+
+    #[test]
+    fn display_should_include_path_and_causes() {
+        let cause = std::io::Error::other("disk full");
+        let error = Error::new(ErrorKind::Io, "Failed to write package")
+            .with_path("resource.dat")
+            .caused_by(&cause);
+
+        assert_eq!("Failed to write package (resource.dat): disk full", error.to_string());
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+
+use crate::error::WithPath;
+use crate::io::util::normalize;
+
+pub const AUDIT_LOG_FILE_NAME: &str = "itb_rs_audit.log";
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+pub const DEFAULT_MAX_FILES: usize = 3;
+/// Number of entries kept in memory for [recent].
+const RECENT_CAPACITY: usize = 256;
+
+lazy_static! {
+    static ref AUDIT: Mutex<Audit> = Mutex::new(Audit::default());
+}
+
+#[cfg(test)]
+lazy_static! {
+    /// Held by tests that enable auditing, since it's shared by the whole process.
+    pub(crate) static ref TEST_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOperation {
+    Write,
+    Delete,
+    Move,
+    Copy,
+}
+
+impl AuditOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuditOperation::Write => "write",
+            AuditOperation::Delete => "delete",
+            AuditOperation::Move => "move",
+            AuditOperation::Copy => "copy",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub operation: AuditOperation,
+    pub path: String,
+    /// Destination of moves and copies.
+    pub destination: Option<String>,
+    /// Mod that was set as the current context when the operation happened.
+    pub mod_id: Option<String>,
+}
+
+impl AuditEntry {
+    /// Formats the entry as a single line of the log file.
+    fn to_line(&self) -> String {
+        let mut line = format!(
+            "{}\t{}\t{}\t{}",
+            format_timestamp(self.timestamp),
+            self.operation.name(),
+            self.mod_id.as_deref().unwrap_or("-"),
+            self.path
+        );
+        if let Some(destination) = &self.destination {
+            line.push('\t');
+            line.push_str(destination);
+        }
+        line
+    }
+}
+
+/// Log of file system changes, written to a file that is rotated once it grows too large.
+pub struct AuditLog {
+    path: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    recent: VecDeque<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Creates a log writing to the specified file. Once the file exceeds `max_file_size`, it is
+    /// renamed to `<name>.1`, older files shifting up to `<name>.<max_files - 1>`.
+    pub fn new<P: AsRef<Path>>(path: P, max_file_size: u64, max_files: usize) -> AuditLog {
+        AuditLog {
+            path: path.as_ref().to_path_buf(),
+            max_file_size,
+            max_files: max_files.max(1),
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, entry: AuditEntry) -> crate::error::Result<()> {
+        let size = std::fs::metadata(&self.path).map(|metadata| metadata.len()).unwrap_or(0);
+        if size >= self.max_file_size {
+            self.rotate()?;
+        }
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)
+            .with_path(&self.path)?;
+        writeln!(file, "{}", entry.to_line()).with_path(&self.path)?;
+
+        if self.recent.len() == RECENT_CAPACITY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(entry);
+        Ok(())
+    }
+
+    /// Returns up to `count` of the most recently recorded entries, oldest first.
+    pub fn recent(&self, count: usize) -> Vec<AuditEntry> {
+        self.recent.iter()
+            .skip(self.recent.len().saturating_sub(count))
+            .cloned()
+            .collect()
+    }
+
+    fn rotate(&self) -> crate::error::Result<()> {
+        let rotated_path = |index: usize| PathBuf::from(format!("{}.{}", self.path.display(), index));
+
+        if self.max_files == 1 {
+            return std::fs::remove_file(&self.path).with_path(&self.path);
+        }
+
+        let oldest = rotated_path(self.max_files - 1);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest).with_path(&oldest)?;
+        }
+        for index in (1..self.max_files - 1).rev() {
+            let source = rotated_path(index);
+            if source.exists() {
+                std::fs::rename(&source, rotated_path(index + 1)).with_path(&source)?;
+            }
+        }
+        std::fs::rename(&self.path, rotated_path(1)).with_path(&self.path)
+    }
+}
+
+#[derive(Default)]
+struct Audit {
+    /// Present while auditing is enabled.
+    log: Option<AuditLog>,
+    mod_id: Option<String>,
+}
+
+/// Starts recording file system changes to [AUDIT_LOG_FILE_NAME] in the given save data directory.
+pub fn enable(save_data_directory: &Path, max_file_size: u64, max_files: usize) -> PathBuf {
+    let path = save_data_directory.join(AUDIT_LOG_FILE_NAME);
+    let mut audit = AUDIT.lock().unwrap();
+    audit.log = Some(AuditLog::new(&path, max_file_size, max_files));
+    path
+}
+
+pub fn disable() {
+    AUDIT.lock().unwrap().log = None;
+}
+
+pub fn is_enabled() -> bool {
+    AUDIT.lock().unwrap().log.is_some()
+}
+
+/// Sets the mod that subsequent operations are attributed to.
+pub fn set_mod(mod_id: Option<String>) {
+    AUDIT.lock().unwrap().mod_id = mod_id;
+}
+
+pub fn current_mod() -> Option<String> {
+    AUDIT.lock().unwrap().mod_id.clone()
+}
+
+pub fn recent(count: usize) -> Vec<AuditEntry> {
+    match &AUDIT.lock().unwrap().log {
+        None => Vec::new(),
+        Some(log) => log.recent(count)
+    }
+}
+
+/// Records a successful operation, if auditing is enabled. Failing to write the log never fails
+/// the operation itself.
+pub(crate) fn record<P: AsRef<Path>>(operation: AuditOperation, path: P, destination: Option<&Path>) {
+    let mut audit = AUDIT.lock().unwrap();
+    let mod_id = audit.mod_id.clone();
+    if let Some(log) = &mut audit.log {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        let _ = log.record(AuditEntry {
+            timestamp,
+            operation,
+            path: normalize(path),
+            destination: destination.map(normalize),
+            mod_id,
+        });
+    }
+}
+
+/// Formats seconds since the Unix epoch as an ISO 8601 date and time in UTC.
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds_of_day = timestamp % 86400;
+
+    // Converts days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day,
+        seconds_of_day / 3600, seconds_of_day / 60 % 60, seconds_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::audit::{AuditEntry, AuditLog, AuditOperation, format_timestamp};
+
+    fn entry(path: &str) -> AuditEntry {
+        AuditEntry {
+            timestamp: 0,
+            operation: AuditOperation::Delete,
+            path: path.to_string(),
+            destination: None,
+            mod_id: Some("my_mod".to_string()),
+        }
+    }
+
+    #[test]
+    fn timestamps_should_be_formatted_as_utc() {
+        assert_eq!("1970-01-01T00:00:00Z", format_timestamp(0));
+        assert_eq!("2000-02-29T12:34:56Z", format_timestamp(951_827_696));
+    }
+
+    #[test]
+    fn entries_should_be_appended_as_lines() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let mut log = AuditLog::new(tmp_dir.path().join("audit.log"), 1024, 2);
+
+        log.record(entry("a.txt")).unwrap();
+        log.record(AuditEntry { destination: Some("c.txt".to_string()), operation: AuditOperation::Move, ..entry("b.txt") }).unwrap();
+
+        let content = std::fs::read_to_string(tmp_dir.path().join("audit.log")).unwrap();
+        assert_eq!(
+            "1970-01-01T00:00:00Z\tdelete\tmy_mod\ta.txt\n1970-01-01T00:00:00Z\tmove\tmy_mod\tb.txt\tc.txt\n",
+            content
+        );
+    }
+
+    #[test]
+    fn log_should_rotate_when_exceeding_max_size() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let mut log = AuditLog::new(tmp_dir.path().join("audit.log"), 10, 3);
+
+        for path in ["1", "2", "3", "4"] {
+            log.record(entry(path)).unwrap();
+        }
+
+        assert!(std::fs::read_to_string(tmp_dir.path().join("audit.log")).unwrap().ends_with("\t4\n"));
+        assert!(std::fs::read_to_string(tmp_dir.path().join("audit.log.1")).unwrap().ends_with("\t3\n"));
+        assert!(std::fs::read_to_string(tmp_dir.path().join("audit.log.2")).unwrap().ends_with("\t2\n"));
+        assert!(!tmp_dir.path().join("audit.log.3").exists());
+    }
+
+    #[test]
+    fn recent_should_return_newest_entries_oldest_first() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let mut log = AuditLog::new(tmp_dir.path().join("audit.log"), 1024, 1);
+        for path in ["1", "2", "3"] {
+            log.record(entry(path)).unwrap();
+        }
+
+        let recent: Vec<String> = log.recent(2).into_iter().map(|entry| entry.path).collect();
+
+        assert_eq!(vec!["2".to_string(), "3".to_string()], recent);
+    }
+
+    #[test]
+    #[cfg(feature = "ftldat")]
+    fn file_system_changes_should_be_recorded_while_enabled() {
+        use ftldat::{Package, PackageEntry};
+
+        use crate::io::{Directory, File, Sandbox};
+        use crate::io::audit;
+        use crate::package::extract_all;
+
+        let _guard = audit::TEST_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let sandbox = Sandbox::new(tmp_dir.path(), tmp_dir.path().join("save")).unwrap();
+        let mut package = Package::new();
+        package.put_entry(PackageEntry::from_string("extracted.txt", "content"));
+
+        audit::enable(tmp_dir.path(), 1024 * 1024, 1);
+        File::from(tmp_dir.path().join("written.txt")).write_string("content").unwrap();
+        File::from(tmp_dir.path().join("written.txt")).delete().unwrap();
+        extract_all(&sandbox, &package, &Directory::from(tmp_dir.path().join("out")), &[], &mut |_, _, _| true).unwrap();
+        Directory::from(tmp_dir.path().join("out")).delete().unwrap();
+        // Other tests may be changing files at the same time
+        let recorded: Vec<(AuditOperation, String)> = audit::recent(256).into_iter()
+            .filter(|entry| ["written.txt", "extracted.txt", "out"].iter().any(|name| entry.path.ends_with(name)))
+            .map(|entry| (entry.operation, entry.path.rsplit('/').next().unwrap().to_string()))
+            .collect();
+        audit::disable();
+
+        assert_eq!(vec![
+            (AuditOperation::Write, "written.txt".to_string()),
+            (AuditOperation::Delete, "written.txt".to_string()),
+            (AuditOperation::Write, "extracted.txt".to_string()),
+            (AuditOperation::Delete, "out".to_string()),
+        ], recorded);
+    }
+}
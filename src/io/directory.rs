@@ -1,17 +1,20 @@
-use std::io::{Error, ErrorKind};
 use std::path::{Path, PathBuf};
 
 use walkdir::WalkDir;
 
+use crate::error::{Error, ErrorKind, WithPath};
+use crate::io::audit;
+use crate::io::audit::AuditOperation;
 use crate::io::file::File;
 use crate::io::has_path::HasPath;
 use crate::io::has_relative_path::HasRelativePath;
 use crate::io::has_root::HasRoot;
 use crate::io::HasParent;
-use crate::io::path_filter::PathFilter;
+use crate::io::sandbox::Sandbox;
 use crate::io::util::normalize;
+use crate::progress::{aborted, Progress};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Directory {
     pub path: PathBuf,
 }
@@ -23,19 +26,17 @@ impl Directory {
 
     pub fn relativize<P: AsRef<Path>>(&self, path: P) -> Option<String> {
         let is_dir = path.as_ref().is_dir();
-        let normalized_path_relative_to_self = pathdiff::diff_paths(path, &self.path)
+        pathdiff::diff_paths(path, &self.path)
             .map(|path| {
                 if is_dir {
                     normalize(&path) + "/"
                 } else {
                     normalize(&path)
                 }
-            });
-
-        normalized_path_relative_to_self
+            })
     }
 
-    pub fn files(&self) -> std::io::Result<Vec<File>> {
+    pub fn files(&self) -> crate::error::Result<Vec<File>> {
         if self.exists() {
             let mut result = Vec::new();
 
@@ -53,11 +54,11 @@ impl Directory {
 
             Ok(result)
         } else {
-            Err(Error::new(ErrorKind::Other, "Directory doesn't exist"))
+            Err(Error::new(ErrorKind::NotFound, "Directory doesn't exist").with_path(&self.path))
         }
     }
 
-    pub fn directories(&self) -> std::io::Result<Vec<Directory>> {
+    pub fn directories(&self) -> crate::error::Result<Vec<Directory>> {
         if self.exists() {
             let mut result = Vec::new();
 
@@ -75,15 +76,40 @@ impl Directory {
 
             Ok(result)
         } else {
-            Err(Error::new(ErrorKind::Other, "Directory doesn't exist"))
+            Err(Error::new(ErrorKind::NotFound, "Directory doesn't exist").with_path(&self.path))
+        }
+    }
+
+    /// Returns all files within this directory and its subdirectories, ordered by path. Only
+    /// needed to add directories to packages.
+    #[cfg(feature = "ftldat")]
+    pub fn files_recursive(&self) -> crate::error::Result<Vec<File>> {
+        if self.exists() {
+            let mut result = Vec::new();
+
+            for entry in WalkDir::new(&self.path)
+                .min_depth(1)
+                .follow_links(true)
+                .sort_by_file_name()
+                .into_iter()
+            {
+                let entry = entry?;
+                if entry.file_type().is_file() {
+                    result.push(File::from(entry.path()));
+                }
+            }
+
+            Ok(result)
+        } else {
+            Err(Error::new(ErrorKind::NotFound, "Directory doesn't exist").with_path(&self.path))
         }
     }
 
-    pub fn make_directories(&self) -> std::io::Result<()> {
-        if PathFilter::is_whitelisted(&self.path)? {
-            std::fs::create_dir_all(&self.path)
+    pub fn make_directories(&self, sandbox: &Sandbox) -> crate::error::Result<()> {
+        if sandbox.is_whitelisted(&self.path)? {
+            std::fs::create_dir_all(&self.path).with_path(&self.path)
         } else {
-            Err(Error::new(ErrorKind::Other, "Path does not point to an allowed directory"))
+            Err(Error::new(ErrorKind::NotAllowed, "Path does not point to an allowed directory").with_path(&self.path))
         }
     }
 
@@ -91,22 +117,54 @@ impl Directory {
         self.path.exists()
     }
 
-    pub fn is_ancestor<P: AsRef<Path>>(&self, path: P) -> std::io::Result<bool> {
+    pub fn is_ancestor<P: AsRef<Path>>(&self, path: P) -> crate::error::Result<bool> {
         let path = path.as_ref();
         if path.is_absolute() {
             Ok(path.starts_with(&self.path))
         } else {
-            Err(Error::new(ErrorKind::Other, "Not an absolute path"))
+            Err(Error::new(ErrorKind::InvalidInput, "Not an absolute path").with_path(path))
         }
     }
 
-    pub fn delete(&self) -> std::io::Result<()> {
+    pub fn delete(&self) -> crate::error::Result<()> {
         if self.exists() {
-            std::fs::remove_dir_all(&self.path)
+            std::fs::remove_dir_all(&self.path).with_path(&self.path)?;
+            audit::record(AuditOperation::Delete, &self.path, None);
+            Ok(())
         } else {
             Ok(())
         }
     }
+
+    /// Deletes this directory one entry at a time, reporting each file or directory before it is
+    /// removed. If aborted, entries removed up to that point stay removed. Each entry is recorded
+    /// in the audit log as it's removed, so that an aborted or failed delete is recorded as well.
+    pub fn delete_with_progress(&self, progress: &mut Progress) -> crate::error::Result<()> {
+        if !self.exists() {
+            return Ok(());
+        }
+
+        let entries = WalkDir::new(&self.path)
+            .contents_first(true)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (done, entry) in entries.iter().enumerate() {
+            if !progress(done, entries.len(), &normalize(entry.path())) {
+                return Err(aborted());
+            }
+
+            if entry.file_type().is_dir() {
+                std::fs::remove_dir(entry.path()).with_path(entry.path())?;
+            } else {
+                std::fs::remove_file(entry.path()).with_path(entry.path())?;
+            }
+            audit::record(AuditOperation::Delete, entry.path(), None);
+        }
+
+        progress(entries.len(), entries.len(), "");
+        Ok(())
+    }
 }
 
 impl HasPath for Directory {
@@ -135,11 +193,18 @@ impl<P: AsRef<Path>> From<P> for Directory where PathBuf: From<P> {
 mod tests {
     use std::path::PathBuf;
 
+    use crate::io::audit;
+    use crate::io::audit::AuditOperation;
     use crate::io::directory::Directory;
     use crate::io::has_parent::HasParent;
     use crate::io::has_path::HasPath;
     use crate::io::has_relative_path::HasRelativePath;
-    use crate::io::path_filter::PathFilter;
+    use crate::io::sandbox::Sandbox;
+    use crate::io::util::normalize;
+
+    fn sandbox(tmp_dir: &tempfile::TempDir) -> Sandbox {
+        Sandbox::new(tmp_dir.path(), tmp_dir.path().join("save")).unwrap()
+    }
 
     #[test]
     fn path_should_be_reported_with_trailing_slash() {
@@ -152,15 +217,17 @@ mod tests {
     #[test]
     fn relative_path_should_be_reported_with_trailing_slash() {
         let tmp_dir = tempfile::TempDir::new().unwrap();
-        let dir = Directory::from(tmp_dir.path());
+        std::fs::create_dir(tmp_dir.path().join("mods")).unwrap();
+        let dir = Directory::from(tmp_dir.path().join("mods"));
 
-        assert!(dir.relative_path().unwrap().ends_with("/"));
+        assert_eq!("mods/", dir.relative_path(&sandbox(&tmp_dir)));
     }
 
     #[test]
     fn relativize_should_return_none_for_path_in_different_root() {
         let dir = Directory::from("test");
-        let separate_dir = Directory::from(PathFilter::save_data_directory().unwrap());
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let separate_dir = Directory::from(tmp_dir.path());
 
         let result = separate_dir.relativize(dir.path);
 
@@ -169,8 +236,9 @@ mod tests {
 
     #[test]
     fn relativize_should_remove_common_path() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
         let dir = Directory::from("some/path/test");
-        let parent_dir = dir.parent().unwrap();
+        let parent_dir = dir.parent(&sandbox(&tmp_dir)).unwrap();
 
         let result = parent_dir.relativize(dir.path());
 
@@ -204,9 +272,27 @@ mod tests {
         assert!(relative_path.ends_with("/"));
     }
 
+    #[test]
+    #[cfg(feature = "ftldat")]
+    fn files_recursive_should_include_files_in_subdirectories() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp_dir.path().join("sub/deeper")).unwrap();
+        std::fs::write(tmp_dir.path().join("b.txt"), "").unwrap();
+        std::fs::write(tmp_dir.path().join("sub/deeper/a.txt"), "").unwrap();
+        let dir = Directory::from(tmp_dir.path());
+
+        let files = dir.files_recursive().unwrap();
+
+        let relative_paths: Vec<String> = files.iter()
+            .map(|file| dir.relativize(&file.path).unwrap())
+            .collect();
+        assert_eq!(vec!["b.txt", "sub/deeper/a.txt"], relative_paths);
+    }
+
     #[test]
     fn is_ancestor_should_return_true_for_child_absolute_path() {
-        let dir = Directory::from(PathFilter::game_directory().unwrap().join("some/path"));
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Directory::from(tmp_dir.path().join("some/path"));
         let test_path = dir.path.join("test");
         let result = dir.is_ancestor(test_path);
 
@@ -216,7 +302,8 @@ mod tests {
 
     #[test]
     fn is_ancestor_should_return_false_for_non_child_absolute_path() {
-        let dir = Directory::from(PathFilter::game_directory().unwrap().join("some/path"));
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Directory::from(tmp_dir.path().join("some/path"));
         let test_path = dir.path.parent().unwrap().join("test");
 
         let result = dir.is_ancestor(test_path);
@@ -227,11 +314,59 @@ mod tests {
 
     #[test]
     fn is_ancestor_should_return_error_for_relative_path() {
-        let dir = Directory::from(PathFilter::game_directory().unwrap().join("some/path"));
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Directory::from(tmp_dir.path().join("some/path"));
         let test_path = PathBuf::from("test");
 
         let result = dir.is_ancestor(test_path);
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn delete_with_progress_should_remove_contents_before_directory() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Directory::from(tmp_dir.path().join("mod"));
+        std::fs::create_dir_all(dir.path.join("scripts")).unwrap();
+        std::fs::write(dir.path.join("scripts/init.lua"), "").unwrap();
+
+        let mut reports = Vec::new();
+        dir.delete_with_progress(&mut |done, total, path| {
+            reports.push((done, total, path.to_string()));
+            true
+        }).unwrap();
+
+        assert!(!dir.exists());
+        assert_eq!(4, reports.len());
+        assert!(reports[0].2.ends_with("scripts/init.lua"));
+        assert_eq!((3, 3, String::new()), reports[3]);
+    }
+
+    #[test]
+    fn delete_with_progress_should_stop_when_aborted() {
+        let _guard = audit::TEST_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Directory::from(tmp_dir.path().join("mod"));
+        std::fs::create_dir_all(&dir.path).unwrap();
+        std::fs::write(dir.path.join("a.txt"), "").unwrap();
+        std::fs::write(dir.path.join("b.txt"), "").unwrap();
+
+        audit::enable(tmp_dir.path(), 1024 * 1024, 1);
+        let mut reported = Vec::new();
+        let result = dir.delete_with_progress(&mut |done, _, path| {
+            reported.push(path.to_string());
+            done < 1
+        });
+        // Other tests may be changing files at the same time
+        let recorded: Vec<(AuditOperation, String)> = audit::recent(256).into_iter()
+            .filter(|entry| entry.path.starts_with(&normalize(&dir.path)))
+            .map(|entry| (entry.operation, entry.path))
+            .collect();
+        audit::disable();
+
+        assert!(result.is_err());
+        assert!(dir.exists());
+        assert_eq!(1, std::fs::read_dir(&dir.path).unwrap().count());
+        assert_eq!(vec![(AuditOperation::Delete, reported[0].clone())], recorded);
+    }
 }
@@ -1,12 +1,15 @@
 use std::fs::OpenOptions;
-use std::io::{Error, ErrorKind, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use crate::io::has_parent::HasParent;
 use crate::io::has_path::HasPath;
 use crate::io::has_relative_path::HasRelativePath;
 use crate::io::has_root::HasRoot;
-use crate::io::path_filter::PathFilter;
+use crate::io::sandbox::Sandbox;
+use crate::error::{Error, ErrorKind, WithPath};
+use crate::io::audit;
+use crate::io::audit::AuditOperation;
 use crate::io::util::normalize;
 
 #[derive(Debug)]
@@ -28,34 +31,36 @@ impl File {
             .map(|s| s.to_str().unwrap().to_string())
     }
 
-    pub fn read_to_byte_array(&self) -> std::io::Result<Vec<u8>> {
+    pub fn read_to_byte_array(&self) -> crate::error::Result<Vec<u8>> {
         if self.exists() {
-            std::fs::read(&self.path)
+            std::fs::read(&self.path).with_path(&self.path)
         } else {
-            Err(Error::new(ErrorKind::Other, "File doesn't exist"))
+            Err(Error::new(ErrorKind::NotFound, "File doesn't exist").with_path(&self.path))
         }
     }
 
-    pub fn read_to_string(&self) -> std::io::Result<String> {
+    pub fn read_to_string(&self) -> crate::error::Result<String> {
         if self.exists() {
-            std::fs::read_to_string(&self.path)
+            std::fs::read_to_string(&self.path).with_path(&self.path)
         } else {
-            Err(Error::new(ErrorKind::Other, "File doesn't exist"))
+            Err(Error::new(ErrorKind::NotFound, "File doesn't exist").with_path(&self.path))
         }
     }
 
-    pub fn write_string<S: AsRef<str> + AsRef<[u8]>>(&self, content: S) -> std::io::Result<()> {
+    pub fn write_string<S: AsRef<str> + AsRef<[u8]>>(&self, content: S) -> crate::error::Result<()> {
         let maybe_parent = &self.path.parent();
         if let Some(parent) = maybe_parent {
-            std::fs::create_dir_all(parent)?;
+            std::fs::create_dir_all(parent).with_path(parent)?;
         }
-        std::fs::write(&self.path, content)
+        std::fs::write(&self.path, content).with_path(&self.path)?;
+        audit::record(AuditOperation::Write, &self.path, None);
+        Ok(())
     }
 
-    pub fn append_string<S: AsRef<str>>(&self, content: S) -> std::io::Result<()> {
+    pub fn append_string<S: AsRef<str>>(&self, content: S) -> crate::error::Result<()> {
         let maybe_parent = &self.path.parent();
         if let Some(parent) = maybe_parent {
-            std::fs::create_dir_all(parent)?;
+            std::fs::create_dir_all(parent).with_path(parent)?;
         }
         let mut file = OpenOptions::new()
             .append(true)
@@ -64,38 +69,46 @@ impl File {
             .unwrap();
 
         file.write(content.as_ref().as_bytes())
-            .map(|_| ())
+            .with_path(&self.path)?;
+        audit::record(AuditOperation::Write, &self.path, None);
+        Ok(())
     }
 
-    pub fn write_byte_array(&self, content: Vec<u8>) -> std::io::Result<()> {
+    pub fn write_byte_array(&self, content: Vec<u8>) -> crate::error::Result<()> {
         let maybe_parent = &self.path.parent();
         if let Some(parent) = maybe_parent {
-            std::fs::create_dir_all(parent)?;
+            std::fs::create_dir_all(parent).with_path(parent)?;
         }
-        std::fs::write(&self.path, content)
+        std::fs::write(&self.path, content).with_path(&self.path)?;
+        audit::record(AuditOperation::Write, &self.path, None);
+        Ok(())
     }
 
-    pub fn copy<P: AsRef<Path>>(&self, destination: &P) -> std::io::Result<()> {
-        if PathFilter::is_whitelisted(destination)? {
+    pub fn copy<P: AsRef<Path>>(&self, sandbox: &Sandbox, destination: &P) -> crate::error::Result<()> {
+        if sandbox.is_whitelisted(destination)? {
             let maybe_dest_parent = destination.as_ref().parent();
             if let Some(dest_parent) = maybe_dest_parent {
-                std::fs::create_dir_all(dest_parent)?;
+                std::fs::create_dir_all(dest_parent).with_path(dest_parent)?;
             }
-            std::fs::copy(&self.path, destination).map(|_| ())
+            std::fs::copy(&self.path, destination).with_path(&self.path)?;
+            audit::record(AuditOperation::Copy, &self.path, Some(destination.as_ref()));
+            Ok(())
         } else {
-            Err(Error::new(ErrorKind::Other, "Destination is not within allowed directory"))
+            Err(Error::new(ErrorKind::NotAllowed, "Destination is not within allowed directory").with_path(destination))
         }
     }
 
-    pub fn move_file<P: AsRef<Path>>(&self, destination: &P) -> std::io::Result<()> {
-        if PathFilter::is_whitelisted(destination)? {
+    pub fn move_file<P: AsRef<Path>>(&self, sandbox: &Sandbox, destination: &P) -> crate::error::Result<()> {
+        if sandbox.is_whitelisted(destination)? {
             let maybe_dest_parent = destination.as_ref().parent();
             if let Some(dest_parent) = maybe_dest_parent {
-                std::fs::create_dir_all(dest_parent)?;
+                std::fs::create_dir_all(dest_parent).with_path(dest_parent)?;
             }
-            std::fs::rename(&self.path, destination)
+            std::fs::rename(&self.path, destination).with_path(&self.path)?;
+            audit::record(AuditOperation::Move, &self.path, Some(destination.as_ref()));
+            Ok(())
         } else {
-            Err(Error::new(ErrorKind::Other, "Destination is not within allowed directory"))
+            Err(Error::new(ErrorKind::NotAllowed, "Destination is not within allowed directory").with_path(destination))
         }
     }
 
@@ -103,9 +116,11 @@ impl File {
         self.path.exists()
     }
 
-    pub fn delete(&self) -> std::io::Result<()> {
+    pub fn delete(&self) -> crate::error::Result<()> {
         if self.exists() {
-            std::fs::remove_file(&self.path)
+            std::fs::remove_file(&self.path).with_path(&self.path)?;
+            audit::record(AuditOperation::Delete, &self.path, None);
+            Ok(())
         } else {
             Ok(())
         }
@@ -136,7 +151,7 @@ impl<P: AsRef<Path>> From<P> for File where PathBuf: From<P> {
 mod tests {
     use crate::io::file::File;
     use crate::io::has_path::HasPath;
-    use crate::io::HasRelativePath;
+    use crate::io::{HasRelativePath, Sandbox};
 
     #[test]
     fn path_should_be_reported_without_trailing_slash() {
@@ -148,10 +163,11 @@ mod tests {
 
     #[test]
     fn relative_path_should_be_reported_without_trailing_slash() {
-        let tmp_file = tempfile::NamedTempFile::new().unwrap();
-        let file = File::from(tmp_file.path());
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let sandbox = Sandbox::new(tmp_dir.path(), tmp_dir.path().join("save")).unwrap();
+        let file = File::from(tmp_dir.path().join("scripts/init.lua"));
 
-        assert!(!file.relative_path().unwrap().ends_with("/"));
+        assert_eq!("scripts/init.lua", file.relative_path(&sandbox));
     }
 
     #[test]
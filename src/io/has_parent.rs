@@ -1,23 +1,23 @@
-use std::io::{Error, ErrorKind};
 use std::path::PathBuf;
 
-use crate::io::{Directory, PathFilter};
+use crate::error::{Error, ErrorKind};
+use crate::io::{Directory, Sandbox};
 use crate::io::has_path::HasPath;
 
 pub trait HasParent: HasPath {
-    fn parent(&self) -> std::io::Result<Directory> {
+    fn parent(&self, sandbox: &Sandbox) -> crate::error::Result<Directory> {
         let maybe_dir = PathBuf::from(self.path()).parent()
-            .map(|parent| Directory::from(parent));
+            .map(Directory::from);
 
         if let Some(dir) = maybe_dir {
-            if PathFilter::is_whitelisted(&dir.path())? {
+            if sandbox.is_whitelisted(dir.path())? {
                 Ok(dir)
             } else {
-                Err(Error::new(ErrorKind::Other, "Parent is not an allowed directory"))
+                Err(Error::new(ErrorKind::NotAllowed, "Parent is not an allowed directory").with_path(&dir.path))
             }
         } else {
             // We do not allow traversal to root directories, so we should never encounter this case.
-            Err(Error::new(ErrorKind::Other, "Directory does not have a parent - this should never happen"))
+            Err(Error::new(ErrorKind::NotFound, "Directory does not have a parent - this should never happen").with_path(self.path()))
         }
     }
 }
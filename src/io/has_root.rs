@@ -1,15 +1,7 @@
-use std::path::PathBuf;
-use crate::io::{Directory, HasPath, PathFilter};
+use crate::io::{Directory, HasPath, Sandbox};
 
 pub trait HasRoot: HasPath {
-    fn root(&self) -> std::io::Result<Directory> {
-        let path = PathBuf::from(self.path());
-        let root_path = if path.starts_with(PathFilter::game_directory()?) {
-            PathFilter::game_directory()?
-        } else {
-            PathFilter::save_data_directory()?
-        };
-
-        Ok(Directory::from(root_path))
+    fn root(&self, sandbox: &Sandbox) -> Directory {
+        Directory::from(sandbox.root_of(self.path()))
     }
 }
@@ -4,14 +4,18 @@ pub use has_parent::HasParent;
 pub use has_path::HasPath;
 pub use has_relative_path::HasRelativePath;
 pub use has_root::HasRoot;
-pub use path_filter::PathFilter;
+pub use sandbox::Sandbox;
+pub use watcher::{WatchOptions, Watcher};
 
+pub mod audit;
+pub mod save_data;
 mod file;
 mod directory;
-mod path_filter;
+mod sandbox;
 mod util;
 mod has_parent;
 mod has_path;
 mod has_root;
 mod has_relative_path;
+mod watcher;
 
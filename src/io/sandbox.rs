@@ -0,0 +1,198 @@
+use std::path::{Path, PathBuf};
+
+use path_absolutize::Absolutize;
+
+use crate::error::{Error, ErrorKind};
+use crate::io::save_data;
+use crate::io::save_data::DiscoveryContext;
+
+/// Directories that Lua is allowed to access: the game's installation directory, and its save
+/// data directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sandbox {
+    game_directory: PathBuf,
+    save_data_directory: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new<G: AsRef<Path>, S: AsRef<Path>>(game_directory: G, save_data_directory: S) -> crate::error::Result<Sandbox> {
+        Ok(Sandbox {
+            game_directory: absolutize(game_directory)?,
+            save_data_directory: absolutize(save_data_directory)?,
+        })
+    }
+
+    /// Creates a sandbox for the game running in the current working directory, with its save
+    /// data directory located by discovery.
+    pub fn discover() -> crate::error::Result<Sandbox> {
+        let game_directory = absolutize(std::env::current_dir()?)?;
+        Sandbox::discover_in(DiscoveryContext::from_environment(game_directory))
+    }
+
+    pub fn discover_in(context: DiscoveryContext) -> crate::error::Result<Sandbox> {
+        let save_data_directory = save_data::discover(&context)?;
+        Sandbox::new(context.game_directory, save_data_directory)
+    }
+
+    pub fn game_directory(&self) -> &Path {
+        &self.game_directory
+    }
+
+    pub fn save_data_directory(&self) -> &Path {
+        &self.save_data_directory
+    }
+
+    /// Turns the given path into an absolute one, with relative paths resolved against the game
+    /// directory.
+    pub fn resolve<P: AsRef<Path>>(&self, path: P) -> crate::error::Result<PathBuf> {
+        Ok(path.as_ref().absolutize_from(&self.game_directory)?.to_path_buf())
+    }
+
+    pub fn is_whitelisted<P: AsRef<Path>>(&self, path: P) -> crate::error::Result<bool> {
+        let normalized_path = self.resolve(path)?;
+
+        let result = normalized_path.starts_with(&self.game_directory)
+            || normalized_path.starts_with(&self.save_data_directory);
+
+        Ok(result)
+    }
+
+    /// Returns the directory of this sandbox that the given path belongs to.
+    pub fn root_of<P: AsRef<Path>>(&self, path: P) -> &Path {
+        if path.as_ref().starts_with(&self.game_directory) {
+            &self.game_directory
+        } else {
+            &self.save_data_directory
+        }
+    }
+
+    /// Switches to another of the save data locations discovery considers, eg. a native Linux
+    /// installation's instead of a Proton prefix's. `None` repeats the discovery.
+    ///
+    /// Since the save data directory is whitelisted, only valid locations among the candidates are
+    /// accepted, so that this can't be used to gain access to arbitrary directories. Embedders
+    /// wanting another directory create their own sandbox, or set `ITB_SAVE_DATA_DIR`.
+    pub fn set_save_data_directory(&mut self, context: &DiscoveryContext, path: Option<PathBuf>) -> crate::error::Result<()> {
+        self.save_data_directory = match path {
+            Some(path) => {
+                if !save_data::is_valid_location(&path) {
+                    return Err(Error::new(ErrorKind::InvalidInput, "Not a valid save data location").with_path(path));
+                }
+                let path = absolutize(path)?;
+                let is_candidate = save_data::candidates(context).iter()
+                    .any(|candidate| absolutize(&candidate.path).is_ok_and(|candidate| candidate == path));
+                if !is_candidate {
+                    return Err(Error::new(ErrorKind::NotAllowed, "Not one of the save data locations considered by discovery").with_path(path));
+                }
+                path
+            }
+            None => absolutize(save_data::discover(context)?)?
+        };
+
+        Ok(())
+    }
+}
+
+fn absolutize<P: AsRef<Path>>(path: P) -> crate::error::Result<PathBuf> {
+    Ok(path.as_ref().absolutize()?.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+
+    use tempfile::tempdir;
+
+    use crate::error::ErrorKind;
+    use crate::io::Sandbox;
+    use crate::io::save_data;
+    use crate::io::save_data::DiscoveryContext;
+
+    #[test]
+    fn empty_dir_should_not_be_valid_save_data_location() {
+        let tmp_dir = tempdir().unwrap();
+        let result = save_data::is_valid_location(tmp_dir.path());
+
+        assert!(!result);
+    }
+
+    #[test]
+    #[allow(clippy::ineffective_open_options)]
+    fn dir_containing_io_test_should_be_valid_save_data_location() {
+        let tmp_dir = tempdir().unwrap();
+        let tmp_file = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .append(true)
+            .open(tmp_dir.path().join("io_test.txt"))
+            .unwrap();
+
+        let result = save_data::is_valid_location(tmp_dir.path());
+
+        assert!(result);
+
+        drop(tmp_file);
+    }
+
+    #[test]
+    fn dir_returned_by_save_data_directory_should_be_valid_save_data_location() {
+        let tmp_dir = tempdir().unwrap();
+        let game_directory = tmp_dir.path().join("game");
+        std::fs::create_dir_all(game_directory.join("user")).unwrap();
+        std::fs::write(game_directory.join("user/io_test.txt"), "").unwrap();
+
+        let sandbox = Sandbox::discover_in(DiscoveryContext {
+            game_directory,
+            ..DiscoveryContext::default()
+        }).unwrap();
+
+        assert!(save_data::is_valid_location(sandbox.save_data_directory()));
+    }
+
+    #[test]
+    fn only_paths_within_sandbox_should_be_whitelisted() {
+        let tmp_dir = tempdir().unwrap();
+        let sandbox = Sandbox::new(tmp_dir.path().join("game"), tmp_dir.path().join("save")).unwrap();
+
+        assert!(sandbox.is_whitelisted(tmp_dir.path().join("game/mods/init.lua")).unwrap());
+        assert!(sandbox.is_whitelisted(tmp_dir.path().join("save/profile_Alpha")).unwrap());
+        assert!(!sandbox.is_whitelisted(tmp_dir.path().join("other")).unwrap());
+        assert!(!sandbox.is_whitelisted(tmp_dir.path().join("game/../other")).unwrap());
+        assert!(sandbox.is_whitelisted("scripts/init.lua").unwrap());
+    }
+
+    #[test]
+    fn set_save_data_directory_should_reject_invalid_location() {
+        let tmp_dir = tempdir().unwrap();
+        let mut sandbox = Sandbox::new(tmp_dir.path().join("game"), tmp_dir.path().join("save")).unwrap();
+
+        let error = sandbox.set_save_data_directory(&DiscoveryContext::default(), Some(tmp_dir.path().to_path_buf())).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert_eq!(tmp_dir.path().join("save"), sandbox.save_data_directory());
+    }
+
+    #[test]
+    fn set_save_data_directory_should_only_accept_candidates() {
+        let tmp_dir = tempdir().unwrap();
+        let context = DiscoveryContext {
+            game_directory: tmp_dir.path().join("game"),
+            documents_directory: Some(tmp_dir.path().join("documents")),
+            ..DiscoveryContext::default()
+        };
+        let candidate = tmp_dir.path().join("documents/My Games/Into The Breach");
+        let elsewhere = tmp_dir.path().join("elsewhere");
+        for directory in [&candidate, &elsewhere] {
+            std::fs::create_dir_all(directory).unwrap();
+            std::fs::write(directory.join("settings.lua"), "").unwrap();
+        }
+        let mut sandbox = Sandbox::new(tmp_dir.path().join("game"), tmp_dir.path().join("save")).unwrap();
+
+        let error = sandbox.set_save_data_directory(&context, Some(elsewhere)).unwrap_err();
+        assert_eq!(ErrorKind::NotAllowed, error.kind());
+        assert_eq!(tmp_dir.path().join("save"), sandbox.save_data_directory());
+
+        sandbox.set_save_data_directory(&context, Some(candidate.clone())).unwrap();
+        assert_eq!(candidate, sandbox.save_data_directory());
+    }
+}
@@ -0,0 +1,274 @@
+use std::path::{Path, PathBuf};
+
+use directories::{BaseDirs, UserDirs};
+
+use crate::error::{Error, ErrorKind};
+
+/// Environment variable that, when set, overrides save data directory discovery.
+pub const SAVE_DATA_DIR_ENV: &str = "ITB_SAVE_DATA_DIR";
+/// Steam's app id of Into the Breach, which names its Proton prefix.
+const STEAM_APP_ID: &str = "590380";
+/// Location of the save data directory relative to a Windows user's home directory.
+const DOCUMENTS_SUBPATH: &str = "Documents/My Games/Into The Breach";
+
+/// Locations on the current system that save data directory discovery starts from.
+/// Kept separate from the discovery itself, so that it can be tested against fake directory trees.
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryContext {
+    /// Directory the game runs from.
+    pub game_directory: PathBuf,
+    /// Explicitly requested save data directory, which takes precedence over everything else.
+    pub override_directory: Option<PathBuf>,
+    pub home_directory: Option<PathBuf>,
+    pub documents_directory: Option<PathBuf>,
+    /// `$XDG_DATA_HOME` on Linux.
+    pub data_directory: Option<PathBuf>,
+    /// `$WINEPREFIX`, if the game runs under Wine.
+    pub wine_prefix: Option<PathBuf>,
+}
+
+impl DiscoveryContext {
+    pub fn from_environment(game_directory: PathBuf) -> DiscoveryContext {
+        let base_dirs = BaseDirs::new();
+        let user_dirs = UserDirs::new();
+
+        DiscoveryContext {
+            game_directory,
+            override_directory: std::env::var_os(SAVE_DATA_DIR_ENV).map(PathBuf::from),
+            home_directory: base_dirs.as_ref().map(|dirs| dirs.home_dir().to_path_buf()),
+            documents_directory: user_dirs.as_ref().and_then(|dirs| dirs.document_dir()).map(Path::to_path_buf),
+            data_directory: base_dirs.as_ref().map(|dirs| dirs.data_dir().to_path_buf()),
+            wine_prefix: std::env::var_os("WINEPREFIX").map(PathBuf::from),
+        }
+    }
+}
+
+/// A location where the save data directory may be found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub path: PathBuf,
+    /// Short description of the kind of installation this location belongs to.
+    pub source: &'static str,
+}
+
+impl Candidate {
+    fn new<P: Into<PathBuf>>(path: P, source: &'static str) -> Candidate {
+        Candidate { path: path.into(), source }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        is_valid_location(&self.path)
+    }
+}
+
+/// Whether the given directory contains any of the files the game creates in its save data
+/// directory.
+pub fn is_valid_location<P: AsRef<Path>>(path: P) -> bool {
+    let path = path.as_ref();
+    if path.join("io_test.txt").exists() || path.join("settings.lua").exists() {
+        return true;
+    }
+
+    std::fs::read_dir(path)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_name().to_string_lossy().starts_with("profile_")
+                && entry.path().is_dir()))
+        .unwrap_or(false)
+}
+
+/// Lists all locations where the save data directory may be found, in order of preference.
+pub fn candidates(context: &DiscoveryContext) -> Vec<Candidate> {
+    let mut result = Vec::new();
+
+    // Windows, including GOG and Epic installations, and Wine when the game runs inside of it
+    if let Some(documents) = &context.documents_directory {
+        result.push(Candidate::new(documents.join("My Games/Into The Breach"), "documents"));
+    }
+
+    // Proton prefix of the Steam library the game is installed in,
+    // with the game in <library>/steamapps/common/Into the Breach
+    let library_compatdata = context.game_directory.join("../../compatdata").join(STEAM_APP_ID);
+    result.extend(prefix_candidates(&library_compatdata.join("pfx"), "proton"));
+
+    if let Some(home) = &context.home_directory {
+        // Proton prefix of Steam's default library
+        for steam_root in [".steam/steam", ".local/share/Steam", ".var/app/com.valvesoftware.Steam/.local/share/Steam"] {
+            let prefix = home.join(steam_root).join("steamapps/compatdata").join(STEAM_APP_ID).join("pfx");
+            result.extend(prefix_candidates(&prefix, "proton"));
+        }
+    }
+
+    // Plain Wine, or Lutris and Heroic, which run GOG and Epic installations in their own prefixes
+    if let Some(prefix) = &context.wine_prefix {
+        result.extend(prefix_candidates(prefix, "wine"));
+    }
+    if let Some(home) = &context.home_directory {
+        result.extend(prefix_candidates(&home.join(".wine"), "wine"));
+        for prefixes in ["Games/Heroic/Prefixes", "Games/Heroic/Prefixes/default", "Games"] {
+            for prefix in subdirectories(&home.join(prefixes)) {
+                result.extend(prefix_candidates(&prefix, "heroic/lutris"));
+            }
+        }
+    }
+
+    // Native Linux builds
+    if let Some(data) = &context.data_directory {
+        result.push(Candidate::new(data.join("IntoTheBreach"), "xdg"));
+        result.push(Candidate::new(data.join("Into The Breach"), "xdg"));
+    }
+
+    // Installation directory fallback
+    result.push(Candidate::new(context.game_directory.join("user"), "installation"));
+
+    result
+}
+
+/// Lists save data locations of all users within a Wine prefix.
+fn prefix_candidates(prefix: &Path, source: &'static str) -> Vec<Candidate> {
+    let users = prefix.join("drive_c/users");
+    let mut user_dirs = subdirectories(&users);
+    // Proton always runs as 'steamuser'; report it even if the prefix doesn't exist, so that
+    // failed discovery shows where it was looked for.
+    if user_dirs.is_empty() && source == "proton" {
+        user_dirs.push(users.join("steamuser"));
+    }
+
+    user_dirs.into_iter()
+        .map(|user_dir| Candidate::new(user_dir.join(DOCUMENTS_SUBPATH), source))
+        .collect()
+}
+
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+    let mut result: Vec<PathBuf> = std::fs::read_dir(path)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect())
+        .unwrap_or_default();
+    result.sort();
+    result
+}
+
+/// Finds the save data directory. An override is used as long as it exists, without checking
+/// its content. Otherwise, the first valid candidate is used.
+pub fn discover(context: &DiscoveryContext) -> crate::error::Result<PathBuf> {
+    if let Some(override_directory) = &context.override_directory {
+        return if override_directory.is_dir() {
+            Ok(override_directory.clone())
+        } else {
+            Err(Error::new(ErrorKind::NotFound, "Save data directory override doesn't exist")
+                .with_path(override_directory))
+        };
+    }
+
+    let candidates = candidates(context);
+    match candidates.iter().find(|candidate| candidate.is_valid()) {
+        Some(candidate) => Ok(candidate.path.clone()),
+        None => {
+            let tried = candidates.iter()
+                .map(|candidate| format!("\n  {} ({})", candidate.path.display(), candidate.source))
+                .collect::<String>();
+            Err(Error::new(ErrorKind::NotFound, format!(
+                "Could not find a valid save data location, tried:{}", tried
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use crate::error::ErrorKind;
+    use crate::io::save_data::{candidates, discover, DiscoveryContext};
+
+    fn make_save_dir(path: &Path) {
+        std::fs::create_dir_all(path.join("profile_Alpha")).unwrap();
+    }
+
+    fn context(root: &TempDir) -> DiscoveryContext {
+        let game_directory = root.path().join("library/steamapps/common/Into the Breach");
+        std::fs::create_dir_all(&game_directory).unwrap();
+
+        DiscoveryContext {
+            game_directory,
+            home_directory: Some(root.path().join("home")),
+            data_directory: Some(root.path().join("home/.local/share")),
+            ..DiscoveryContext::default()
+        }
+    }
+
+    #[test]
+    fn full_proton_path_should_be_discovered() {
+        let root = tempfile::tempdir().unwrap();
+        let context = context(&root);
+        let save_dir = root.path().join("library/steamapps/compatdata/590380/pfx/drive_c/users/steamuser/Documents/My Games/Into The Breach");
+        make_save_dir(&save_dir);
+
+        let result = discover(&context).unwrap();
+
+        assert_eq!(save_dir.canonicalize().unwrap(), result.canonicalize().unwrap());
+    }
+
+    #[test]
+    fn heroic_prefix_should_be_discovered() {
+        let root = tempfile::tempdir().unwrap();
+        let context = context(&root);
+        let save_dir = root.path().join("home/Games/Heroic/Prefixes/default/Into the Breach/drive_c/users/player/Documents/My Games/Into The Breach");
+        make_save_dir(&save_dir);
+
+        assert_eq!(save_dir, discover(&context).unwrap());
+    }
+
+    #[test]
+    fn native_linux_save_data_should_be_discovered() {
+        let root = tempfile::tempdir().unwrap();
+        let context = context(&root);
+        let save_dir = root.path().join("home/.local/share/IntoTheBreach");
+        std::fs::create_dir_all(&save_dir).unwrap();
+        std::fs::write(save_dir.join("settings.lua"), "").unwrap();
+
+        assert_eq!(save_dir, discover(&context).unwrap());
+    }
+
+    #[test]
+    fn documents_should_take_precedence_over_installation_fallback() {
+        let root = tempfile::tempdir().unwrap();
+        let mut context = context(&root);
+        context.documents_directory = Some(root.path().join("home/Documents"));
+        let save_dir = root.path().join("home/Documents/My Games/Into The Breach");
+        make_save_dir(&save_dir);
+        make_save_dir(&context.game_directory.join("user"));
+
+        assert_eq!(save_dir, discover(&context).unwrap());
+    }
+
+    #[test]
+    fn override_should_take_precedence() {
+        let root = tempfile::tempdir().unwrap();
+        let mut context = context(&root);
+        make_save_dir(&context.game_directory.join("user"));
+        context.override_directory = Some(root.path().join("custom"));
+        std::fs::create_dir_all(root.path().join("custom")).unwrap();
+
+        assert_eq!(root.path().join("custom"), discover(&context).unwrap());
+    }
+
+    #[test]
+    fn failed_discovery_should_report_all_candidates() {
+        let root = tempfile::tempdir().unwrap();
+        let context = context(&root);
+
+        let error = discover(&context).unwrap_err();
+
+        assert_eq!(ErrorKind::NotFound, error.kind());
+        for candidate in candidates(&context) {
+            assert!(error.message().contains(&candidate.path.display().to_string()), "{}", error);
+        }
+        assert!(error.message().contains("steamuser/Documents/My Games/Into The Breach"));
+    }
+}
@@ -0,0 +1,391 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+
+use notify::event::{CreateKind, ModifyKind, RemoveKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use path_absolutize::Absolutize;
+
+use crate::error::{Error, ErrorKind};
+use crate::io::{Directory, Sandbox};
+use crate::io::util::normalize;
+use crate::package::InnerPathFilter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEventKind {
+    Create,
+    Modify,
+    Delete,
+    Rename,
+}
+
+impl WatchEventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WatchEventKind::Create => "create",
+            WatchEventKind::Modify => "modify",
+            WatchEventKind::Delete => "delete",
+            WatchEventKind::Rename => "rename",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchEvent {
+    pub kind: WatchEventKind,
+    /// Path relative to the watched directory, with a trailing slash for directories.
+    /// For renames, this is the old path.
+    pub path: String,
+    /// New path of a renamed file or directory.
+    pub to: Option<String>,
+}
+
+pub struct WatchOptions {
+    /// Whether to watch subdirectories as well.
+    pub recursive: bool,
+    /// How long a path has to stay quiet before its events are reported. Editors often write
+    /// files in several steps, which would otherwise be reported as separate events.
+    pub debounce: Duration,
+    /// Only events for paths matching any of these filters are reported.
+    /// All events are reported if there are no filters.
+    pub filters: Vec<InnerPathFilter>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            recursive: true,
+            debounce: Duration::from_millis(100),
+            filters: Vec::new(),
+        }
+    }
+}
+
+/// Watches a directory for changes on a background thread. Changes are queued until
+/// retrieved with [Watcher::poll], so that they can be processed on the thread that owns
+/// the Lua state.
+pub struct Watcher {
+    root: PathBuf,
+    /// Some platforms report canonical paths, which may differ from `root` if it goes through
+    /// a symlink.
+    canonical_root: Option<PathBuf>,
+    filters: Vec<InnerPathFilter>,
+    queue: EventQueue,
+    receiver: Receiver<notify::Result<Event>>,
+    /// Dropping the platform watcher stops the background thread.
+    watcher: Option<RecommendedWatcher>,
+}
+
+impl Watcher {
+    pub fn new(sandbox: &Sandbox, directory: &Directory, options: WatchOptions) -> crate::error::Result<Watcher> {
+        if !sandbox.is_whitelisted(&directory.path)? {
+            return Err(Error::new(ErrorKind::NotAllowed, "Path does not point to an allowed directory").with_path(&directory.path));
+        }
+        if !directory.exists() {
+            return Err(Error::new(ErrorKind::NotFound, "Directory doesn't exist").with_path(&directory.path));
+        }
+
+        let root = directory.path.absolutize()?.to_path_buf();
+        let (sender, receiver) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        let mode = if options.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher.watch(&root, mode)?;
+
+        Ok(Watcher {
+            canonical_root: root.canonicalize().ok(),
+            root,
+            filters: options.filters,
+            queue: EventQueue::new(options.debounce),
+            receiver,
+            watcher: Some(watcher),
+        })
+    }
+
+    /// Returns all events that are no longer being debounced, in the order they first occurred.
+    pub fn poll(&mut self) -> crate::error::Result<Vec<WatchEvent>> {
+        let now = Instant::now();
+        while let Ok(result) = self.receiver.try_recv() {
+            let event = result?;
+            self.handle(event, now);
+        }
+
+        Ok(self.queue.drain(now)
+            .into_iter()
+            .filter(|event| self.is_reported(event))
+            .collect())
+    }
+
+    pub fn close(&mut self) {
+        self.watcher = None;
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.watcher.is_none()
+    }
+
+    fn handle(&mut self, event: Event, now: Instant) {
+        let is_dir = matches!(event.kind,
+            EventKind::Create(CreateKind::Folder) | EventKind::Remove(RemoveKind::Folder));
+        let paths: Vec<String> = event.paths.iter()
+            .filter_map(|path| self.relativize(path, is_dir))
+            .collect();
+
+        match (event.kind, paths.as_slice()) {
+            (EventKind::Modify(ModifyKind::Name(RenameMode::Both)), [from, to]) => {
+                self.queue.push_rename(from.clone(), to.clone(), now);
+            }
+            (EventKind::Modify(ModifyKind::Name(RenameMode::From)), [from]) => {
+                self.queue.push_rename_from(from.clone(), now);
+            }
+            (EventKind::Modify(ModifyKind::Name(RenameMode::To)), [to]) => {
+                self.queue.push_rename_to(to.clone(), now);
+            }
+            (EventKind::Modify(ModifyKind::Name(_)), _) => {
+                // The platform couldn't tell which side of the rename this is, so check
+                // whether the path still exists.
+                for (path, relative_path) in event.paths.iter().zip(paths) {
+                    let kind = if path.exists() { WatchEventKind::Create } else { WatchEventKind::Delete };
+                    self.queue.push(kind, relative_path, now);
+                }
+            }
+            (EventKind::Create(_), _) => self.push_all(WatchEventKind::Create, paths, now),
+            (EventKind::Remove(_), _) => self.push_all(WatchEventKind::Delete, paths, now),
+            (EventKind::Modify(_) | EventKind::Any | EventKind::Other, _) => {
+                self.push_all(WatchEventKind::Modify, paths, now)
+            }
+            (EventKind::Access(_), _) => {}
+        }
+    }
+
+    fn push_all(&mut self, kind: WatchEventKind, paths: Vec<String>, now: Instant) {
+        for path in paths {
+            self.queue.push(kind, path, now);
+        }
+    }
+
+    fn relativize(&self, path: &Path, is_dir: bool) -> Option<String> {
+        let relative_path = path.strip_prefix(&self.root)
+            .ok()
+            .or_else(|| self.canonical_root.as_ref().and_then(|root| path.strip_prefix(root).ok()))?;
+
+        if relative_path.as_os_str().is_empty() {
+            // Event concerning the watched directory itself
+            return None;
+        }
+
+        if is_dir || path.is_dir() {
+            Some(normalize(relative_path) + "/")
+        } else {
+            Some(normalize(relative_path))
+        }
+    }
+
+    fn is_reported(&self, event: &WatchEvent) -> bool {
+        if self.filters.is_empty() {
+            return true;
+        }
+
+        let matches = |path: &str| self.filters.iter().any(|filter| filter.matches(path));
+        matches(&event.path) || event.to.as_deref().map(matches).unwrap_or(false)
+    }
+}
+
+struct Pending {
+    event: WatchEvent,
+    last_seen: Instant,
+}
+
+/// Coalesces events for the same path, and holds them back until no further events arrived
+/// for that path for the debounce duration.
+struct EventQueue {
+    debounce: Duration,
+    pending: Vec<Pending>,
+    /// Old path of a rename whose new path hasn't been reported yet.
+    rename_from: Option<String>,
+}
+
+impl EventQueue {
+    fn new(debounce: Duration) -> EventQueue {
+        EventQueue {
+            debounce,
+            pending: Vec::new(),
+            rename_from: None,
+        }
+    }
+
+    fn push(&mut self, kind: WatchEventKind, path: String, now: Instant) {
+        let maybe_index = self.pending.iter()
+            .position(|pending| pending.event.path == path && pending.event.kind != WatchEventKind::Rename);
+
+        match maybe_index {
+            None => self.pending.push(Pending {
+                event: WatchEvent { kind, path, to: None },
+                last_seen: now,
+            }),
+            Some(index) => {
+                let merged_kind = match (self.pending[index].event.kind, kind) {
+                    // File was only there temporarily, eg. an editor's swap file
+                    (WatchEventKind::Create, WatchEventKind::Delete) => None,
+                    (WatchEventKind::Create, _) => Some(WatchEventKind::Create),
+                    (WatchEventKind::Delete, WatchEventKind::Create) => Some(WatchEventKind::Modify),
+                    (_, kind) => Some(kind),
+                };
+
+                match merged_kind {
+                    None => {
+                        self.pending.remove(index);
+                    }
+                    Some(merged_kind) => {
+                        let pending = &mut self.pending[index];
+                        pending.event.kind = merged_kind;
+                        pending.last_seen = now;
+                    }
+                }
+            }
+        }
+    }
+
+    fn push_rename(&mut self, from: String, to: String, now: Instant) {
+        let maybe_created = self.pending.iter()
+            .position(|pending| pending.event.path == from && pending.event.kind == WatchEventKind::Create);
+
+        if let Some(index) = maybe_created {
+            // Nobody has seen the old path yet, so as far as Lua is concerned, the new one was just created
+            self.pending.remove(index);
+            self.push(WatchEventKind::Create, to, now);
+        } else {
+            self.pending.push(Pending {
+                event: WatchEvent { kind: WatchEventKind::Rename, path: from, to: Some(to) },
+                last_seen: now,
+            });
+        }
+    }
+
+    /// Some platforms report both sides of a rename as separate events. Report the old path as
+    /// deleted, unless the new path follows right after.
+    fn push_rename_from(&mut self, from: String, now: Instant) {
+        self.push(WatchEventKind::Delete, from.clone(), now);
+        self.rename_from = Some(from);
+    }
+
+    fn push_rename_to(&mut self, to: String, now: Instant) {
+        match self.rename_from.take() {
+            Some(from) => {
+                let maybe_deleted = self.pending.iter()
+                    .position(|pending| pending.event.path == from && pending.event.kind == WatchEventKind::Delete);
+                if let Some(index) = maybe_deleted {
+                    self.pending.remove(index);
+                }
+                self.push_rename(from, to, now);
+            }
+            None => self.push(WatchEventKind::Create, to, now)
+        }
+    }
+
+    fn drain(&mut self, now: Instant) -> Vec<WatchEvent> {
+        let debounce = self.debounce;
+        let (ready, waiting): (Vec<Pending>, Vec<Pending>) = self.pending.drain(..)
+            .partition(|pending| now.duration_since(pending.last_seen) >= debounce);
+
+        self.pending = waiting;
+        ready.into_iter()
+            .map(|pending| pending.event)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::io::{Directory, Sandbox};
+    use crate::io::watcher::{EventQueue, WatchEvent, WatchEventKind, WatchOptions, Watcher};
+    use crate::package::InnerPathFilter;
+
+    fn event(kind: WatchEventKind, path: &str) -> WatchEvent {
+        WatchEvent { kind, path: path.to_string(), to: None }
+    }
+
+    #[test]
+    fn events_should_be_held_back_until_debounce_elapses() {
+        let start = Instant::now();
+        let mut queue = EventQueue::new(Duration::from_millis(100));
+
+        queue.push(WatchEventKind::Modify, "a.lua".to_string(), start);
+
+        assert!(queue.drain(start + Duration::from_millis(50)).is_empty());
+        assert_eq!(vec![event(WatchEventKind::Modify, "a.lua")], queue.drain(start + Duration::from_millis(100)));
+        assert!(queue.drain(start + Duration::from_millis(200)).is_empty());
+    }
+
+    #[test]
+    fn repeated_events_should_restart_debounce() {
+        let start = Instant::now();
+        let mut queue = EventQueue::new(Duration::from_millis(100));
+
+        queue.push(WatchEventKind::Modify, "a.lua".to_string(), start);
+        queue.push(WatchEventKind::Modify, "a.lua".to_string(), start + Duration::from_millis(80));
+
+        assert!(queue.drain(start + Duration::from_millis(150)).is_empty());
+        assert_eq!(1, queue.drain(start + Duration::from_millis(180)).len());
+    }
+
+    #[test]
+    fn events_for_same_path_should_be_coalesced() {
+        let now = Instant::now();
+        let mut queue = EventQueue::new(Duration::ZERO);
+
+        queue.push(WatchEventKind::Create, "new.lua".to_string(), now);
+        queue.push(WatchEventKind::Modify, "new.lua".to_string(), now);
+        queue.push(WatchEventKind::Create, "swap.tmp".to_string(), now);
+        queue.push(WatchEventKind::Delete, "swap.tmp".to_string(), now);
+        queue.push(WatchEventKind::Delete, "replaced.lua".to_string(), now);
+        queue.push(WatchEventKind::Create, "replaced.lua".to_string(), now);
+
+        assert_eq!(vec![
+            event(WatchEventKind::Create, "new.lua"),
+            event(WatchEventKind::Modify, "replaced.lua"),
+        ], queue.drain(now));
+    }
+
+    #[test]
+    fn split_rename_events_should_be_paired() {
+        let now = Instant::now();
+        let mut queue = EventQueue::new(Duration::ZERO);
+
+        queue.push_rename_from("old.lua".to_string(), now);
+        queue.push_rename_to("new.lua".to_string(), now);
+
+        assert_eq!(vec![WatchEvent {
+            kind: WatchEventKind::Rename,
+            path: "old.lua".to_string(),
+            to: Some("new.lua".to_string()),
+        }], queue.drain(now));
+    }
+
+    #[test]
+    fn watcher_should_report_paths_relative_to_watched_directory() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let sandbox = Sandbox::new(tmp_dir.path(), tmp_dir.path().join("save")).unwrap();
+        std::fs::create_dir(tmp_dir.path().join("scripts")).unwrap();
+        let options = WatchOptions {
+            debounce: Duration::ZERO,
+            filters: vec![InnerPathFilter::parse("scripts/*.lua").unwrap()],
+            ..WatchOptions::default()
+        };
+        let mut watcher = Watcher::new(&sandbox, &Directory::from(tmp_dir.path()), options).unwrap();
+
+        std::fs::write(tmp_dir.path().join("scripts/init.lua"), "return {}").unwrap();
+        std::fs::write(tmp_dir.path().join("scripts/notes.txt"), "").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut events = Vec::new();
+        while events.is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+            events.extend(watcher.poll().unwrap());
+        }
+
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|event| event.path == "scripts/init.lua"), "{:?}", events);
+    }
+}
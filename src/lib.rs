@@ -1,14 +1,60 @@
+#[cfg(all(feature = "module", feature = "vendored"))]
+compile_error!("Features `module` and `vendored` are mutually exclusive, build with --no-default-features to enable `module`");
+
+#[cfg(feature = "buffer")]
+mod buffer;
+#[cfg(feature = "compress")]
+mod compression;
+#[cfg(feature = "encoding")]
+mod encoding;
+mod error;
+#[cfg(feature = "io")]
 mod io;
+#[cfg(feature = "io")]
+mod package;
+#[cfg(feature = "io")]
+mod progress;
+#[cfg(feature = "task")]
+mod task;
 mod lua;
+#[cfg(feature = "mods")]
+mod mods;
+#[cfg(feature = "semver")]
+mod version;
+#[cfg(feature = "vendored")]
+pub mod docs;
+#[cfg(all(feature = "vendored", feature = "io"))]
+pub mod runner;
 
+/// Entry point called by `require("itb_rs")`, returning the exports table.
+///
+/// # Safety
+///
+/// `lua_state` must be a valid pointer to a Lua state that outlives this library.
 #[no_mangle]
-pub extern "C" fn luaopen_itb_rs(lua_state: *mut mlua::lua_State) -> i32 {
-    // Leak the Lua purposefully because it's supposed to live for the duration of the program.
-    // It should be owned by the game, so as a client DLL, we can assume it's truly 'static.
-    let lua = unsafe { mlua::Lua::init_from_ptr(lua_state) }.into_static();
+pub unsafe extern "C" fn luaopen_itb_rs(lua_state: *mut mlua::lua_State) -> i32 {
+    open(lua_state)
+}
 
-    let export = lua::exports::init(&lua).expect("Failed to initialize module export table");
-    lua.globals().set("itb_rs", export).unwrap();
+/// Entry point called by `require("itb_rs_lua")` or `package.loadlib(path, "luaopen_itb_rs_lua")`,
+/// named after the library file.
+///
+/// # Safety
+///
+/// `lua_state` must be a valid pointer to a Lua state that outlives this library.
+#[no_mangle]
+pub unsafe extern "C" fn luaopen_itb_rs_lua(lua_state: *mut mlua::lua_State) -> i32 {
+    open(lua_state)
+}
 
-    0
+unsafe fn open(lua_state: *mut mlua::lua_State) -> i32 {
+    // The Lua is owned by the game, and stays alive for as long as the state does.
+    mlua::Lua::init_from_ptr(lua_state)
+        .entrypoint1(|lua| {
+            let exports = lua::exports::init(lua)?;
+            // Scripts written before the library could be loaded with `require` look for the global.
+            lua.globals().set(lua::exports::GLOBAL_NAME, exports.clone())?;
+            Ok(exports)
+        })
+        .expect("Failed to initialize module export table")
 }
@@ -0,0 +1,443 @@
+//! Registration of everything exposed to Lua, which records a description of each function,
+//! method and field as it is registered. The descriptions are what `itb_rs.has` looks methods up
+//! in, and what the LuaLS annotations and the API reference are generated from, so neither can
+//! drift from the bindings.
+use std::cell::RefCell;
+
+use mlua::{FromLua, FromLuaMulti, Lua, MetaMethod, ToLua, ToLuaMulti, UserDataFields, UserDataMethods};
+use mlua::prelude::{LuaAnyUserData, LuaError, LuaResult, LuaTable, LuaUserData};
+
+use crate::error::{Error, ErrorKind};
+use crate::lua::error::external_lua_error;
+#[cfg(any(feature = "io", feature = "semver"))]
+use crate::lua::safe::safe_variants;
+
+/// Parameter of a function, with its type in LuaLS notation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDoc {
+    pub name: String,
+    pub params: Vec<Param>,
+    /// Types of the returned values, in LuaLS notation.
+    pub returns: Vec<String>,
+    pub doc: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDoc {
+    pub name: String,
+    pub ty: String,
+    pub doc: String,
+}
+
+/// Userdata type exposed to Lua.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassDoc {
+    pub name: &'static str,
+    pub doc: String,
+    pub fields: Vec<FieldDoc>,
+    pub methods: Vec<FunctionDoc>,
+    /// Metamethods, under their Lua names, eg. `__len`.
+    pub meta_methods: Vec<FunctionDoc>,
+}
+
+/// Table of functions exposed to Lua, along with the userdata types its functions work with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleDoc {
+    pub name: &'static str,
+    pub doc: String,
+    pub fields: Vec<FieldDoc>,
+    pub functions: Vec<FunctionDoc>,
+    pub modules: Vec<ModuleDoc>,
+    pub classes: Vec<ClassDoc>,
+}
+
+impl ModuleDoc {
+    /// Calls `visit` with this module and all modules nested within it, along with their
+    /// dot-separated paths starting with this module's name.
+    pub fn walk<'a>(&'a self, visit: &mut dyn FnMut(&str, &'a ModuleDoc)) {
+        self.walk_from(self.name, visit);
+    }
+
+    fn walk_from<'a>(&'a self, path: &str, visit: &mut dyn FnMut(&str, &'a ModuleDoc)) {
+        visit(path, self);
+        for module in &self.modules {
+            module.walk_from(&format!("{}.{}", path, module.name), visit);
+        }
+    }
+}
+
+/// Exports table of a module under construction.
+pub struct Module<'lua> {
+    lua: &'lua Lua,
+    table: LuaTable<'lua>,
+    doc: ModuleDoc,
+}
+
+impl<'lua> Module<'lua> {
+    pub fn new(lua: &'lua Lua, name: &'static str, doc: &str) -> LuaResult<Module<'lua>> {
+        Ok(Module {
+            lua,
+            table: lua.create_table()?,
+            doc: ModuleDoc {
+                name,
+                doc: doc.to_string(),
+                fields: Vec::new(),
+                functions: Vec::new(),
+                modules: Vec::new(),
+                classes: Vec::new(),
+            },
+        })
+    }
+
+    /// Exports a function. The signature lists its parameters and return values in LuaLS
+    /// notation, eg. `(path: string, options: { format: string? }?) -> Package`; functions that
+    /// don't return anything leave out the arrow.
+    pub fn function<A, R, F>(&mut self, name: &str, signature: &str, doc: &str, function: F) -> LuaResult<()>
+        where A: FromLuaMulti<'lua>,
+              R: ToLuaMulti<'lua>,
+              F: 'static + Fn(&'lua Lua, A) -> LuaResult<R> {
+        self.table.set(name, self.lua.create_function(function)?)?;
+        self.doc.functions.push(function_doc(name, signature, doc)?);
+        Ok(())
+    }
+
+    /// Exports a value other than a function, with its type in LuaLS notation.
+    pub fn field<V: ToLua<'lua>>(&mut self, name: &str, ty: &str, doc: &str, value: V) -> LuaResult<()> {
+        self.table.set(name, value)?;
+        self.doc.fields.push(FieldDoc { name: name.to_string(), ty: ty.to_string(), doc: doc.to_string() });
+        Ok(())
+    }
+
+    /// Nests another module within this one, under its name.
+    pub fn module(&mut self, module: Module<'lua>) -> LuaResult<()> {
+        self.table.set(module.doc.name, module.table)?;
+        self.doc.modules.push(module.doc);
+        Ok(())
+    }
+
+    /// Documents a userdata type returned or accepted by this module's functions. Its methods
+    /// and fields are those registered through [ApiMethods] and [ApiFields].
+    pub fn class<T: LuaUserData>(&mut self, name: &'static str, doc: &str) -> LuaResult<()> {
+        self.doc.classes.push(describe::<T>(name, doc)?);
+        Ok(())
+    }
+
+    /// Exports non-throwing variants of all functions exported so far, see [safe_variants].
+    #[cfg(any(feature = "io", feature = "semver"))]
+    pub fn safe_variants(&mut self) -> LuaResult<()> {
+        let safe = safe_variants(self.lua, &self.table)?;
+        self.field("safe", "table<string, function>",
+            "Non-throwing variants of this module's functions, which return `nil`, the error's kind and its message on \
+            failure, and `true` instead of nothing on success. Methods can be called the same way with \
+            `safe.call(object, method_name, ...)`.",
+            safe)
+    }
+
+    pub fn doc(&self) -> &ModuleDoc {
+        &self.doc
+    }
+
+    pub fn clone_table(&self) -> LuaTable<'lua> {
+        self.table.clone()
+    }
+
+    pub fn into_table(self) -> LuaTable<'lua> {
+        self.table
+    }
+}
+
+/// Methods of a userdata type, registered along with their description.
+pub trait ApiMethods<'lua, T: LuaUserData>: UserDataMethods<'lua, T> {
+    #[cfg(any(test, feature = "buffer", feature = "io", feature = "semver"))]
+    fn method<A, R, M>(&mut self, name: &str, signature: &str, doc: &str, method: M)
+        where A: FromLuaMulti<'lua>,
+              R: ToLuaMulti<'lua>,
+              M: 'static + Fn(&'lua Lua, &T, A) -> LuaResult<R> {
+        record(|class| {
+            class.methods.push(function_doc(name, signature, doc)?);
+            Ok(())
+        });
+        self.add_method(name, method);
+    }
+
+    #[cfg(any(feature = "buffer", feature = "io"))]
+    fn method_mut<A, R, M>(&mut self, name: &str, signature: &str, doc: &str, method: M)
+        where A: FromLuaMulti<'lua>,
+              R: ToLuaMulti<'lua>,
+              M: 'static + FnMut(&'lua Lua, &mut T, A) -> LuaResult<R> {
+        record(|class| {
+            class.methods.push(function_doc(name, signature, doc)?);
+            Ok(())
+        });
+        self.add_method_mut(name, method);
+    }
+
+    fn meta_method<A, R, M>(&mut self, meta: MetaMethod, signature: &str, doc: &str, method: M)
+        where A: FromLuaMulti<'lua>,
+              R: ToLuaMulti<'lua>,
+              M: 'static + Fn(&'lua Lua, &T, A) -> LuaResult<R> {
+        record(|class| {
+            class.meta_methods.push(function_doc(meta.name(), signature, doc)?);
+            Ok(())
+        });
+        self.add_meta_method(meta, method);
+    }
+
+    /// Metamethods that may be called with an instance of `T` as either operand.
+    #[cfg(feature = "buffer")]
+    fn meta_function<A, R, F>(&mut self, meta: MetaMethod, signature: &str, doc: &str, function: F)
+        where A: FromLuaMulti<'lua>,
+              R: ToLuaMulti<'lua>,
+              F: 'static + Fn(&'lua Lua, A) -> LuaResult<R> {
+        record(|class| {
+            class.meta_methods.push(function_doc(meta.name(), signature, doc)?);
+            Ok(())
+        });
+        self.add_meta_function(meta, function);
+    }
+}
+
+impl<'lua, T: LuaUserData, M: UserDataMethods<'lua, T>> ApiMethods<'lua, T> for M {}
+
+/// Fields of a userdata type, registered along with their description.
+pub trait ApiFields<'lua, T: LuaUserData>: UserDataFields<'lua, T> {
+    fn field_get<R, M>(&mut self, name: &str, ty: &str, doc: &str, method: M)
+        where R: ToLua<'lua>,
+              M: 'static + Fn(&'lua Lua, &T) -> LuaResult<R> {
+        record(|class| {
+            class.fields.push(FieldDoc { name: name.to_string(), ty: ty.to_string(), doc: doc.to_string() });
+            Ok(())
+        });
+        self.add_field_method_get(name, method);
+    }
+}
+
+impl<'lua, T: LuaUserData, F: UserDataFields<'lua, T>> ApiFields<'lua, T> for F {}
+
+thread_local! {
+    /// Class being described by [describe], if any. Outside of it, registered methods and fields
+    /// aren't recorded, since mlua registers them whenever it creates a type's metatable.
+    /// Registration can't fail, so the first error in describing the class takes its place.
+    static DESCRIBED_CLASS: RefCell<Option<LuaResult<ClassDoc>>> = const { RefCell::new(None) };
+}
+
+fn record<F: FnOnce(&mut ClassDoc) -> LuaResult<()>>(update: F) {
+    DESCRIBED_CLASS.with(|class| {
+        let mut class = class.borrow_mut();
+        if let Some(Ok(described)) = class.as_mut() {
+            if let Err(error) = update(described) {
+                *class = Some(Err(error));
+            }
+        }
+    });
+}
+
+/// Collects the methods and fields `T` registers, by having it register them with a [Describer]
+/// instead of mlua.
+fn describe<T: LuaUserData>(name: &'static str, doc: &str) -> LuaResult<ClassDoc> {
+    DESCRIBED_CLASS.with(|class| {
+        *class.borrow_mut() = Some(Ok(ClassDoc {
+            name,
+            doc: doc.to_string(),
+            fields: Vec::new(),
+            methods: Vec::new(),
+            meta_methods: Vec::new(),
+        }));
+    });
+
+    T::add_fields(&mut Describer);
+    T::add_methods(&mut Describer);
+
+    DESCRIBED_CLASS.with(|class| class.borrow_mut().take())
+        .expect("Described class was taken by someone else")
+}
+
+/// Stand-in for mlua's registries of methods and fields, which discards everything registered.
+struct Describer;
+
+impl<'lua, T: LuaUserData> UserDataMethods<'lua, T> for Describer {
+    fn add_method<S, A, R, M>(&mut self, _: &S, _: M)
+        where S: AsRef<[u8]> + ?Sized, A: FromLuaMulti<'lua>, R: ToLuaMulti<'lua>,
+              M: 'static + Fn(&'lua Lua, &T, A) -> LuaResult<R> {}
+
+    fn add_method_mut<S, A, R, M>(&mut self, _: &S, _: M)
+        where S: AsRef<[u8]> + ?Sized, A: FromLuaMulti<'lua>, R: ToLuaMulti<'lua>,
+              M: 'static + FnMut(&'lua Lua, &mut T, A) -> LuaResult<R> {}
+
+    fn add_function<S, A, R, F>(&mut self, _: &S, _: F)
+        where S: AsRef<[u8]> + ?Sized, A: FromLuaMulti<'lua>, R: ToLuaMulti<'lua>,
+              F: 'static + Fn(&'lua Lua, A) -> LuaResult<R> {}
+
+    fn add_function_mut<S, A, R, F>(&mut self, _: &S, _: F)
+        where S: AsRef<[u8]> + ?Sized, A: FromLuaMulti<'lua>, R: ToLuaMulti<'lua>,
+              F: 'static + FnMut(&'lua Lua, A) -> LuaResult<R> {}
+
+    fn add_meta_method<S, A, R, M>(&mut self, _: S, _: M)
+        where S: Into<MetaMethod>, A: FromLuaMulti<'lua>, R: ToLuaMulti<'lua>,
+              M: 'static + Fn(&'lua Lua, &T, A) -> LuaResult<R> {}
+
+    fn add_meta_method_mut<S, A, R, M>(&mut self, _: S, _: M)
+        where S: Into<MetaMethod>, A: FromLuaMulti<'lua>, R: ToLuaMulti<'lua>,
+              M: 'static + FnMut(&'lua Lua, &mut T, A) -> LuaResult<R> {}
+
+    fn add_meta_function<S, A, R, F>(&mut self, _: S, _: F)
+        where S: Into<MetaMethod>, A: FromLuaMulti<'lua>, R: ToLuaMulti<'lua>,
+              F: 'static + Fn(&'lua Lua, A) -> LuaResult<R> {}
+
+    fn add_meta_function_mut<S, A, R, F>(&mut self, _: S, _: F)
+        where S: Into<MetaMethod>, A: FromLuaMulti<'lua>, R: ToLuaMulti<'lua>,
+              F: 'static + FnMut(&'lua Lua, A) -> LuaResult<R> {}
+}
+
+impl<'lua, T: LuaUserData> UserDataFields<'lua, T> for Describer {
+    fn add_field_method_get<S, R, M>(&mut self, _: &S, _: M)
+        where S: AsRef<[u8]> + ?Sized, R: ToLua<'lua>,
+              M: 'static + Fn(&'lua Lua, &T) -> LuaResult<R> {}
+
+    fn add_field_method_set<S, A, M>(&mut self, _: &S, _: M)
+        where S: AsRef<[u8]> + ?Sized, A: FromLua<'lua>,
+              M: 'static + FnMut(&'lua Lua, &mut T, A) -> LuaResult<()> {}
+
+    fn add_field_function_get<S, R, F>(&mut self, _: &S, _: F)
+        where S: AsRef<[u8]> + ?Sized, R: ToLua<'lua>,
+              F: 'static + Fn(&'lua Lua, LuaAnyUserData<'lua>) -> LuaResult<R> {}
+
+    fn add_field_function_set<S, A, F>(&mut self, _: &S, _: F)
+        where S: AsRef<[u8]> + ?Sized, A: FromLua<'lua>,
+              F: 'static + FnMut(&'lua Lua, LuaAnyUserData<'lua>, A) -> LuaResult<()> {}
+
+    fn add_meta_field_with<S, R, F>(&mut self, _: S, _: F)
+        where S: Into<MetaMethod>, F: 'static + Fn(&'lua Lua) -> LuaResult<R>, R: ToLua<'lua> {}
+}
+
+/// Parses a signature in the form of `(name: type, ...) -> type, ...`.
+///
+/// Signatures are written by hand next to the functions they describe, so malformed ones are
+/// bugs. They fail the registration, rather than panicking while the library is being loaded.
+fn function_doc(name: &str, signature: &str, doc: &str) -> LuaResult<FunctionDoc> {
+    let (params, returns) = match signature.split_once(" -> ") {
+        Some((params, returns)) => (params, split_top_level(returns)),
+        None => (signature, Vec::new()),
+    };
+    let params = params.strip_prefix('(')
+        .and_then(|params| params.strip_suffix(')'))
+        .ok_or_else(|| malformed_signature(format!("Parameters of '{}' aren't enclosed in parentheses: {}", name, signature)))?;
+
+    let params = split_top_level(params).into_iter()
+        .map(|param| match param.split_once(": ") {
+            Some((name, ty)) => Ok(Param { name: name.to_string(), ty: ty.to_string() }),
+            None => Err(malformed_signature(format!("Parameter of '{}' has no type: {}", name, param))),
+        })
+        .collect::<LuaResult<_>>()?;
+
+    Ok(FunctionDoc {
+        name: name.to_string(),
+        params,
+        returns,
+        doc: doc.to_string(),
+    })
+}
+
+fn malformed_signature(message: String) -> LuaError {
+    external_lua_error(Error::new(ErrorKind::InvalidInput, message))
+}
+
+/// Splits a comma-separated list, ignoring commas nested within brackets of any kind.
+fn split_top_level(list: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in list.char_indices() {
+        match c {
+            '(' | '{' | '[' | '<' => depth += 1,
+            ')' | '}' | ']' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(list[start..index].trim().to_string());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    let last = list[start..].trim();
+    if !last.is_empty() {
+        result.push(last.to_string());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use mlua::UserDataMethods;
+    use mlua::prelude::LuaUserData;
+
+    use crate::error::ErrorKind;
+    use crate::lua::api::{ApiMethods, describe, function_doc, Param};
+    use crate::lua::error::crate_error;
+
+    struct Malformed;
+
+    impl LuaUserData for Malformed {
+        fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+            methods.method("open", "(path)", "", |_, _, ()| Ok(()));
+            methods.method("close", "()", "", |_, _, ()| Ok(()));
+        }
+    }
+
+    #[test]
+    fn signature_should_be_split_into_params_and_returns() {
+        let doc = function_doc("read", "(path: string, options: { format: string?, size: integer }?) -> Package, string?", "").unwrap();
+
+        assert_eq!(vec![
+            Param { name: "path".to_string(), ty: "string".to_string() },
+            Param { name: "options".to_string(), ty: "{ format: string?, size: integer }?".to_string() },
+        ], doc.params);
+        assert_eq!(vec!["Package", "string?"], doc.returns);
+    }
+
+    #[test]
+    fn signature_without_params_or_returns_should_be_accepted() {
+        let doc = function_doc("close", "()", "").unwrap();
+
+        assert!(doc.params.is_empty());
+        assert!(doc.returns.is_empty());
+    }
+
+    #[test]
+    fn function_types_should_not_be_split() {
+        let doc = function_doc("delete", "(callback: fun(done: integer, total: integer): boolean?)", "").unwrap();
+
+        assert_eq!(1, doc.params.len());
+        assert_eq!("fun(done: integer, total: integer): boolean?", doc.params[0].ty);
+    }
+
+    #[test]
+    fn malformed_signatures_should_be_rejected() {
+        let without_type = function_doc("file", "(path)", "").unwrap_err();
+        let without_parentheses = function_doc("file", "path: string", "").unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidInput, crate_error(&without_type).kind());
+        assert_eq!(ErrorKind::InvalidInput, crate_error(&without_parentheses).kind());
+    }
+
+    #[test]
+    fn class_with_malformed_method_signature_should_be_rejected() {
+        let error = describe::<Malformed>("Malformed", "").unwrap_err();
+
+        assert!(crate_error(&error).message().contains("'open'"));
+    }
+
+    #[test]
+    #[cfg(feature = "vendored")]
+    fn signatures_of_all_exports_should_be_valid() {
+        let lua = mlua::Lua::new();
+
+        crate::lua::exports::module(&lua).unwrap();
+    }
+}
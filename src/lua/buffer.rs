@@ -0,0 +1,202 @@
+use mlua::{FromLua, Lua, MetaMethod, ToLua, UserDataMethods, Variadic};
+use mlua::prelude::{LuaError, LuaResult, LuaUserData, LuaValue};
+
+use crate::buffer::{Buffer, Endian, Number};
+use crate::lua::api::{ApiMethods, Module};
+use crate::lua::error::external_lua_error;
+
+/// Build the module's exports table, governing what is exposed to Lua.
+pub fn init(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "buffer", "Growable containers of binary data, with cursor-based reading and writing.")?;
+
+    exports.function("new", "(content: string|Buffer|integer[]?) -> Buffer",
+        "Creates a buffer holding a copy of the specified content, or an empty one.", new)?;
+    exports.class::<Buffer>("Buffer",
+        "Binary data with a cursor, which reads without a position and all writes start at, and advance. Positions \
+        are 1-based, like those of the string library. Buffers are accepted anywhere binary content is expected.")?;
+
+    Ok(exports)
+}
+
+//region <Exported adapter functions>
+fn new(_: &Lua, (content, ): (Option<LuaBytes>, )) -> LuaResult<Buffer> {
+    match content {
+        None => Ok(Buffer::new()),
+        Some(content) => Ok(Buffer::from(content.0))
+    }
+}
+//endregion
+
+/// Binary content passed from Lua, either as a string (which are 8-bit clean in Lua 5.1),
+/// a [Buffer], or a table of byte values.
+pub(crate) struct LuaBytes(pub Vec<u8>);
+
+impl<'lua> FromLua<'lua> for LuaBytes {
+    fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::String(string) => Ok(LuaBytes(string.as_bytes().to_vec())),
+            LuaValue::UserData(ref userdata) if userdata.is::<Buffer>() => {
+                Ok(LuaBytes(userdata.borrow::<Buffer>()?.as_bytes().to_vec()))
+            }
+            LuaValue::Table(_) => Ok(LuaBytes(lua.unpack(value)?)),
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "bytes",
+                message: Some("expected a string, a buffer, or a table of bytes".to_string()),
+            })
+        }
+    }
+}
+
+/// Converts a 1-based position passed from Lua into an offset into the buffer.
+fn offset(position: i64) -> LuaResult<usize> {
+    if position < 1 {
+        Err(LuaError::RuntimeError(format!("Position must be at least 1, but was {}", position)))
+    } else {
+        // Positions past the address space can't be in the buffer either, and fail where they're used
+        Ok(usize::try_from(position - 1).unwrap_or(usize::MAX))
+    }
+}
+
+fn endian(name: Option<String>) -> LuaResult<Endian> {
+    match name {
+        None => Ok(Endian::Little),
+        Some(name) => Endian::parse(name).map_err(external_lua_error)
+    }
+}
+
+/// Registers `read_<name>([position], [endian])` and `write_<name>(value, [endian])` methods.
+/// Reads without a position, as well as all writes, happen at the cursor and advance it.
+fn add_number_methods<'lua, M, T>(methods: &mut M, name: &str, description: &str)
+    where M: UserDataMethods<'lua, Buffer>,
+          T: Number + for<'a> ToLua<'a> + for<'a> FromLua<'a> + 'static
+{
+    let ty = if name.starts_with('f') { "number" } else { "integer" };
+    let read_signature = format!("(position: integer?, endian: \"le\"|\"little\"|\"be\"|\"big\"?) -> {}", ty);
+    let write_signature = format!("(value: {}, endian: \"le\"|\"little\"|\"be\"|\"big\"?)", ty);
+
+    let read_doc = format!("Reads {} at the specified position, or at the cursor, in little endian unless specified otherwise.", description);
+    let write_doc = format!("Writes {} at the cursor, in little endian unless specified otherwise.", description);
+
+    methods.method_mut(&format!("read_{}", name), &read_signature, &read_doc, |_, this, (position, endian_name): (Option<i64>, Option<String>)| {
+        let endian = endian(endian_name)?;
+        let result = match position {
+            Some(position) => this.read::<T>(offset(position)?, endian),
+            None => this.read_next::<T>(endian)
+        };
+        result.map_err(external_lua_error)
+    });
+
+    methods.method_mut(&format!("write_{}", name), &write_signature, &write_doc, |_, this, (value, endian_name): (T, Option<String>)| {
+        this.write_next(value, endian(endian_name)?)
+            .map_err(external_lua_error)
+    });
+}
+
+impl LuaUserData for Buffer {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.method("len", "() -> integer", "Number of bytes in the buffer.", |_, this, ()| {
+            Ok(this.len())
+        });
+
+        methods.method("sub", "(i: integer, j: integer?) -> Buffer",
+            "Copies the bytes between positions `i` and `j` into a new buffer, following the rules of `string.sub`.", |_, this, (i, j): (i64, Option<i64>)| {
+            Ok(this.sub(i, j))
+        });
+
+        methods.method("byte", "(i: integer?, j: integer?) -> integer...",
+            "Returns the bytes between positions `i` and `j`, following the rules of `string.byte`.", |_, this, (i, j): (Option<i64>, Option<i64>)| {
+            Ok(Variadic::from_iter(this.byte(i, j).iter().copied()))
+        });
+
+        methods.method("tostring", "() -> string", "Returns the buffer's content as a Lua string, which is binary-safe.", |lua, this, ()| {
+            lua.create_string(this.as_bytes())
+        });
+
+        methods.method("to_string", "() -> string", "Same as `tostring`.", |lua, this, ()| {
+            lua.create_string(this.as_bytes())
+        });
+
+        methods.method("tell", "() -> integer", "Position of the cursor.", |_, this, ()| {
+            Ok(this.position() + 1)
+        });
+
+        methods.method_mut("seek", "(position: integer)", "Moves the cursor to the specified position.", |_, this, (position, ): (i64, )| {
+            this.seek(offset(position)?)
+                .map_err(external_lua_error)
+        });
+
+        methods.method_mut("skip", "(count: integer)", "Moves the cursor by the specified number of bytes, backwards if negative.", |_, this, (count, ): (i64, )| {
+            let position = (this.position() as i64).saturating_add(count);
+            if position < 0 {
+                return Err(LuaError::RuntimeError("Cannot skip before the start of the buffer".to_string()));
+            }
+            this.seek(usize::try_from(position).unwrap_or(usize::MAX))
+                .map_err(external_lua_error)
+        });
+
+        add_number_methods::<M, u8>(methods, "u8", "an unsigned 8-bit integer");
+        add_number_methods::<M, i8>(methods, "i8", "a signed 8-bit integer");
+        add_number_methods::<M, u16>(methods, "u16", "an unsigned 16-bit integer");
+        add_number_methods::<M, i16>(methods, "i16", "a signed 16-bit integer");
+        add_number_methods::<M, u32>(methods, "u32", "an unsigned 32-bit integer");
+        add_number_methods::<M, i32>(methods, "i32", "a signed 32-bit integer");
+        add_number_methods::<M, f32>(methods, "f32", "a 32-bit float");
+        add_number_methods::<M, f64>(methods, "f64", "a 64-bit float");
+
+        methods.method_mut("read_bytes", "(len: integer, position: integer?) -> Buffer",
+            "Reads the specified number of bytes at the specified position, or at the cursor, into a new buffer.", |_, this, (len, position): (usize, Option<i64>)| {
+            let bytes = match position {
+                Some(position) => this.read_bytes(offset(position)?, len).map(|bytes| bytes.to_vec()),
+                None => this.read_bytes_next(len)
+            };
+            bytes.map(Buffer::from)
+                .map_err(external_lua_error)
+        });
+
+        methods.method_mut("read_string", "(len: integer, position: integer?) -> string",
+            "Reads the specified number of bytes at the specified position, or at the cursor, into a string.", |lua, this, (len, position): (usize, Option<i64>)| {
+            let bytes = match position {
+                Some(position) => this.read_bytes(offset(position)?, len).map(|bytes| bytes.to_vec()),
+                None => this.read_bytes_next(len)
+            };
+            let bytes = bytes.map_err(external_lua_error)?;
+            lua.create_string(&bytes)
+        });
+
+        methods.method_mut("write_bytes", "(content: string|Buffer|integer[])", "Writes the specified bytes at the cursor.", |_, this, (content, ): (LuaBytes, )| {
+            this.write_bytes_next(&content.0)
+                .map_err(external_lua_error)
+        });
+
+        // Returns start and end positions of the first occurrence, like `string.find` with `plain`
+        methods.method("find", "(needle: string|Buffer|integer[], init: integer?) -> integer?, integer?",
+            "Returns the start and end positions of the first occurrence of `needle` at or after position `init`.", |_, this, (needle, init): (LuaBytes, Option<i64>)| {
+            let from = offset(init.unwrap_or(1).max(1))?;
+            match this.find(&needle.0, from) {
+                Some(start) => Ok((Some(start + 1), Some(start + needle.0.len()))),
+                None => Ok((None, None))
+            }
+        });
+
+        methods.meta_method(MetaMethod::Len, "() -> integer", "Number of bytes in the buffer.", |_, this, ()| {
+            Ok(this.len())
+        });
+
+        methods.meta_method(MetaMethod::ToString, "() -> string", "Returns the buffer's content as a Lua string.", |lua, this, ()| {
+            lua.create_string(this.as_bytes())
+        });
+
+        methods.meta_method(MetaMethod::Eq, "(other: Buffer) -> boolean", "Compares the content of two buffers.", |_, this, (other, ): (Buffer, )| {
+            Ok(this.as_bytes() == other.as_bytes())
+        });
+
+        // Either operand may be a string, so this can't be a method on `self`
+        methods.meta_function(MetaMethod::Concat, "(a: string|Buffer, b: string|Buffer) -> Buffer",
+            "Concatenates the content of buffers or strings into a new buffer.", |_, (a, b): (LuaBytes, LuaBytes)| {
+            let mut bytes = a.0;
+            bytes.extend_from_slice(&b.0);
+            Ok(Buffer::from(bytes))
+        });
+    }
+}
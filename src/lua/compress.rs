@@ -0,0 +1,43 @@
+use mlua::Lua;
+use mlua::prelude::LuaResult;
+
+use crate::buffer::Buffer;
+use crate::compression;
+use crate::compression::{Algorithm, DEFAULT_DECOMPRESSED_SIZE_LIMIT};
+use crate::lua::api::Module;
+use crate::lua::buffer::LuaBytes;
+use crate::lua::error::external_lua_error;
+
+/// Build the module's exports table, governing what is exposed to Lua.
+pub fn init(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "compress", "Compression and decompression of binary data.")?;
+
+    exports.function("compress", "(data: string|Buffer|integer[], algorithm: \"deflate\"|\"zlib\"|\"gzip\"|\"lz4\", level: integer?) -> Buffer",
+        "Compresses the data with the specified algorithm. The level ranges from 0 to 9, and is ignored by LZ4.", compress)?;
+    exports.function("decompress", "(data: string|Buffer|integer[], algorithm: \"deflate\"|\"zlib\"|\"gzip\"|\"lz4\", size_limit: integer?) -> Buffer",
+        "Decompresses the data, failing once the output exceeds the size limit, `default_size_limit` unless specified otherwise.", decompress)?;
+    exports.field("default_size_limit", "integer",
+        "Size limit of decompressed data, in bytes, guarding against zip bombs.", DEFAULT_DECOMPRESSED_SIZE_LIMIT)?;
+
+    Ok(exports)
+}
+
+//region <Exported adapter functions>
+fn compress(_: &Lua, (data, algorithm, level): (LuaBytes, String, Option<u32>)) -> LuaResult<Buffer> {
+    let algorithm = Algorithm::parse(algorithm)
+        .map_err(external_lua_error)?;
+
+    compression::compress(&data.0, algorithm, level)
+        .map(Buffer::from)
+        .map_err(external_lua_error)
+}
+
+fn decompress(_: &Lua, (data, algorithm, size_limit): (LuaBytes, String, Option<usize>)) -> LuaResult<Buffer> {
+    let algorithm = Algorithm::parse(algorithm)
+        .map_err(external_lua_error)?;
+
+    compression::decompress(&data.0, algorithm, size_limit.unwrap_or(DEFAULT_DECOMPRESSED_SIZE_LIMIT))
+        .map(Buffer::from)
+        .map_err(external_lua_error)
+}
+//endregion
@@ -0,0 +1,81 @@
+use mlua::Lua;
+use mlua::prelude::{LuaResult, LuaString, LuaTable};
+
+use crate::encoding;
+use crate::encoding::Base64Options;
+use crate::lua::api::Module;
+use crate::lua::buffer::LuaBytes;
+use crate::lua::error::external_lua_error;
+
+/// Build the module's exports table, governing what is exposed to Lua.
+///
+/// All functions accept strings, buffers, or tables of bytes, and return Lua strings, which
+/// are binary-safe in Lua 5.1.
+pub fn init(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "encoding", "Base64, hex and percent-encoding of binary data.")?;
+
+    exports.function("base64_encode", "(data: string|Buffer|integer[], options: { url_safe: boolean?, padding: boolean? }?) -> string",
+        "Encodes the data in base64, with the standard alphabet and padding unless specified otherwise.", base64_encode)?;
+    exports.function("base64_decode", "(text: string|Buffer|integer[], options: { url_safe: boolean?, padding: boolean? }?) -> string",
+        "Decodes base64 text, which has to use the same alphabet and padding as specified.", base64_decode)?;
+    exports.function("hex_encode", "(data: string|Buffer|integer[], options: { uppercase: boolean? }?) -> string",
+        "Encodes the data as hex digits, lowercase unless specified otherwise.", hex_encode)?;
+    exports.function("hex_decode", "(text: string|Buffer|integer[]) -> string",
+        "Decodes hex digits of either case.", hex_decode)?;
+    exports.function("percent_encode", "(data: string|Buffer|integer[]) -> string",
+        "Percent-encodes all bytes except unreserved characters.", percent_encode)?;
+    exports.function("percent_decode", "(text: string|Buffer|integer[]) -> string",
+        "Decodes percent-encoded text, leaving invalid escapes as they are.", percent_decode)?;
+
+    Ok(exports)
+}
+
+//region <Exported adapter functions>
+fn base64_encode(_: &Lua, (data, options): (LuaBytes, Option<LuaTable>)) -> LuaResult<String> {
+    Ok(encoding::base64_encode(&data.0, base64_options(options)?))
+}
+
+fn base64_decode<'lua>(lua: &'lua Lua, (text, options): (LuaBytes, Option<LuaTable>)) -> LuaResult<LuaString<'lua>> {
+    let data = encoding::base64_decode(&text.0, base64_options(options)?)
+        .map_err(external_lua_error)?;
+    lua.create_string(&data)
+}
+
+fn hex_encode(_: &Lua, (data, options): (LuaBytes, Option<LuaTable>)) -> LuaResult<String> {
+    let uppercase = match options {
+        None => false,
+        Some(options) => options.get::<_, Option<bool>>("uppercase")?.unwrap_or(false)
+    };
+
+    Ok(encoding::hex_encode(&data.0, uppercase))
+}
+
+fn hex_decode<'lua>(lua: &'lua Lua, (text, ): (LuaBytes, )) -> LuaResult<LuaString<'lua>> {
+    let data = encoding::hex_decode(&text.0)
+        .map_err(external_lua_error)?;
+    lua.create_string(&data)
+}
+
+fn percent_encode(_: &Lua, (data, ): (LuaBytes, )) -> LuaResult<String> {
+    Ok(encoding::percent_encode(&data.0))
+}
+
+fn percent_decode<'lua>(lua: &'lua Lua, (text, ): (LuaBytes, )) -> LuaResult<LuaString<'lua>> {
+    lua.create_string(&encoding::percent_decode(&text.0))
+}
+//endregion
+
+/// Reads `url_safe` and `padding` fields from an options table passed from Lua.
+fn base64_options(options: Option<LuaTable>) -> LuaResult<Base64Options> {
+    let mut result = Base64Options::default();
+    if let Some(options) = options {
+        if let Some(url_safe) = options.get::<_, Option<bool>>("url_safe")? {
+            result.url_safe = url_safe;
+        }
+        if let Some(padding) = options.get::<_, Option<bool>>("padding")? {
+            result.padding = padding;
+        }
+    }
+
+    Ok(result)
+}
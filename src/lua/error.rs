@@ -1,28 +1,103 @@
-use std::error::Error;
-use std::fmt::{Display, Formatter};
 use std::sync::Arc;
-use mlua::prelude::LuaError;
-
-/// Some errors include cause wrapped in a Box, which prevents automatic impl of Send and Sync
-/// traits, which makes these errors unsafe to share between threads.
-/// To fix this, just convert the errors to a string - we still get the information we need for
-/// traceability and debugging purposes.
-#[derive(Debug)]
-struct LuaErrorWrapper {
-    message: String,
+
+use mlua::{Lua, UserDataFields, UserDataMethods};
+use mlua::prelude::{LuaError, LuaFunction, LuaMultiValue, LuaResult, LuaUserData, LuaValue};
+
+use crate::error::{Error, ErrorKind};
+use crate::lua::api::{ApiFields, ApiMethods, Module};
+
+/// Build the module's exports table, governing what is exposed to Lua.
+pub fn init(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "error", "Inspection of errors raised by the other modules.")?;
+
+    exports.function("inspect", "(error: any) -> Error",
+        "Turns an error caught with `pcall` into an `Error`. Errors that didn't come from this library are of kind `Lua`.", inspect)?;
+    exports.function("pcall", "(f: function, ...: any) -> boolean, any...",
+        "Works like `pcall`, but returns an `Error` if the call failed.", pcall)?;
+    exports.class::<Error>("Error",
+        "Error raised by this library. Its kind is one of `NotFound`, `NotAllowed`, `AlreadyExists`, `InvalidInput`, \
+        `InvalidData`, `Aborted`, `Closed`, `Unsupported`, `Lua` and `Io`.")?;
+
+    Ok(exports)
 }
 
-impl Display for LuaErrorWrapper {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.message)
+//region <Exported adapter functions>
+fn inspect(_: &Lua, (error, ): (LuaValue, )) -> LuaResult<Error> {
+    let result = match error {
+        LuaValue::Error(error) => crate_error(&error),
+        LuaValue::String(message) => Error::new(ErrorKind::Lua, message.to_string_lossy()),
+        LuaValue::UserData(userdata) if userdata.is::<Error>() => userdata.borrow::<Error>()?.clone(),
+        other => Error::new(ErrorKind::Lua, format!("Error value of type {}", other.type_name())),
+    };
+    Ok(result)
+}
+
+fn pcall<'lua>(lua: &'lua Lua, (function, args): (LuaFunction<'lua>, LuaMultiValue<'lua>)) -> LuaResult<LuaMultiValue<'lua>> {
+    match function.call::<_, LuaMultiValue>(args) {
+        Ok(mut values) => {
+            values.push_front(LuaValue::Boolean(true));
+            Ok(values)
+        }
+        Err(error) => {
+            let error = lua.create_userdata(crate_error(&error))?;
+            Ok(LuaMultiValue::from_vec(vec![LuaValue::Boolean(false), LuaValue::UserData(error)]))
+        }
     }
 }
+//endregion
 
-impl Error for LuaErrorWrapper {}
+/// Converts an error of this crate, or of any of its dependencies, into one that can be raised
+/// in Lua. The crate's [Error] is kept intact within, so that its kind and path can be
+/// recovered with [crate_error].
+pub fn external_lua_error<E: Into<Error>>(error: E) -> LuaError {
+    LuaError::ExternalError(Arc::new(error.into()))
+}
 
-pub fn external_lua_error<T: Error>(error: T) -> LuaError {
-    let wrapped_error = LuaErrorWrapper {
-        message: error.to_string()
-    };
-    LuaError::ExternalError(Arc::new(wrapped_error))
+impl From<Error> for LuaError {
+    fn from(error: Error) -> Self {
+        external_lua_error(error)
+    }
+}
+
+/// Recovers the crate's [Error] from an error that was raised through Lua, or describes the
+/// error if it didn't originate from this crate.
+pub fn crate_error(error: &LuaError) -> Error {
+    match error {
+        LuaError::CallbackError { cause, .. } => crate_error(cause),
+        LuaError::ExternalError(cause) => match cause.downcast_ref::<Error>() {
+            Some(error) => error.clone(),
+            None => Error::new(ErrorKind::Io, cause),
+        },
+        LuaError::FromLuaConversionError { .. } | LuaError::ToLuaConversionError { .. } => {
+            Error::new(ErrorKind::InvalidInput, error)
+        }
+        LuaError::UserDataDestructed | LuaError::CallbackDestructed => Error::new(ErrorKind::Closed, error),
+        other => Error::new(ErrorKind::Lua, other),
+    }
+}
+
+impl LuaUserData for Error {
+    fn add_fields<'lua, F: UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.field_get("kind", "string", "Category of the error, to tell failures apart without matching on messages.", |_, this| {
+            Ok(this.kind().name())
+        });
+
+        fields.field_get("message", "string", "Description of the error.", |_, this| {
+            Ok(this.message().to_string())
+        });
+
+        fields.field_get("path", "string?", "Path the error concerns, if any.", |_, this| {
+            Ok(this.path().map(|path| path.to_string_lossy().replace('\\', "/")))
+        });
+
+        fields.field_get("causes", "string[]", "Messages of the errors that led to this one, starting with the most immediate.", |_, this| {
+            Ok(this.causes().to_vec())
+        });
+    }
+
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.meta_method(mlua::MetaMethod::ToString, "() -> string", "Kind and message of the error, along with its path and causes.", |_, this, ()| {
+            Ok(format!("{}: {}", this.kind().name(), this))
+        });
+    }
 }
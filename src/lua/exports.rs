@@ -1,14 +1,365 @@
+use std::collections::HashSet;
+
 use mlua::Lua;
-use mlua::prelude::{LuaResult, LuaTable};
+use mlua::prelude::{LuaResult, LuaTable, LuaValue};
 
+use crate::error::{Error, ErrorKind};
 use crate::lua;
+use crate::lua::api::{Module, ModuleDoc};
+use crate::lua::error::external_lua_error;
+
+/// Name of the global variable the exports table is published under.
+pub const GLOBAL_NAME: &str = "itb_rs";
+
+/// Version of this library, reported to Lua as `itb_rs.version`.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the ftldat library this one was built with.
+pub const FTLDAT_VERSION: &str = env!("ITB_RS_FTLDAT_VERSION");
+
+type ModuleInit = fn(&Lua) -> LuaResult<Module<'_>>;
+
+/// Modules exposed to Lua. Modules whose cargo feature is disabled are left out, and so are
+/// absent from `itb_rs.features`.
+const MODULES: &[ModuleInit] = &[
+    #[cfg(feature = "io")]
+    lua::io::init,
+    #[cfg(feature = "ftldat")]
+    lua::ftldat::init,
+    #[cfg(feature = "buffer")]
+    lua::buffer::init,
+    #[cfg(feature = "compress")]
+    lua::compress::init,
+    #[cfg(feature = "encoding")]
+    lua::encoding::init,
+    #[cfg(feature = "task")]
+    lua::task::init,
+    #[cfg(feature = "mods")]
+    lua::mods::init,
+    #[cfg(feature = "semver")]
+    lua::semver::init,
+    lua::error::init,
+];
+
+/// Cargo features enabled for modules exposed to Lua, which are named the same as their modules.
+/// Modules that are always exposed aren't listed.
+const FEATURES: &[&str] = &[
+    #[cfg(feature = "io")]
+    "io",
+    #[cfg(feature = "ftldat")]
+    "ftldat",
+    #[cfg(feature = "buffer")]
+    "buffer",
+    #[cfg(feature = "compress")]
+    "compress",
+    #[cfg(feature = "encoding")]
+    "encoding",
+    #[cfg(feature = "task")]
+    "task",
+    #[cfg(feature = "mods")]
+    "mods",
+    #[cfg(feature = "semver")]
+    "semver",
+];
 
 /// Build the module's exports table, governing what is exposed to Lua.
-pub fn init(lua: &Lua) -> LuaResult<LuaTable> {
-    let exports = lua.create_table()?;
+pub fn init(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+    Ok(module(lua)?.into_table())
+}
+
+/// Registers everything exposed to Lua, along with its description.
+pub fn module(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, GLOBAL_NAME, "Lua bindings of utilities used in development of Into the Breach mods.")?;
+
+    for init_module in MODULES {
+        exports.module(init_module(lua)?)?;
+    }
+
+    exports.field("version", "{ itb_rs: string, ftldat: string, major: integer, minor: integer, patch: integer }",
+        "Version of this library, and of the ftldat library it was built with.", version(lua)?)?;
+    exports.field("features", "string[]", "Names of the optional modules compiled into this library, matching the cargo features that enable them.", FEATURES)?;
+    exports.function("require_version", "(requirement: string) -> string",
+        "Fails with `Unsupported` unless this library's version satisfies the requirement, eg. `>=0.2` or \
+        `^0.1, <0.1.5`, read as Cargo does. Returns the version.", require_version)?;
 
-    exports.set("io", lua::io::init(lua)?)?;
-    exports.set("ftldat", lua::ftldat::init(lua)?)?;
+    // `has` looks names up in the exports table itself, so that it can't disagree with it
+    let exports_key = lua.create_registry_value(exports.clone_table())?;
+    let methods = class_methods(exports.doc());
+    exports.function("has", "(name: string) -> boolean",
+        "Whether this library has a module, function or field under the dot-separated path, eg. `io.watch`, or a \
+        method, eg. `Package:remove`.", move |lua, (name, ): (String, )| {
+        if name.contains(':') {
+            return Ok(methods.contains(&name));
+        }
+        has(lua.registry_value(&exports_key)?, &name)
+    })?;
 
     Ok(exports)
 }
+
+//region <Exported adapter functions>
+fn require_version(_: &Lua, (requirement, ): (String, )) -> LuaResult<String> {
+    check_version(&requirement)
+        .map_err(external_lua_error)?;
+    Ok(VERSION.to_string())
+}
+//endregion
+
+fn version(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+    let result = lua.create_table()?;
+    result.set("itb_rs", VERSION)?;
+    result.set("ftldat", FTLDAT_VERSION)?;
+    let (major, minor, patch) = version_numbers()
+        .map_err(external_lua_error)?;
+    result.set("major", major)?;
+    result.set("minor", minor)?;
+    result.set("patch", patch)?;
+    Ok(result)
+}
+
+/// Major, minor and patch numbers of this library's version. They're taken from cargo rather
+/// than parsed, so that this works without the semver module.
+fn version_numbers() -> crate::error::Result<(u64, u64, u64)> {
+    let parse = |part: &str| part.parse::<u64>()
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error));
+    Ok((
+        parse(env!("CARGO_PKG_VERSION_MAJOR"))?,
+        parse(env!("CARGO_PKG_VERSION_MINOR"))?,
+        parse(env!("CARGO_PKG_VERSION_PATCH"))?,
+    ))
+}
+
+/// Lists methods of all documented classes, in the form of `Class:method`.
+fn class_methods(doc: &ModuleDoc) -> HashSet<String> {
+    let mut result = HashSet::new();
+    doc.walk(&mut |_, module| {
+        for class in &module.classes {
+            for method in &class.methods {
+                result.insert(format!("{}:{}", class.name, method.name));
+            }
+        }
+    });
+    result
+}
+
+/// Checks whether the exports table has a non-nil value under the specified dot-separated path,
+/// eg. `io`, `ftldat.read_package` or `io.safe.file`.
+fn has(exports: LuaTable, name: &str) -> LuaResult<bool> {
+    let mut current = LuaValue::Table(exports);
+    for segment in name.split('.') {
+        current = match current {
+            LuaValue::Table(table) => table.get(segment)?,
+            _ => return Ok(false),
+        };
+    }
+    Ok(current != LuaValue::Nil)
+}
+
+/// Fails unless this library's version satisfies the specified requirement, eg. `>=0.2` or
+/// `^0.1, <0.1.5`.
+pub fn check_version(requirement: &str) -> crate::error::Result<()> {
+    if requirement_matches(requirement, version_numbers()?)? {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::Unsupported, format!(
+            "itb_rs version {} doesn't satisfy the requirement '{}'", VERSION, requirement.trim()
+        )))
+    }
+}
+
+/// Operator of a comparator in a version requirement.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+}
+
+/// Whether the release version satisfies the requirement: comma-separated comparators made of an
+/// operator (`=`, `>`, `>=`, `<`, `<=`, `~`, or `^`, the default) and a version whose minor and
+/// patch numbers may be left out, or `*`. Comparators are read as Cargo does, so that this agrees
+/// with `itb_rs.semver.matches` without depending on the semver module. Pre-releases aren't
+/// supported, since this library doesn't have any.
+fn requirement_matches(requirement: &str, version: (u64, u64, u64)) -> crate::error::Result<bool> {
+    let malformed = || Error::new(ErrorKind::InvalidInput, format!("Malformed version requirement '{}'", requirement.trim()));
+
+    let mut result = true;
+    for comparator in requirement.split(',') {
+        let comparator = comparator.trim();
+        if comparator == "*" {
+            continue;
+        }
+
+        let (op, numbers) = [(">=", Op::GreaterEq), ("<=", Op::LessEq), (">", Op::Greater), ("<", Op::Less),
+            ("=", Op::Exact), ("~", Op::Tilde), ("^", Op::Caret)]
+            .into_iter()
+            .find_map(|(prefix, op)| comparator.strip_prefix(prefix).map(|numbers| (op, numbers.trim_start())))
+            .unwrap_or((Op::Caret, comparator));
+
+        let mut parts = numbers.split('.');
+        let mut number = |required: bool| match parts.next() {
+            None | Some("*" | "x" | "X") if !required => Ok(None),
+            Some(part) if !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit()) => {
+                part.parse::<u64>().map(Some).map_err(|_| malformed())
+            }
+            _ => Err(malformed()),
+        };
+        let major = number(true)?.ok_or_else(malformed)?;
+        let minor = number(false)?;
+        let patch = match minor {
+            Some(_) => number(false)?,
+            None => None,
+        };
+        if parts.next().is_some() {
+            return Err(malformed());
+        }
+
+        result &= comparator_matches(op, major, minor, patch, version);
+    }
+    Ok(result)
+}
+
+/// Whether the version satisfies a single comparator, following the semver crate's rules for
+/// versions with missing numbers.
+fn comparator_matches(op: Op, major: u64, minor: Option<u64>, patch: Option<u64>, version: (u64, u64, u64)) -> bool {
+    let (version_major, version_minor, version_patch) = version;
+    let exact = version_major == major
+        && minor.is_none_or(|minor| version_minor == minor)
+        && patch.is_none_or(|patch| version_patch == patch);
+    let greater = match (minor, patch) {
+        _ if version_major != major => version_major > major,
+        (None, _) => false,
+        (Some(minor), _) if version_minor != minor => version_minor > minor,
+        (_, None) => false,
+        (_, Some(patch)) => version_patch > patch,
+    };
+    let less = match (minor, patch) {
+        _ if version_major != major => version_major < major,
+        (None, _) => false,
+        (Some(minor), _) if version_minor != minor => version_minor < minor,
+        (_, None) => false,
+        (_, Some(patch)) => version_patch < patch,
+    };
+
+    match op {
+        Op::Exact => exact,
+        Op::Greater => greater,
+        Op::GreaterEq => exact || greater,
+        Op::Less => less,
+        Op::LessEq => exact || less,
+        Op::Tilde => version_major == major
+            && minor.is_none_or(|minor| version_minor == minor)
+            && patch.is_none_or(|patch| version_patch >= patch),
+        Op::Caret => {
+            let Some(minor) = minor else {
+                return version_major == major;
+            };
+            match patch {
+                _ if version_major != major => false,
+                None if major > 0 => version_minor >= minor,
+                None => version_minor == minor,
+                Some(patch) if major > 0 => version_minor > minor || (version_minor == minor && version_patch >= patch),
+                Some(patch) if minor > 0 => version_minor == minor && version_patch >= patch,
+                Some(patch) => version_minor == minor && version_patch == patch,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use crate::lua::exports::{check_version, requirement_matches, VERSION};
+
+    #[test]
+    fn check_version_should_accept_satisfied_requirements() {
+        assert!(check_version(&format!("={}", VERSION)).is_ok());
+        assert!(check_version(">=0.1").is_ok());
+    }
+
+    #[test]
+    fn check_version_should_reject_unsatisfied_and_malformed_requirements() {
+        assert_eq!(ErrorKind::Unsupported, check_version(">=100").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, check_version("newest").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, check_version("").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, check_version(">=1.2.3.4").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, check_version("^1.*.3").unwrap_err().kind());
+    }
+
+    #[test]
+    fn requirements_should_be_read_as_cargo_does() {
+        let matches = |requirement, version| requirement_matches(requirement, version).unwrap();
+
+        assert!(matches("1.2", (1, 9, 0)));
+        assert!(!matches("1.2", (2, 0, 0)));
+        assert!(matches("^0.2.3", (0, 2, 9)));
+        assert!(!matches("^0.2.3", (0, 3, 0)));
+        assert!(!matches("^0.0.3", (0, 0, 4)));
+        assert!(matches("~1.2", (1, 2, 7)));
+        assert!(!matches("~1.2.3", (1, 3, 0)));
+        assert!(matches(">1.2", (1, 3, 0)));
+        assert!(!matches(">1.2", (1, 2, 9)));
+        assert!(matches("<=1.2", (1, 2, 9)));
+        assert!(matches("=1", (1, 5, 0)));
+        assert!(matches(">= 0.1, < 0.2", (0, 1, 5)));
+        assert!(matches("1.x", (1, 5, 0)));
+        assert!(matches("*", (7, 0, 0)));
+    }
+
+    #[test]
+    #[cfg(feature = "semver")]
+    fn requirements_should_agree_with_semver() {
+        use semver::{Version, VersionReq};
+
+        let numbers = ["0", "1", "2"];
+        let mut versions = Vec::new();
+        for major in 0..3 {
+            for minor in 0..3 {
+                for patch in 0..3 {
+                    versions.push((major, minor, patch));
+                }
+            }
+        }
+        for op in ["", "=", ">", ">=", "<", "<=", "~", "^"] {
+            for major in numbers {
+                for minor in [None, Some("0"), Some("1"), Some("2")] {
+                    for patch in [None, Some("0"), Some("1"), Some("2")] {
+                        let text = match (minor, patch) {
+                            (None, _) => format!("{}{}", op, major),
+                            (Some(minor), None) => format!("{}{}.{}", op, major, minor),
+                            (Some(minor), Some(patch)) => format!("{}{}.{}.{}", op, major, minor, patch),
+                        };
+                        let requirement = VersionReq::parse(&text).unwrap();
+                        for &(major, minor, patch) in &versions {
+                            assert_eq!(requirement.matches(&Version::new(major, minor, patch)),
+                                       requirement_matches(&text, (major, minor, patch)).unwrap(),
+                                       "{} against {}.{}.{}", text, major, minor, patch);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "vendored")]
+    fn exports_should_contain_enabled_modules() {
+        let lua = mlua::Lua::new();
+        let exports = crate::lua::exports::init(&lua).unwrap();
+
+        assert_eq!(cfg!(feature = "io"), exports.contains_key("io").unwrap());
+        assert_eq!(cfg!(feature = "ftldat"), exports.contains_key("ftldat").unwrap());
+        assert_eq!(cfg!(feature = "buffer"), exports.contains_key("buffer").unwrap());
+        assert_eq!(cfg!(feature = "compress"), exports.contains_key("compress").unwrap());
+        assert_eq!(cfg!(feature = "encoding"), exports.contains_key("encoding").unwrap());
+        assert_eq!(cfg!(feature = "task"), exports.contains_key("task").unwrap());
+        assert_eq!(cfg!(feature = "mods"), exports.contains_key("mods").unwrap());
+        assert_eq!(cfg!(feature = "semver"), exports.contains_key("semver").unwrap());
+        assert!(exports.contains_key("error").unwrap());
+        assert!(!crate::lua::exports::FEATURES.contains(&"error"));
+    }
+}
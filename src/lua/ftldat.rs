@@ -1,108 +1,430 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-use ftldat::error::PackageReadError;
 use ftldat::{Package, PackageEntry};
+use memmap2::Mmap;
 use mlua::{Lua, UserDataMethods};
-use mlua::prelude::{LuaResult, LuaTable, LuaUserData};
+use mlua::prelude::{LuaAnyUserData, LuaFunction, LuaResult, LuaTable, LuaUserData, LuaValue};
+use crate::buffer::Buffer;
+use crate::io::Directory;
+use crate::error::{Error, ErrorKind, WithPath};
+use crate::lua::api::{ApiMethods, Module};
+use crate::lua::buffer::LuaBytes;
 use crate::lua::error::external_lua_error;
+use crate::lua::io::{inner_path_filters, sandbox, sandboxed_directory, sandboxed_file};
+use crate::lua::progress::LuaProgress;
+use crate::package;
+use crate::package::{directory_files, IndexEntry, InnerPathFilter, MergeStrategy, PackageFormat, read_index, salvage, verify_path};
+use crate::progress::{aborted, Progress};
 
 /// Build the module's exports table, governing what is exposed to Lua.
-pub fn init(lua: &Lua) -> LuaResult<LuaTable> {
-    let exports = lua.create_table()?;
+pub fn init(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "ftldat",
+        "Reading and writing of the .dat (Into the Breach, Faster than Light before 1.6.1) and .pkg (Faster than Light \
+        since 1.6.1) package formats. Formats are named `dat` or `itb`, and `pkg` or `ftl`; `auto` detects them. Paths \
+        are confined to the sandbox, and relative ones resolved against the game directory, as in the io module.")?;
 
-    exports.set("read_package", lua.create_function(read)?)?;
-    exports.set("new_package", lua.create_function(new)?)?;
+    exports.function("read_package", "(path: string, options: { format: string? }?) -> Package",
+        "Reads the package at the specified path, detecting its format unless specified otherwise.", read)?;
+    exports.function("new_package", "(options: { format: string? }?) -> Package",
+        "Creates an empty package, written out in the specified format (`dat` by default).", new)?;
+    exports.function("detect_format", "(path: string) -> string",
+        "Detects the format of the package at the specified path, returning `dat` or `pkg`.", detect_format)?;
+    exports.function("diff", "(a: Package, b: Package) -> { added: string[], removed: string[], modified: string[] }",
+        "Lists inner paths present only in `b`, only in `a`, and in both but with different content.", diff)?;
+    exports.function("merge", "(base: Package, other: Package, strategy: \"error\"|\"keep_base\"|\"take_other\"?) -> { added: string[], replaced: string[], kept: string[] }",
+        "Copies entries of `other` into `base`, resolving entries that differ according to the strategy. With `error`, \
+        the default, `base` is left untouched if any entries conflict.", merge)?;
+    exports.function("verify", "(path: string, options: { format: string?, repair: string? }?) -> { ok: boolean, format: string, entry_count: integer, readable_count: integer, problems: { kind: string, entry: integer?, path: string?, message: string }[], repaired_count: integer? }",
+        "Checks the package at the specified path for corruption. If `repair` is set to a path, all readable entries \
+        are salvaged into a new package written to that path.", verify)?;
+    exports.class::<LuaPackageWrapper>("Package",
+        "Package held in memory. Entries read from a file are only read once their content is needed. Writing the \
+        package out closes it, after which its methods raise `Closed` errors.")?;
+    exports.safe_variants()?;
 
     Ok(exports)
 }
 
 //region <Exported adapter functions>
-fn new(_: &Lua, (): ()) -> LuaResult<LuaPackageWrapper> {
-    Ok(LuaPackageWrapper::new())
+fn new(_: &Lua, (options, ): (Option<LuaTable>, )) -> LuaResult<LuaPackageWrapper> {
+    let format = package_format(options)?
+        .unwrap_or(PackageFormat::Dat);
+    Ok(LuaPackageWrapper::new(format))
 }
 
-fn read(_: &Lua, (path, ): (String, )) -> LuaResult<LuaPackageWrapper> {
-    LuaPackageWrapper::read_from_path(&path)
+fn read(lua: &Lua, (path, options): (String, Option<LuaTable>)) -> LuaResult<LuaPackageWrapper> {
+    let file = sandboxed_file(lua, path)?;
+    let format = match package_format(options)? {
+        Some(format) => format,
+        None => PackageFormat::detect(&file.path).map_err(external_lua_error)?
+    };
+
+    LuaPackageWrapper::read_from_path(&file.path, format)
         .map_err(external_lua_error)
 }
+
+fn detect_format(lua: &Lua, (path, ): (String, )) -> LuaResult<&'static str> {
+    PackageFormat::detect(&sandboxed_file(lua, path)?.path)
+        .map(|format| format.name())
+        .map_err(external_lua_error)
+}
+
+fn diff<'lua>(lua: &'lua Lua, (a, b): (LuaAnyUserData<'lua>, LuaAnyUserData<'lua>)) -> LuaResult<LuaTable<'lua>> {
+    let a = a.borrow::<LuaPackageWrapper>()?;
+    let b = b.borrow::<LuaPackageWrapper>()?;
+    let diff = package::diff(a.package_ref()?, b.package_ref()?)
+        .map_err(external_lua_error)?;
+
+    let result = lua.create_table()?;
+    result.set("added", diff.added)?;
+    result.set("removed", diff.removed)?;
+    result.set("modified", diff.modified)?;
+    Ok(result)
+}
+
+fn merge<'lua>(lua: &'lua Lua, (base, other, strategy): (LuaAnyUserData<'lua>, LuaAnyUserData<'lua>, Option<String>)) -> LuaResult<LuaTable<'lua>> {
+    let strategy = MergeStrategy::parse(strategy.as_deref().unwrap_or("error"))
+        .map_err(external_lua_error)?;
+    let mut base = base.borrow_mut::<LuaPackageWrapper>()?;
+    let other = other.borrow::<LuaPackageWrapper>()?;
+    let merge = package::merge(base.package_mut()?, other.package_ref()?, strategy)
+        .map_err(external_lua_error)?;
+
+    for inner_path in &merge.replaced {
+        base.forget(inner_path);
+    }
+
+    let result = lua.create_table()?;
+    result.set("added", merge.added)?;
+    result.set("replaced", merge.replaced)?;
+    result.set("kept", merge.kept)?;
+    Ok(result)
+}
+
+/// Checks the package at the specified path for corruption, returning a report of all problems
+/// found. If `options.repair` is set to a path, all readable entries are salvaged into a new
+/// package written to that path.
+fn verify<'lua>(lua: &'lua Lua, (path, options): (String, Option<LuaTable<'lua>>)) -> LuaResult<LuaTable<'lua>> {
+    let file = sandboxed_file(lua, path)?;
+    let repair_file = match &options {
+        None => None,
+        Some(options) => options.get::<_, Option<String>>("repair")?
+            .map(|repair_path| sandboxed_file(lua, repair_path))
+            .transpose()?
+    };
+    let format = match package_format(options)? {
+        Some(format) => format,
+        None => PackageFormat::detect(&file.path).map_err(external_lua_error)?
+    };
+
+    let (report, bytes) = verify_path(&file.path, format)
+        .map_err(external_lua_error)?;
+
+    let problems = lua.create_table()?;
+    for problem in &report.problems {
+        let entry = lua.create_table()?;
+        entry.set("kind", problem.kind.name())?;
+        // Convert to Lua's 1-based indexing
+        entry.set("entry", problem.entry.map(|index| index + 1))?;
+        entry.set("path", problem.inner_path.clone())?;
+        entry.set("message", problem.message.clone())?;
+        problems.push(entry)?;
+    }
+
+    let result = lua.create_table()?;
+    result.set("ok", report.is_ok())?;
+    result.set("format", report.format.name())?;
+    result.set("entry_count", report.declared_entry_count)?;
+    result.set("readable_count", report.readable.len())?;
+    result.set("problems", problems)?;
+
+    if let Some(repair_file) = repair_file {
+        let package = salvage(&bytes, &report);
+        drop(bytes);
+        let repaired_count = package.entry_count();
+        format.write(package, &repair_file.path)
+            .map_err(external_lua_error)?;
+        result.set("repaired_count", repaired_count)?;
+    }
+
+    Ok(result)
+}
 //endregion
 
-struct LuaPackageWrapper(Option<Package>);
+/// Reads the `format` field from an options table passed from Lua.
+/// Returns `None` if the format was not specified, or is `auto`.
+pub(crate) fn package_format(options: Option<LuaTable>) -> LuaResult<Option<PackageFormat>> {
+    let maybe_name: Option<String> = match options {
+        None => None,
+        Some(options) => options.get("format")?
+    };
+
+    match maybe_name {
+        None => Ok(None),
+        Some(name) => PackageFormat::parse(name).map_err(external_lua_error)
+    }
+}
+
+fn closed() -> Error {
+    Error::new(ErrorKind::Closed, "This instance has already been written out, and is no longer open.")
+}
+
+struct LuaPackageWrapper {
+    package: Option<Package>,
+    /// Format the package was read in, used when writing it out unless specified otherwise.
+    format: PackageFormat,
+    /// Memory map of the file this package was read from, to recreate entries listed in `index`.
+    source: Option<Rc<Mmap>>,
+    /// Locations of entries within the file this package was read from, keyed by inner path.
+    /// Entries that were added or replaced since then are not present here.
+    index: HashMap<String, IndexEntry>,
+    /// Files that entries added from the file system are read from, keyed by inner path.
+    /// Entries that were replaced since then are not present here.
+    files: HashMap<String, PathBuf>,
+}
 
 impl LuaPackageWrapper {
-    fn new() -> LuaPackageWrapper {
-        LuaPackageWrapper(Some(Package::new()))
+    fn new(format: PackageFormat) -> LuaPackageWrapper {
+        LuaPackageWrapper {
+            package: Some(Package::new()),
+            format,
+            source: None,
+            index: HashMap::new(),
+            files: HashMap::new(),
+        }
     }
 
-    fn package(&mut self) -> Package {
-        self.0.take()
-            .expect("This instance has already been written out, and is no longer open.")
+    fn package(&mut self) -> crate::error::Result<Package> {
+        self.forget_all();
+        self.package.take()
+            .ok_or_else(closed)
     }
 
-    fn package_ref(&self) -> &Package {
-        & self.0.as_ref()
-            .expect("This instance has already been written out, and is no longer open.")
+    fn package_ref(&self) -> crate::error::Result<&Package> {
+        self.package.as_ref()
+            .ok_or_else(closed)
     }
 
-    fn package_mut(&mut self) -> &mut Package {
-        self.0.as_mut()
-            .expect("This instance has already been written out, and is no longer open.")
+    fn package_mut(&mut self) -> crate::error::Result<&mut Package> {
+        self.package.as_mut()
+            .ok_or_else(closed)
     }
 
-    fn read_from_path<P: AsRef<Path>>(path: P) -> Result<LuaPackageWrapper, PackageReadError> {
-        Package::from_path_dat(path)
-            .map(|package| {
-                LuaPackageWrapper(Some(package))
-            })
+    fn read_from_path<P: AsRef<Path>>(path: P, format: PackageFormat) -> crate::error::Result<LuaPackageWrapper> {
+        let package = format.read(&path)?;
+        let index = read_index(&path, format)?
+            .into_iter()
+            .map(|entry| (entry.inner_path.clone(), entry))
+            .collect();
+        let file = std::fs::File::open(&path).with_path(&path)?;
+        // SAFETY: same as ftldat's own readers, which map the file for the package's entries
+        let source = unsafe { Mmap::map(&file) }.with_path(&path)?;
+
+        Ok(LuaPackageWrapper {
+            package: Some(package),
+            format,
+            source: Some(Rc::new(source)),
+            index,
+            files: HashMap::new(),
+        })
+    }
+
+    /// Forgets where the content of the entry under the specified inner path came from, once
+    /// it's been replaced or removed.
+    fn forget<S: AsRef<str>>(&mut self, inner_path: S) {
+        self.index.remove(inner_path.as_ref());
+        self.files.remove(inner_path.as_ref());
+    }
+
+    fn forget_all(&mut self) {
+        self.index.clear();
+        self.files.clear();
+    }
+
+    /// Recreates the entry under the specified inner path from the file its content comes from,
+    /// without reading it. Returns `None` for entries whose content is held in memory.
+    fn lazy_entry(&self, inner_path: &str) -> Option<PackageEntry> {
+        if let Some(path) = self.files.get(inner_path) {
+            return Some(PackageEntry::from_file(inner_path, path));
+        }
+        let source = self.source.as_ref()?;
+        self.index.get(inner_path)
+            .map(|entry| PackageEntry::from_memory_mapped_file(inner_path, Rc::clone(source), entry.offset, entry.size))
+    }
+
+    /// Removes the entry under the specified inner path, returning whether there was one.
+    fn remove(&mut self, inner_path: &str) -> crate::error::Result<bool> {
+        let mut package = self.package.take()
+            .ok_or_else(closed)?;
+        let result = package::remove_entry(&mut package, inner_path, |inner_path| self.lazy_entry(inner_path));
+        self.package = Some(package);
+        self.forget(inner_path);
+        result
+    }
+
+    /// Returns the size of the entry under the specified inner path, or `None` if there's no
+    /// such entry. Entries read from a file report their size without reading their content.
+    fn entry_size<S: AsRef<str>>(&self, inner_path: S) -> crate::error::Result<Option<u64>> {
+        let inner_path = inner_path.as_ref();
+        match self.index.get(inner_path) {
+            Some(entry) => Ok(Some(entry.size)),
+            None => Ok(self.package_ref()?.content_by_path(inner_path)
+                .map(|content| content.len() as u64))
+        }
+    }
+
+    /// Returns the offset of the entry's content within the file this package was read from,
+    /// or `None` if the entry didn't come from that file.
+    fn entry_offset<S: AsRef<str>>(&self, inner_path: S) -> Option<u64> {
+        self.index.get(inner_path.as_ref())
+            .map(|entry| entry.offset)
+    }
+
+    /// Puts all files within the specified directory into this package, under the specified
+    /// prefix. Returns the number of entries that were added and replaced, respectively.
+    ///
+    /// Progress is reported before each file is added; if aborted, the package is left unchanged.
+    fn add_directory(&mut self, dir: &Directory, inner_prefix: &str, filters: &[InnerPathFilter], progress: &mut Progress) -> crate::error::Result<(usize, usize)> {
+        let files = directory_files(dir, inner_prefix, filters)?;
+        for (done, (inner_path, _)) in files.iter().enumerate() {
+            if !progress(done, files.len(), inner_path) {
+                return Err(aborted());
+            }
+        }
+
+        let mut added = 0;
+        let mut replaced = 0;
+        for (inner_path, file) in &files {
+            self.forget(inner_path);
+            self.files.insert(inner_path.clone(), file.path.clone());
+            if self.package_ref()?.entry_exists(inner_path) {
+                replaced += 1;
+            } else {
+                added += 1;
+            }
+            self.package_mut()?.put_entry(PackageEntry::from_file(inner_path, &file.path));
+        }
+
+        progress(files.len(), files.len(), "");
+        Ok((added, replaced))
+    }
+
+    /// Writes this package to the specified path, and closes it.
+    ///
+    /// ftldat writes a package in one go, so the content of entries added from files is read
+    /// beforehand, reporting progress before each entry. If aborted, or if a file can't be read,
+    /// the package is left unchanged and open.
+    fn write(&mut self, path: &Path, format: PackageFormat, progress: &mut Progress) -> crate::error::Result<()> {
+        let inner_paths = self.package_ref()?.inner_paths();
+        let mut contents = Vec::new();
+        for (done, inner_path) in inner_paths.iter().enumerate() {
+            if !progress(done, inner_paths.len(), inner_path) {
+                return Err(aborted());
+            }
+            if let Some(source) = self.files.get(inner_path) {
+                contents.push((inner_path, std::fs::read(source).with_path(source)?));
+            }
+        }
+
+        let mut package = self.package()?;
+        for (inner_path, content) in contents {
+            package.put_entry(PackageEntry::from_byte_array(inner_path, content));
+        }
+        format.write(package, path)?;
+        progress(inner_paths.len(), inner_paths.len(), "");
+        Ok(())
+    }
+
+    fn inner_paths(&self, maybe_filter: Option<String>) -> crate::error::Result<Vec<String>> {
+        let inner_paths = self.package_ref()?.inner_paths();
+        match maybe_filter {
+            None => Ok(inner_paths),
+            Some(filter) => {
+                let filter = InnerPathFilter::parse(filter)?;
+                Ok(inner_paths.into_iter()
+                    .filter(|inner_path| filter.matches(inner_path))
+                    .collect())
+            }
+        }
     }
 }
 
 impl LuaUserData for LuaPackageWrapper {
     fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
-        methods.add_method_mut("to_file", |_, this, (path, ): (String, )| {
-            Package::into_path_dat(this.package(), &path)
-                .map_err(external_lua_error)
+        methods.method_mut("to_file", "(path: string, options: { format: string? }?, callback: fun(done: integer, total: integer, path: string): boolean??)",
+            "Writes the package to the specified path, in the format it was read or created in unless specified otherwise, \
+            and closes it. Progress is reported as the content of each entry is read; aborting through the callback \
+            keeps the package open.", |lua, this, (path, options, callback): (String, Option<LuaTable>, Option<LuaFunction>)| {
+            let file = sandboxed_file(lua, path)?;
+            let format = package_format(options)?
+                .unwrap_or(this.format);
+
+            let mut progress = LuaProgress::new(callback);
+            let result = this.write(&file.path, format, &mut |done, total, path| progress.report(done, total, path));
+            progress.finish(result)
         });
 
-        methods.add_method_mut("destroy", |_, this, ()| {
-            if this.0.is_some() {
-                this.0.take();
+        methods.method("format", "() -> string", "Format the package is written out in by default, `dat` or `pkg`.", |_, this, ()| {
+            Ok(this.format.name())
+        });
+
+        methods.method_mut("destroy", "()", "Closes the package, releasing its content.", |_, this, ()| {
+            if this.package.is_some() {
+                this.package.take();
             }
+            this.forget_all();
             Ok(())
         });
 
-        methods.add_method_mut("add_entry_from_string", |_, this, (path, content): (String, String)| {
-            this.package_mut().add_entry(PackageEntry::from_string(path, content))
+        methods.method_mut("add_entry_from_string", "(path: string, content: string)",
+            "Adds an entry, failing with `AlreadyExists` if there's one under the same path already.", |_, this, (path, content): (String, String)| {
+            this.package_mut()?.add_entry(PackageEntry::from_string(path, content))
                 .map_err(external_lua_error)
         });
 
-        methods.add_method_mut("add_entry_from_byte_array", |_, this, (path, content): (String, Vec<u8>)| {
-            this.package_mut().add_entry(PackageEntry::from_byte_array(path, content))
+        methods.method_mut("add_entry_from_byte_array", "(path: string, content: string|Buffer|integer[])",
+            "Same as `add_entry_from_string`, for binary content.", |_, this, (path, content): (String, LuaBytes)| {
+            this.package_mut()?.add_entry(PackageEntry::from_byte_array(path, content.0))
                 .map_err(external_lua_error)
         });
 
-        methods.add_method_mut("add_entry_from_file", |_, this, (path, source_path): (String, String)| {
-            this.package_mut().add_entry(PackageEntry::from_file(path, source_path))
-                .map_err(external_lua_error)
+        methods.method_mut("add_entry_from_file", "(path: string, source_path: string)",
+            "Adds an entry with the content of the file at `source_path`, which is read once the package is written out.", |lua, this, (path, source_path): (String, String)| {
+            let source = sandboxed_file(lua, source_path)?;
+            this.package_mut()?.add_entry(PackageEntry::from_file(&path, &source.path))
+                .map_err(external_lua_error)?;
+            this.files.insert(path, source.path);
+            Ok(())
         });
 
-        methods.add_method_mut("put_entry_from_string", |_, this, (path, content): (String, String)| {
-            this.package_mut().put_entry(PackageEntry::from_string(path, content));
+        methods.method_mut("put_entry_from_string", "(path: string, content: string)",
+            "Adds an entry, replacing the one under the same path, if any.", |_, this, (path, content): (String, String)| {
+            this.forget(&path);
+            this.package_mut()?.put_entry(PackageEntry::from_string(path, content));
             Ok(())
         });
 
-        methods.add_method_mut("put_entry_from_byte_array", |_, this, (path, content): (String, Vec<u8>)| {
-            this.package_mut().put_entry(PackageEntry::from_byte_array(path, content));
+        methods.method_mut("put_entry_from_byte_array", "(path: string, content: string|Buffer|integer[])",
+            "Same as `put_entry_from_string`, for binary content.", |_, this, (path, content): (String, LuaBytes)| {
+            this.forget(&path);
+            this.package_mut()?.put_entry(PackageEntry::from_byte_array(path, content.0));
             Ok(())
         });
 
-        methods.add_method_mut("put_entry_from_file", |_, this, (path, source_path): (String, String)| {
-            this.package_mut().put_entry(PackageEntry::from_file(path, source_path));
+        methods.method_mut("put_entry_from_file", "(path: string, source_path: string)",
+            "Same as `add_entry_from_file`, but replaces the entry under the same path, if any.", |lua, this, (path, source_path): (String, String)| {
+            let source = sandboxed_file(lua, source_path)?;
+            this.forget(&path);
+            this.package_mut()?.put_entry(PackageEntry::from_file(&path, &source.path));
+            this.files.insert(path, source.path);
             Ok(())
         });
 
-        methods.add_method("read_content_as_string", |_, this, (path, ): (String, )| {
-            let maybe_bytes = this.package_ref().content_by_path(path);
+        methods.method("read_content_as_string", "(path: string) -> string?",
+            "Content of the entry under the specified path as UTF-8 text, or `nil` if there's no such entry.", |_, this, (path, ): (String, )| {
+            let maybe_bytes = this.package_ref()?.content_by_path(path);
             match maybe_bytes {
                 None => Ok(None),
                 Some(bytes) => {
@@ -113,38 +435,103 @@ impl LuaUserData for LuaPackageWrapper {
             }
         });
 
-        methods.add_method("read_content_as_byte_array", |_, this, (path, ): (String, )| {
-            let maybe_bytes = this.package_ref().content_by_path(path);
+        methods.method("read_content_as_byte_array", "(path: string) -> integer[]?",
+            "Content of the entry under the specified path as a table of bytes, or `nil` if there's no such entry.", |_, this, (path, ): (String, )| {
+            let maybe_bytes = this.package_ref()?.content_by_path(path);
             Ok(maybe_bytes)
         });
 
-        methods.add_method_mut("remove", |_, this, (path, ): (String, )| {
-            Ok(this.package_mut().remove_entry(path))
+        methods.method("read_content_as_buffer", "(path: string) -> Buffer?",
+            "Content of the entry under the specified path, or `nil` if there's no such entry.", |_, this, (path, ): (String, )| {
+            let maybe_bytes = this.package_ref()?.content_by_path(path);
+            Ok(maybe_bytes.map(Buffer::from))
         });
 
-        methods.add_method("exists", |_, this, (path, ): (String, )| {
-            Ok(this.package_ref().entry_exists(&path))
+        methods.method_mut("remove", "(path: string) -> boolean",
+            "Removes the entry under the specified path, returning whether there was one.", |_, this, (path, ): (String, )| {
+            this.remove(&path)
+                .map_err(external_lua_error)
         });
 
-        methods.add_method_mut("clear", |_, this, ()| {
-            Ok(this.package_mut().clear())
+        methods.method("exists", "(path: string) -> boolean", "Whether there's an entry under the specified path.", |_, this, (path, ): (String, )| {
+            Ok(this.package_ref()?.entry_exists(&path))
         });
 
-        methods.add_method("inner_paths", |_lua, this, ()| {
-            Ok(this.package_ref().inner_paths())
+        methods.method_mut("clear", "()", "Removes all entries.", |_, this, ()| {
+            this.package_mut()?.clear();
+            this.forget_all();
+            Ok(())
         });
 
-        methods.add_method("len", |_, this, ()| {
-            Ok(this.package_ref().entry_count())
+        methods.method("inner_paths", "(filter: string?) -> string[]",
+            "Paths of all entries, or of those matching the filter: a prefix, or a glob pattern such as `**/*.lua`.", |_, this, (filter, ): (Option<String>, )| {
+            this.inner_paths(filter)
+                .map_err(external_lua_error)
         });
 
-        methods.add_method("entry_count", |_, this, ()| {
-            Ok(this.package_ref().entry_count())
+        methods.method("entry_size", "(path: string) -> integer?",
+            "Size of the entry's content in bytes, or `nil` if there's no such entry.", |_, this, (path, ): (String, )| {
+            Ok(this.entry_size(path)?)
         });
 
-        methods.add_method("extract", |_, this, (path, ): (String, )| {
-            this.package_ref().extract(path)
+        // Usage: `for path, size, offset in package:entries() do ... end`
+        // `offset` is nil for entries that were not read from the package's source file.
+        methods.method("entries", "() -> fun(): string?, integer?, integer?",
+            "Iterator over the path, size and offset of each entry. The offset is `nil` for entries that weren't read \
+            from the package's file.", |lua, this, ()| {
+            let mut entries = this.package_ref()?.inner_paths()
+                .into_iter()
+                .map(|inner_path| {
+                    let size = this.entry_size(&inner_path)?;
+                    let offset = this.entry_offset(&inner_path);
+                    Ok((inner_path, size, offset))
+                })
+                .collect::<crate::error::Result<Vec<_>>>()?
+                .into_iter();
+
+            lua.create_function_mut(move |_, ()| {
+                match entries.next() {
+                    Some((inner_path, size, offset)) => Ok((Some(inner_path), size, offset)),
+                    None => Ok((None, None, None))
+                }
+            })
+        });
+
+        methods.method("len", "() -> integer", "Number of entries.", |_, this, ()| {
+            Ok(this.package_ref()?.entry_count())
+        });
+
+        methods.method("entry_count", "() -> integer", "Same as `len`.", |_, this, ()| {
+            Ok(this.package_ref()?.entry_count())
+        });
+
+        methods.method("extract", "(path: string) -> integer",
+            "Extracts all entries into the directory at the specified path, relative to the game directory, returning \
+            their number.", |lua, this, (path, ): (String, )| {
+            let dest_dir = sandboxed_directory(lua, path)?;
+            package::extract_all(&sandbox(lua)?, this.package_ref()?, &dest_dir, &[], &mut |_, _, _| true)
                 .map_err(external_lua_error)
         });
+
+        methods.method_mut("add_directory", "(directory: Directory, prefix: string?, filters: string|string[]?, callback: fun(done: integer, total: integer, path: string): boolean??) -> integer, integer",
+            "Puts the files within the directory that match the filters into the package, under the prefix, returning \
+            the numbers of added and replaced entries. Files are only read once the package is written out. If \
+            aborted, the package is left unchanged.", |lua, this, (dir, inner_prefix, filters, callback): (Directory, Option<String>, LuaValue, Option<LuaFunction>)| {
+            let filters = inner_path_filters(lua, filters)?;
+            let mut progress = LuaProgress::new(callback);
+            let result = this.add_directory(&dir, &inner_prefix.unwrap_or_default(), &filters,
+                                            &mut |done, total, path| progress.report(done, total, path));
+            progress.finish(result)
+        });
+
+        methods.method("extract_all", "(directory: Directory, filters: string|string[]?, callback: fun(done: integer, total: integer, path: string): boolean??) -> integer",
+            "Extracts the entries matching the filters into the directory, returning their number.", |lua, this, (dest_dir, filters, callback): (Directory, LuaValue, Option<LuaFunction>)| {
+            let filters = inner_path_filters(lua, filters)?;
+            let sandbox = sandbox(lua)?;
+            let mut progress = LuaProgress::new(callback);
+            let result = package::extract_all(&sandbox, this.package_ref()?, &dest_dir, &filters,
+                                              &mut |done, total, path| progress.report(done, total, path));
+            progress.finish(result)
+        });
     }
 }
@@ -1,84 +1,256 @@
-use std::error::Error;
 use std::path::{Component, Path, PathBuf};
-use std::sync::Arc;
+use std::time::Duration;
 
 use mlua::{Lua, UserDataMethods, Variadic};
-use mlua::prelude::{LuaError, LuaResult, LuaTable, LuaUserData};
-use path_absolutize::Absolutize;
+use mlua::prelude::{LuaError, LuaFunction, LuaResult, LuaTable, LuaUserData, LuaValue};
 
 use crate::io::{Directory, HasParent, HasPath, HasRelativePath, HasRoot};
 use crate::io::File;
-use crate::io::PathFilter;
+use crate::io::Sandbox;
+use crate::io::{WatchOptions, Watcher};
+use crate::io::audit;
+use crate::io::save_data;
+use crate::io::save_data::DiscoveryContext;
+use crate::buffer::Buffer;
+#[cfg(feature = "compress")]
+use crate::compression;
+#[cfg(feature = "compress")]
+use crate::compression::{Algorithm, DEFAULT_DECOMPRESSED_SIZE_LIMIT};
+use crate::error::{Error, ErrorKind};
+use crate::lua::api::{ApiMethods, Module};
+use crate::lua::buffer::LuaBytes;
+use crate::lua::error::external_lua_error;
+use crate::lua::progress::LuaProgress;
+use crate::package::InnerPathFilter;
 
 /// Build the module's exports table, governing what is exposed to Lua.
-pub fn init(lua: &Lua) -> LuaResult<LuaTable> {
-    let exports = lua.create_table()?;
-
-    exports.set("file", lua.create_function(lua_file)?)?;
-    exports.set("directory", lua.create_function(lua_directory)?)?;
-    exports.set("save_data_directory", lua.create_function(save_data_directory)?)?;
+pub fn init(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "io",
+        "File system access, confined to a sandbox made up of the game directory, which relative paths are resolved \
+        against, and the save data directory. Paths outside of it fail with `NotAllowed`.")?;
+
+    exports.function("file", "(path: string?) -> File",
+        "File at the specified path, relative to the game directory.", lua_file)?;
+    exports.function("directory", "(path: string?) -> Directory",
+        "Directory at the specified path, relative to the game directory; the game directory itself by default.", lua_directory)?;
+    exports.function("save_data_directory", "() -> Directory",
+        "Directory the game stores save data in.", save_data_directory)?;
+    exports.function("set_save_data_directory", "(path: string?) -> Directory",
+        "Switches to another valid location among `save_data_candidates`, eg. when several installations exist, or \
+        restores discovery if `nil`. Other directories are rejected with `NotAllowed`.", set_save_data_directory)?;
+    exports.function("save_data_candidates", "() -> { path: string, source: string, valid: boolean }[]",
+        "Locations the save data directory is looked for in, in order.", save_data_candidates)?;
+    exports.function("watch", "(directory: Directory, options: { recursive: boolean?, debounce: integer?, filters: string|string[]? }?) -> Watcher",
+        "Watches the directory for changes, recursively and with a debounce of 100 milliseconds unless specified otherwise.", watch)?;
+    exports.module(audit_exports(lua)?)?;
+    exports.class::<File>("File", "File within the sandbox, which may or may not exist.")?;
+    exports.class::<Directory>("Directory", "Directory within the sandbox, which may or may not exist.")?;
+    exports.class::<Watcher>("Watcher", "Watch over a directory, collecting changes on a background thread.")?;
+    exports.safe_variants()?;
 
     Ok(exports)
 }
 
 //region <Exported adapter functions>
-fn lua_file(_: &Lua, (maybe_path, ): (Option<String>, )) -> LuaResult<File> {
+fn lua_file(lua: &Lua, (maybe_path, ): (Option<String>, )) -> LuaResult<File> {
     let path = match maybe_path {
         None => ".".to_string(),
         Some(path) => path
     };
 
-    let path = normalize(PathBuf::from(path));
-    let normalized_path = path.absolutize()
-        .map_err(external_lua_error)?;
-
-    file(normalized_path)
-        .map_err(external_lua_error)
+    sandboxed_file(lua, path)
 }
 
-fn lua_directory(_: &Lua, (maybe_path, ): (Option<String>, )) -> LuaResult<Directory> {
+fn lua_directory(lua: &Lua, (maybe_path, ): (Option<String>, )) -> LuaResult<Directory> {
     let path = match maybe_path {
         None => ".".to_string(),
         Some(path) => path
     };
 
+    sandboxed_directory(lua, path)
+}
+
+fn save_data_directory(lua: &Lua, (): ()) -> LuaResult<Directory> {
+    Ok(Directory::from(sandbox(lua)?.save_data_directory()))
+}
+
+fn set_save_data_directory(lua: &Lua, (path, ): (Option<String>, )) -> LuaResult<Directory> {
+    let mut sandbox = sandbox(lua)?;
+    let path = match path {
+        None => None,
+        Some(path) => Some(sandbox.resolve(normalize(PathBuf::from(path)))
+            .map_err(external_lua_error)?)
+    };
+
+    let context = DiscoveryContext::from_environment(sandbox.game_directory().to_path_buf());
+    sandbox.set_save_data_directory(&context, path)
+        .map_err(external_lua_error)?;
+    let result = Directory::from(sandbox.save_data_directory());
+    lua.set_app_data(sandbox);
+    Ok(result)
+}
+
+fn save_data_candidates(lua: &Lua, (): ()) -> LuaResult<LuaTable<'_>> {
+    let game_directory = sandbox(lua)?.game_directory().to_path_buf();
+    let context = DiscoveryContext::from_environment(game_directory);
+
+    let result = lua.create_table()?;
+    for (index, candidate) in save_data::candidates(&context).into_iter().enumerate() {
+        let table = lua.create_table()?;
+        table.set("path", candidate.path.to_string_lossy().replace('\\', "/"))?;
+        table.set("source", candidate.source)?;
+        table.set("valid", candidate.is_valid())?;
+        result.set(index + 1, table)?;
+    }
+    Ok(result)
+}
+
+fn watch(lua: &Lua, (directory, options): (Directory, Option<LuaTable>)) -> LuaResult<Watcher> {
+    let mut watch_options = WatchOptions::default();
+    if let Some(options) = options {
+        if let Some(recursive) = options.get::<_, Option<bool>>("recursive")? {
+            watch_options.recursive = recursive;
+        }
+        if let Some(debounce) = options.get::<_, Option<u64>>("debounce")? {
+            watch_options.debounce = Duration::from_millis(debounce);
+        }
+        watch_options.filters = inner_path_filters(lua, options.get::<_, LuaValue>("filters")?)?;
+    }
+
+    Watcher::new(&sandbox(lua)?, &directory, watch_options)
+        .map_err(external_lua_error)
+}
+
+fn audit_exports(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "audit", "Log of file system changes, recording which mod deleted or overwrote what.")?;
+
+    exports.function("enable", "(options: { max_file_size: integer?, max_files: integer? }?) -> File",
+        "Starts recording every write, delete, move and copy to `itb_rs_audit.log` in the save data directory, \
+        rotating the file once it exceeds the size limit. Returns the log file.", |lua, (options, ): (Option<LuaTable>, )| {
+        let (max_file_size, max_files) = match options {
+            None => (None, None),
+            Some(options) => (options.get("max_file_size")?, options.get("max_files")?)
+        };
+        let path = audit::enable(
+            sandbox(lua)?.save_data_directory(),
+            max_file_size.unwrap_or(audit::DEFAULT_MAX_FILE_SIZE),
+            max_files.unwrap_or(audit::DEFAULT_MAX_FILES),
+        );
+        Ok(File::from(path))
+    })?;
+
+    exports.function("disable", "()", "Stops recording.", |_, ()| {
+        audit::disable();
+        Ok(())
+    })?;
+
+    exports.function("is_enabled", "() -> boolean", "Whether changes are being recorded.", |_, ()| {
+        Ok(audit::is_enabled())
+    })?;
+
+    exports.function("set_mod", "(id: string?)", "Sets the mod that subsequent changes are attributed to.", |_, (mod_id, ): (Option<String>, )| {
+        audit::set_mod(mod_id);
+        Ok(())
+    })?;
+
+    exports.function("current_mod", "() -> string?", "Mod that changes are currently attributed to.", |_, ()| {
+        Ok(audit::current_mod())
+    })?;
+
+    exports.function("recent", "(count: integer?) -> { time: number, operation: string, path: string, destination: string?, mod: string? }[]",
+        "Most recent entries of the log, 50 unless specified otherwise, oldest first.", |lua, (count, ): (Option<usize>, )| {
+        let result = lua.create_table()?;
+        for (index, entry) in audit::recent(count.unwrap_or(50)).into_iter().enumerate() {
+            let table = lua.create_table()?;
+            table.set("time", entry.timestamp)?;
+            table.set("operation", entry.operation.name())?;
+            table.set("path", entry.path)?;
+            table.set("destination", entry.destination)?;
+            table.set("mod", entry.mod_id)?;
+            result.set(index + 1, table)?;
+        }
+        Ok(result)
+    })?;
+    exports.safe_variants()?;
+
+    Ok(exports)
+}
+//endregion
+
+/// Returns the sandbox that governs which paths Lua is allowed to access. Embedders can confine
+/// Lua to other directories by storing their own [Sandbox] with [Lua::set_app_data]; otherwise,
+/// the default one is discovered on first use.
+pub(crate) fn sandbox(lua: &Lua) -> LuaResult<Sandbox> {
+    if let Some(sandbox) = lua.app_data_ref::<Sandbox>() {
+        return Ok(sandbox.clone());
+    }
+
+    let sandbox = Sandbox::discover()
+        .map_err(external_lua_error)?;
+    lua.set_app_data(sandbox.clone());
+    Ok(sandbox)
+}
+
+/// Returns the file at a path passed from Lua, resolved against the game directory. Fails with
+/// `NotAllowed` if it lies outside of the sandbox.
+pub(crate) fn sandboxed_file(lua: &Lua, path: String) -> LuaResult<File> {
+    let sandbox = sandbox(lua)?;
     let path = normalize(PathBuf::from(path));
-    let normalized_path = path.absolutize()
+    let normalized_path = sandbox.resolve(path)
         .map_err(external_lua_error)?;
 
-    directory(normalized_path)
+    file(&sandbox, normalized_path)
         .map_err(external_lua_error)
 }
 
-fn save_data_directory(_: &Lua, (): ()) -> LuaResult<Directory> {
-    PathFilter::save_data_directory()
-        .map(Directory::from)
+/// Same as [sandboxed_file], for directories.
+pub(crate) fn sandboxed_directory(lua: &Lua, path: String) -> LuaResult<Directory> {
+    let sandbox = sandbox(lua)?;
+    let path = normalize(PathBuf::from(path));
+    let normalized_path = sandbox.resolve(path)
+        .map_err(external_lua_error)?;
+
+    directory(&sandbox, normalized_path)
         .map_err(external_lua_error)
 }
-//endregion
 
-fn external_lua_error<T: Error + Send + Sync + 'static>(error: T) -> LuaError {
-    LuaError::ExternalError(Arc::new(error))
+/// Filters can be passed from Lua either as a single string, or as a table of strings.
+/// An entry passes if it matches any of the filters, or if no filters were specified.
+pub(crate) fn inner_path_filters(lua: &Lua, value: LuaValue) -> LuaResult<Vec<InnerPathFilter>> {
+    let filters: Vec<String> = match value {
+        LuaValue::Nil => Vec::new(),
+        LuaValue::String(filter) => vec![filter.to_str()?.to_string()],
+        LuaValue::Table(_) => lua.unpack(value)?,
+        _ => return Err(LuaError::FromLuaConversionError {
+            from: value.type_name(),
+            to: "filters",
+            message: Some("expected a string or a table of strings".to_string()),
+        })
+    };
+
+    filters.into_iter()
+        .map(|filter| InnerPathFilter::parse(filter).map_err(external_lua_error))
+        .collect()
 }
 
-fn file<P: AsRef<Path>>(path: P) -> std::io::Result<File> where PathBuf: From<P> {
-    if PathFilter::is_whitelisted(&path)? {
+fn file<P: AsRef<Path>>(sandbox: &Sandbox, path: P) -> crate::error::Result<File> where PathBuf: From<P> {
+    if sandbox.is_whitelisted(&path)? {
         Ok(File::from(path))
     } else {
-        Err(std::io::Error::new(std::io::ErrorKind::Other, "Path points to a file not within an allowed directory"))
+        Err(Error::new(ErrorKind::NotAllowed, "Path points to a file not within an allowed directory").with_path(path))
     }
 }
 
-fn directory<P: AsRef<Path>>(path: P) -> std::io::Result<Directory> where PathBuf: From<P> {
-    if PathFilter::is_whitelisted(&path)? {
+fn directory<P: AsRef<Path>>(sandbox: &Sandbox, path: P) -> crate::error::Result<Directory> where PathBuf: From<P> {
+    if sandbox.is_whitelisted(&path)? {
         Ok(Directory::from(path))
     } else {
-        Err(std::io::Error::new(std::io::ErrorKind::Other, "Path does not point to an allowed directory"))
+        Err(Error::new(ErrorKind::NotAllowed, "Path does not point to an allowed directory").with_path(path))
     }
 }
 
 fn normalize(path: PathBuf) -> PathBuf {
-    let maybe_first_component = path.components().into_iter().next();
+    let maybe_first_component = path.components().next();
     let first_component = match maybe_first_component {
         None => Component::Normal("".as_ref()),
         Some(component) => component
@@ -97,83 +269,126 @@ fn normalize(path: PathBuf) -> PathBuf {
 
 impl LuaUserData for File {
     fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
-        methods.add_method("path", |_, this, ()| {
+        methods.method("path", "() -> string",
+            "Absolute path of the file.", |_, this, ()| {
             Ok(this.path())
         });
 
-        methods.add_method("relative_path", |_, this, ()| {
-            this.relative_path()
-                .map_err(external_lua_error)
+        methods.method("relative_path", "() -> string",
+            "Path of the file relative to the game or save data directory it's in.", |lua, this, ()| {
+            Ok(this.relative_path(&sandbox(lua)?))
         });
 
-        methods.add_method("name", |_, this, ()| {
+        methods.method("name", "() -> string",
+            "Name of the file, including its extension.", |_, this, ()| {
             Ok(this.name())
         });
 
-        methods.add_method("name_without_extension", |_, this, ()| {
+        methods.method("name_without_extension", "() -> string",
+            "Name of the file, without its extension.", |_, this, ()| {
             Ok(this.name_without_extension())
         });
 
-        methods.add_method("extension", |_, this, ()| {
+        methods.method("extension", "() -> string?",
+            "Extension of the file, without the dot, or `nil` if it has none.", |_, this, ()| {
             Ok(this.extension())
         });
 
-        methods.add_method("parent", |_, this, ()| {
-            this.parent()
+        methods.method("parent", "() -> Directory",
+            "Directory the file is in. Fails with `NotAllowed` outside of the sandbox.", |lua, this, ()| {
+            this.parent(&sandbox(lua)?)
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("root", |_, this, ()| {
-            this.root()
-                .map_err(external_lua_error)
+        methods.method("root", "() -> Directory",
+            "Game or save data directory the file is in.", |lua, this, ()| {
+            Ok(this.root(&sandbox(lua)?))
         });
 
-        methods.add_method("read_to_string", |_, this, ()| {
+        methods.method("read_to_string", "() -> string",
+            "Reads the file as UTF-8 text.", |_, this, ()| {
             this.read_to_string()
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("read_to_byte_array", |_, this, ()| {
+        methods.method("read_to_byte_array", "() -> integer[]",
+            "Reads the file as a table of bytes.", |_, this, ()| {
             this.read_to_byte_array()
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("write_string", |_, this, (content, ): (String, )| {
+        methods.method("read_to_buffer", "() -> Buffer",
+            "Reads the file into a buffer.", |_, this, ()| {
+            this.read_to_byte_array()
+                .map(Buffer::from)
+                .map_err(external_lua_error)
+        });
+
+        methods.method("write_string", "(content: string)",
+            "Writes the text to the file, creating its parent directories and replacing its content.", |_, this, (content, ): (String, )| {
             this.write_string(content)
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("append_string", |_, this, (content, ): (String, )| {
+        methods.method("append_string", "(content: string)",
+            "Appends the text to the file, creating it if it doesn't exist.", |_, this, (content, ): (String, )| {
             this.append_string(content)
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("write_byte_array", |_, this, (content, ): (Vec<u8>, )| {
+        methods.method("write_byte_array", "(content: string|Buffer|integer[])",
+            "Writes the bytes to the file, creating its parent directories and replacing its content.", |_, this, (content, ): (LuaBytes, )| {
+            this.write_byte_array(content.0)
+                .map_err(external_lua_error)
+        });
+
+        // Gzipped files need the compression library, which is only included with the compress module
+        #[cfg(feature = "compress")]
+        methods.method("read_gzip", "(size_limit: integer?) -> Buffer",
+            "Reads and decompresses a gzipped file, failing once the output exceeds the size limit.", |_, this, (size_limit, ): (Option<usize>, )| {
+            let content = this.read_to_byte_array()
+                .map_err(external_lua_error)?;
+            compression::decompress(&content, Algorithm::Gzip, size_limit.unwrap_or(DEFAULT_DECOMPRESSED_SIZE_LIMIT))
+                .map(Buffer::from)
+                .map_err(external_lua_error)
+        });
+
+        #[cfg(feature = "compress")]
+        methods.method("write_gzip", "(content: string|Buffer|integer[], level: integer?)",
+            "Compresses the content with gzip, at the specified level from 0 to 9, and writes it to the file.", |_, this, (content, level): (LuaBytes, Option<u32>)| {
+            let content = compression::compress(&content.0, Algorithm::Gzip, level)
+                .map_err(external_lua_error)?;
             this.write_byte_array(content)
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("copy", |_, this, (destination, ): (String, )| {
+        methods.method("copy", "(destination: string)",
+            "Copies the file to the destination path, which has to be within the sandbox.", |lua, this, (destination, ): (String, )| {
+            let sandbox = sandbox(lua)?;
             let path = normalize(PathBuf::from(destination));
-            let normalized_path = path.absolutize()
+            let normalized_path = sandbox.resolve(path)
                 .map_err(external_lua_error)?;
 
-            Ok(this.copy(&normalized_path).map_err(external_lua_error)?)
+            this.copy(&sandbox, &normalized_path).map_err(external_lua_error)
         });
 
-        methods.add_method("move", |_, this, (destination, ): (String, )| {
+        methods.method("move", "(destination: string)",
+            "Moves the file to the destination path, which has to be within the sandbox.", |lua, this, (destination, ): (String, )| {
+            let sandbox = sandbox(lua)?;
             let path = normalize(PathBuf::from(destination));
-            let normalized_path = path.absolutize()
+            let normalized_path = sandbox.resolve(path)
                 .map_err(external_lua_error)?;
 
-            Ok(this.move_file(&normalized_path).map_err(external_lua_error)?)
+            this.move_file(&sandbox, &normalized_path).map_err(external_lua_error)
         });
 
-        methods.add_method("exists", |_, this, ()| {
+        methods.method("exists", "() -> boolean",
+            "Whether the file exists.", |_, this, ()| {
             Ok(this.exists())
         });
 
-        methods.add_method("delete", |_, this, ()| {
+        methods.method("delete", "()",
+            "Deletes the file, if it exists.", |_, this, ()| {
             this.delete()
                 .map_err(external_lua_error)
         });
@@ -182,80 +397,131 @@ impl LuaUserData for File {
 
 impl LuaUserData for Directory {
     fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
-        methods.add_method("path", |_, this, ()| {
+        methods.method("path", "() -> string",
+            "Absolute path of the directory, with a trailing slash.", |_, this, ()| {
             Ok(this.path())
         });
 
-        methods.add_method("relative_path", |_, this, ()| {
-            this.relative_path()
-                .map_err(external_lua_error)
+        methods.method("relative_path", "() -> string",
+            "Path of the directory relative to the game or save data directory it's in.", |lua, this, ()| {
+            Ok(this.relative_path(&sandbox(lua)?))
         });
 
-        methods.add_method("name", |_, this, ()| {
+        methods.method("name", "() -> string",
+            "Name of the directory.", |_, this, ()| {
             Ok(this.name())
         });
 
-        methods.add_method("parent", |_, this, ()| {
-            this.parent()
+        methods.method("parent", "() -> Directory",
+            "Directory this one is in. Fails with `NotAllowed` outside of the sandbox.", |lua, this, ()| {
+            this.parent(&sandbox(lua)?)
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("root", |_, this, ()| {
-            this.root()
-                .map_err(external_lua_error)
+        methods.method("root", "() -> Directory",
+            "Game or save data directory this one is in.", |lua, this, ()| {
+            Ok(this.root(&sandbox(lua)?))
         });
 
-        methods.add_method("relativize", |_, this, (path, ): (String, )| {
+        methods.method("relativize", "(path: string) -> string?",
+            "Path relative to this directory, or `nil` if it can't be expressed as one.", |_, this, (path, ): (String, )| {
             Ok(this.relativize(path))
         });
 
-        methods.add_method("file", |_, this, (paths, ): (Variadic<String>, )| {
+        methods.method("file", "(...: string) -> File",
+            "File at the path made of the specified segments, relative to this directory.", |lua, this, (paths, ): (Variadic<String>, )| {
+            let sandbox = sandbox(lua)?;
             let path: PathBuf = paths.iter().collect();
             let path = this.path.join(path);
-            let normalized_path = path.absolutize()
+            let normalized_path = sandbox.resolve(path)
                 .map_err(external_lua_error)?;
 
-            file(normalized_path)
+            file(&sandbox, normalized_path)
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("directory", |_, this, (paths, ): (Variadic<String>, )| {
+        methods.method("directory", "(...: string) -> Directory",
+            "Directory at the path made of the specified segments, relative to this directory.", |lua, this, (paths, ): (Variadic<String>, )| {
+            let sandbox = sandbox(lua)?;
             let path: PathBuf = paths.iter().collect();
             let path = this.path.join(path);
-            let normalized_path = path.absolutize()
+            let normalized_path = sandbox.resolve(path)
                 .map_err(external_lua_error)?;
 
-            directory(normalized_path)
+            directory(&sandbox, normalized_path)
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("files", |_, this, ()| {
+        methods.method("files", "() -> File[]",
+            "Files directly within this directory.", |_, this, ()| {
             this.files()
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("directories", |_, this, ()| {
+        methods.method("directories", "() -> Directory[]",
+            "Directories directly within this directory.", |_, this, ()| {
             this.directories()
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("make_directories", |_, this, ()| {
-            this.make_directories()
+        methods.method("make_directories", "()",
+            "Creates this directory and all of its missing parents.", |lua, this, ()| {
+            this.make_directories(&sandbox(lua)?)
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("exists", |_, this, ()| {
+        methods.method("exists", "() -> boolean",
+            "Whether the directory exists.", |_, this, ()| {
             Ok(this.exists())
         });
 
-        methods.add_method("is_ancestor", |_, this, (path, ): (String, )| {
+        methods.method("is_ancestor", "(path: string) -> boolean",
+            "Whether the absolute path lies within this directory.", |_, this, (path, ): (String, )| {
             this.is_ancestor(path)
                 .map_err(external_lua_error)
         });
 
-        methods.add_method("delete", |_, this, ()| {
-            this.delete()
-                .map_err(external_lua_error)
+        methods.method("delete", "(callback: fun(done: integer, total: integer, path: string): boolean??)",
+            "Deletes the directory with all of its content. If aborted through the callback, files already deleted stay that way.", |_, this, (callback, ): (Option<LuaFunction>, )| {
+            if callback.is_none() {
+                return this.delete()
+                    .map_err(external_lua_error);
+            }
+
+            let mut progress = LuaProgress::new(callback);
+            let result = this.delete_with_progress(&mut |done, total, path| progress.report(done, total, path));
+            progress.finish(result)
+        });
+    }
+}
+
+impl LuaUserData for Watcher {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.method_mut("poll", "() -> { kind: \"create\"|\"modify\"|\"delete\"|\"rename\", path: string, to: string? }[]",
+            "Changes collected since the last poll, with paths relative to the watched directory. Rapid successive changes to the same path are merged.", |lua, this, ()| {
+            let events = this.poll()
+                .map_err(external_lua_error)?;
+
+            let result = lua.create_table()?;
+            for (index, event) in events.into_iter().enumerate() {
+                let entry = lua.create_table()?;
+                entry.set("kind", event.kind.name())?;
+                entry.set("path", event.path)?;
+                entry.set("to", event.to)?;
+                result.set(index + 1, entry)?;
+            }
+            Ok(result)
+        });
+
+        methods.method_mut("close", "()",
+            "Stops watching.", |_, this, ()| {
+            this.close();
+            Ok(())
+        });
+
+        methods.method("is_closed", "() -> boolean",
+            "Whether the watcher has been closed.", |_, this, ()| {
+            Ok(this.is_closed())
         });
     }
 }
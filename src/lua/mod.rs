@@ -1,4 +1,23 @@
+pub(crate) mod api;
 pub(crate) mod exports;
+#[cfg(feature = "buffer")]
+mod buffer;
+#[cfg(feature = "compress")]
+mod compress;
+#[cfg(feature = "encoding")]
+mod encoding;
+#[cfg(feature = "ftldat")]
 mod ftldat;
 mod error;
+#[cfg(feature = "io")]
 mod io;
+#[cfg(feature = "mods")]
+mod mods;
+#[cfg(feature = "io")]
+mod progress;
+#[cfg(any(feature = "io", feature = "semver"))]
+mod safe;
+#[cfg(feature = "semver")]
+mod semver;
+#[cfg(feature = "task")]
+mod task;
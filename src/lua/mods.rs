@@ -0,0 +1,169 @@
+use mlua::Lua;
+use mlua::prelude::{LuaResult, LuaTable, LuaValue};
+
+use crate::error::{Error, ErrorKind};
+use crate::io::Directory;
+use crate::lua::api::Module;
+use crate::lua::error::external_lua_error;
+use crate::mods;
+use crate::mods::{Dependency, LoadOrder, ModInfo};
+
+/// Mod metadata as exchanged with Lua, in LuaLS notation.
+const MOD_TYPE: &str = "{ id: string, name: string?, version: string?, semver: string?, directory: Directory?, \
+    dependencies: { id: string, requirement: string?, optional: boolean }[], conflicts: string[] }";
+
+/// Build the module's exports table, governing what is exposed to Lua.
+pub fn init(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "mods",
+        "Discovery of mods and resolution of the order to load them in, from the metadata their `scripts/init.lua` \
+        returns, which is read without executing the script.")?;
+
+    exports.function("scan", &format!("(directory: Directory) -> {{ mods: {}[], invalid: {{ directory: Directory, error: Error }}[] }}", MOD_TYPE),
+        "Reads the metadata of the mods in the subdirectories of the mods directory, ordered by name. Mods whose \
+        metadata can't be read statically, such as those computing their `id`, are listed as invalid.", scan)?;
+    exports.function("read_metadata", &format!("(source: string) -> {}", MOD_TYPE),
+        "Reads the metadata of a mod from the source of its init script: its `id`, `name` and `version`, required \
+        dependencies from `requirements` and `dependencies`, optional ones from `optionalDependencies`, and \
+        `conflicts`. Dependencies are listed as ids, or as a table of version requirements by id, where a bare \
+        version is the minimum version. `semver` is the version parsed as semver, if it can be.", read_metadata)?;
+    exports.function("load_order", &format!("(mods: {}[]) -> {{ order: string[], excluded: string[], \
+        missing: {{ id: string, dependency: string, requirement: string?, installed: string? }}[], cycles: string[][], \
+        conflicts: {{ id: string, conflict: string }}[], duplicates: string[] }}", MOD_TYPE),
+        "Resolves the order to load the mods in, so that each one is loaded after its dependencies. Mods are \
+        excluded if a required dependency is missing or excluded itself, if they're in a cycle of required \
+        dependencies, or if they conflict with another mod; the report lists why.", load_order)?;
+    exports.safe_variants()?;
+
+    Ok(exports)
+}
+
+//region <Exported adapter functions>
+fn scan(lua: &Lua, (directory, ): (Directory, )) -> LuaResult<LuaTable<'_>> {
+    let (infos, invalid) = mods::scan(&directory)
+        .map_err(external_lua_error)?;
+
+    let result = lua.create_table()?;
+    let mods_table = lua.create_table()?;
+    for (index, info) in infos.iter().enumerate() {
+        mods_table.set(index + 1, mod_table(lua, info)?)?;
+    }
+    let invalid_table = lua.create_table()?;
+    for (index, invalid) in invalid.into_iter().enumerate() {
+        let table = lua.create_table()?;
+        table.set("directory", Directory::from(invalid.directory))?;
+        table.set("error", invalid.error)?;
+        invalid_table.set(index + 1, table)?;
+    }
+
+    result.set("mods", mods_table)?;
+    result.set("invalid", invalid_table)?;
+    Ok(result)
+}
+
+fn read_metadata(lua: &Lua, (source, ): (String, )) -> LuaResult<LuaTable<'_>> {
+    let info = mods::read_metadata(&source)
+        .map_err(external_lua_error)?;
+    mod_table(lua, &info)
+}
+
+fn load_order<'lua>(lua: &'lua Lua, (mods, ): (LuaTable, )) -> LuaResult<LuaTable<'lua>> {
+    let infos = mods.sequence_values::<LuaTable>()
+        .map(|info| mod_info(info?))
+        .collect::<LuaResult<Vec<ModInfo>>>()?;
+
+    load_order_table(lua, mods::load_order(&infos))
+}
+//endregion
+
+fn mod_table<'lua>(lua: &'lua Lua, info: &ModInfo) -> LuaResult<LuaTable<'lua>> {
+    let table = lua.create_table()?;
+    table.set("id", info.id.as_str())?;
+    table.set("name", info.name.as_deref())?;
+    table.set("version", info.version_text.as_deref())?;
+    table.set("semver", info.version.as_ref().map(|version| version.to_string()))?;
+    table.set("directory", info.directory.clone().map(Directory::from))?;
+
+    let dependencies = lua.create_table()?;
+    for (index, dependency) in info.dependencies.iter().enumerate() {
+        let dependency_table = lua.create_table()?;
+        dependency_table.set("id", dependency.id.as_str())?;
+        dependency_table.set("requirement", dependency.requirement.as_ref().map(|requirement| requirement.to_string()))?;
+        dependency_table.set("optional", dependency.optional)?;
+        dependencies.set(index + 1, dependency_table)?;
+    }
+    table.set("dependencies", dependencies)?;
+    table.set("conflicts", info.conflicts.clone())?;
+    Ok(table)
+}
+
+/// Reads mod metadata from a table in the form returned by `scan` and `read_metadata`, which
+/// scripts may have modified or built themselves.
+fn mod_info(table: LuaTable) -> LuaResult<ModInfo> {
+    let id: String = table.get::<_, Option<String>>("id")?
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Mod doesn't have an `id`"))?;
+    let version_text: Option<String> = table.get("version")?;
+    let version = match table.get::<_, Option<String>>("semver")? {
+        Some(semver) => mods::parse_version(&semver),
+        None => version_text.as_deref().and_then(mods::parse_version),
+    };
+
+    let mut dependencies = Vec::new();
+    if let Some(dependency_tables) = table.get::<_, Option<LuaTable>>("dependencies")? {
+        for dependency in dependency_tables.sequence_values::<LuaValue>() {
+            dependencies.push(match dependency? {
+                LuaValue::String(id) => Dependency { id: id.to_str()?.to_string(), requirement: None, optional: false },
+                LuaValue::Table(dependency) => Dependency {
+                    id: dependency.get("id")?,
+                    requirement: match dependency.get::<_, Option<String>>("requirement")? {
+                        Some(requirement) => Some(mods::parse_requirement(&requirement)
+                            .map_err(|error| external_lua_error(Error::new(ErrorKind::InvalidInput, error.message())))?),
+                        None => None,
+                    },
+                    optional: dependency.get::<_, Option<bool>>("optional")?.unwrap_or(false),
+                },
+                other => return Err(external_lua_error(Error::new(ErrorKind::InvalidInput, format!(
+                    "Dependency of mod '{}' is a {}, expected a table or a string", id, other.type_name()
+                )))),
+            });
+        }
+    }
+
+    Ok(ModInfo {
+        name: table.get("name")?,
+        version_text,
+        version,
+        directory: table.get::<_, Option<Directory>>("directory")?.map(|directory| directory.path),
+        dependencies,
+        conflicts: table.get::<_, Option<Vec<String>>>("conflicts")?.unwrap_or_default(),
+        id,
+    })
+}
+
+fn load_order_table(lua: &Lua, order: LoadOrder) -> LuaResult<LuaTable<'_>> {
+    let missing = lua.create_table()?;
+    for (index, dependency) in order.missing.into_iter().enumerate() {
+        let table = lua.create_table()?;
+        table.set("id", dependency.id)?;
+        table.set("dependency", dependency.dependency)?;
+        table.set("requirement", dependency.requirement.map(|requirement| requirement.to_string()))?;
+        table.set("installed", dependency.installed)?;
+        missing.set(index + 1, table)?;
+    }
+
+    let conflicts = lua.create_table()?;
+    for (index, conflict) in order.conflicts.into_iter().enumerate() {
+        let table = lua.create_table()?;
+        table.set("id", conflict.id)?;
+        table.set("conflict", conflict.conflict)?;
+        conflicts.set(index + 1, table)?;
+    }
+
+    let result = lua.create_table()?;
+    result.set("order", order.order)?;
+    result.set("excluded", order.excluded)?;
+    result.set("missing", missing)?;
+    result.set("cycles", order.cycles)?;
+    result.set("conflicts", conflicts)?;
+    result.set("duplicates", order.duplicates)?;
+    Ok(result)
+}
@@ -0,0 +1,55 @@
+use std::time::Instant;
+
+use mlua::prelude::{LuaError, LuaFunction, LuaResult};
+
+use crate::lua::error::external_lua_error;
+use crate::progress::{DEFAULT_THROTTLE_INTERVAL, Throttle};
+
+/// Forwards progress of bulk operations to an optional Lua callback, throttled so that the
+/// callback isn't invoked for every single item.
+///
+/// The callback receives `(done, total, current_path)`, and may return `false` to abort the
+/// operation. Errors raised by the callback abort the operation as well, and are reported in
+/// place of the operation's own error.
+pub(crate) struct LuaProgress<'lua> {
+    callback: Option<LuaFunction<'lua>>,
+    throttle: Throttle,
+    error: Option<LuaError>,
+}
+
+impl<'lua> LuaProgress<'lua> {
+    pub fn new(callback: Option<LuaFunction<'lua>>) -> LuaProgress<'lua> {
+        LuaProgress {
+            callback,
+            throttle: Throttle::new(DEFAULT_THROTTLE_INTERVAL),
+            error: None,
+        }
+    }
+
+    pub fn report(&mut self, done: usize, total: usize, current_path: &str) -> bool {
+        let callback = match &self.callback {
+            None => return true,
+            Some(callback) => callback
+        };
+        if !self.throttle.ready(done, total, Instant::now()) {
+            return true;
+        }
+
+        match callback.call::<_, Option<bool>>((done, total, current_path)) {
+            // Callbacks that don't return anything shouldn't abort the operation
+            Ok(result) => result.unwrap_or(true),
+            Err(error) => {
+                self.error = Some(error);
+                false
+            }
+        }
+    }
+
+    /// Converts the operation's result, giving precedence to errors raised by the callback.
+    pub fn finish<T>(self, result: crate::error::Result<T>) -> LuaResult<T> {
+        match self.error {
+            Some(error) => Err(error),
+            None => result.map_err(external_lua_error)
+        }
+    }
+}
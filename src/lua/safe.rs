@@ -0,0 +1,141 @@
+use mlua::Lua;
+use mlua::prelude::{LuaFunction, LuaMultiValue, LuaResult, LuaTable, LuaValue};
+
+use crate::lua::error::crate_error;
+
+/// Builds a table holding a non-throwing variant of every function in `exports`, following the
+/// convention of Lua's own `io` library: on failure, the variants return `nil`, the error's kind
+/// and its message instead of raising an error. Functions that don't return anything return
+/// `true` on success, so that callers can tell success apart.
+///
+/// Methods can be called the same way with the table's `call(object, method_name, ...)`.
+///
+/// Only functions are wrapped, so nested modules aren't covered by their parent's table, and
+/// export their own instead.
+pub fn safe_variants<'lua>(lua: &'lua Lua, exports: &LuaTable<'lua>) -> LuaResult<LuaTable<'lua>> {
+    let safe = lua.create_table()?;
+
+    for pair in exports.clone().pairs::<LuaValue, LuaValue>() {
+        let (name, value) = pair?;
+        if let LuaValue::Function(function) = value {
+            let key = lua.create_registry_value(function)?;
+            safe.set(name, lua.create_function(move |lua, args: LuaMultiValue| {
+                let function: LuaFunction = lua.registry_value(&key)?;
+                call_safely(lua, function, args)
+            })?)?;
+        }
+    }
+
+    // Userdata fields can only be looked up through Lua
+    let index = lua.load("local object, key = ... return object[key]").into_function()?;
+    let index = lua.create_registry_value(index)?;
+    safe.set("call", lua.create_function(move |lua, (object, method_name, args): (LuaValue, String, LuaMultiValue)| {
+        let index: LuaFunction = lua.registry_value(&index)?;
+        call_method(lua, index, object, method_name, args)
+    })?)?;
+
+    Ok(safe)
+}
+
+fn call_method<'lua>(lua: &'lua Lua, index: LuaFunction<'lua>, object: LuaValue<'lua>, method_name: String, args: LuaMultiValue<'lua>) -> LuaResult<LuaMultiValue<'lua>> {
+    let method = match &object {
+        LuaValue::UserData(_) | LuaValue::Table(_) => index.call::<_, LuaValue>((object.clone(), method_name.as_str())),
+        _ => Ok(LuaValue::Nil),
+    };
+
+    match method {
+        Ok(LuaValue::Function(method)) => {
+            let mut args = args;
+            args.push_front(object);
+            call_safely(lua, method, args)
+        }
+        _ => failure(lua, "InvalidInput", format!("Object has no method '{}'", method_name)),
+    }
+}
+
+fn call_safely<'lua>(lua: &'lua Lua, function: LuaFunction<'lua>, args: LuaMultiValue<'lua>) -> LuaResult<LuaMultiValue<'lua>> {
+    match function.call::<_, LuaMultiValue>(args) {
+        Ok(values) if values.is_empty() => Ok(LuaMultiValue::from_vec(vec![LuaValue::Boolean(true)])),
+        Ok(values) => Ok(values),
+        Err(error) => {
+            let error = crate_error(&error);
+            failure(lua, error.kind().name(), error.to_string())
+        }
+    }
+}
+
+fn failure<'lua>(lua: &'lua Lua, kind: &str, message: String) -> LuaResult<LuaMultiValue<'lua>> {
+    Ok(LuaMultiValue::from_vec(vec![
+        LuaValue::Nil,
+        LuaValue::String(lua.create_string(kind)?),
+        LuaValue::String(lua.create_string(&message)?),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use mlua::Lua;
+    use mlua::prelude::{LuaFunction, LuaResult, LuaTable, LuaValue};
+
+    use crate::error::{Error, ErrorKind};
+    use crate::lua::error::external_lua_error;
+    use crate::lua::safe::safe_variants;
+
+    fn exports(lua: &Lua) -> LuaTable<'_> {
+        let exports = lua.create_table().unwrap();
+        exports.set("answer", lua.create_function(|_, ()| Ok(42)).unwrap()).unwrap();
+        exports.set("nothing", lua.create_function(|_, ()| Ok(())).unwrap()).unwrap();
+        exports.set("fail", lua.create_function(|_, ()| -> LuaResult<()> {
+            Err(external_lua_error(Error::new(ErrorKind::NotFound, "File doesn't exist")))
+        }).unwrap()).unwrap();
+        exports.set("nested", lua.create_table().unwrap()).unwrap();
+        exports
+    }
+
+    #[test]
+    fn variants_should_return_values_or_true_on_success() {
+        let lua = Lua::new();
+        let safe = safe_variants(&lua, &exports(&lua)).unwrap();
+
+        assert_eq!(42, safe.get::<_, LuaFunction>("answer").unwrap().call::<_, i64>(()).unwrap());
+        assert!(safe.get::<_, LuaFunction>("nothing").unwrap().call::<_, bool>(()).unwrap());
+        assert_eq!(LuaValue::Nil, safe.get::<_, LuaValue>("nested").unwrap());
+    }
+
+    #[test]
+    fn variants_should_return_nil_kind_and_message_on_failure() {
+        let lua = Lua::new();
+        let safe = safe_variants(&lua, &exports(&lua)).unwrap();
+
+        let (value, kind, message): (LuaValue, String, String) = safe.get::<_, LuaFunction>("fail").unwrap()
+            .call(()).unwrap();
+
+        assert_eq!(LuaValue::Nil, value);
+        assert_eq!("NotFound", kind);
+        assert!(message.contains("File doesn't exist"));
+    }
+
+    #[test]
+    fn call_should_call_methods_safely() {
+        let lua = Lua::new();
+        let safe = safe_variants(&lua, &exports(&lua)).unwrap();
+        lua.globals().set("safe", safe).unwrap();
+
+        let (result, ): (i64, ) = lua.load(r#"
+            local object = { value = 1 }
+            function object:add(amount) return self.value + amount end
+            return safe.call(object, "add", 2)
+        "#).eval().unwrap();
+        assert_eq!(3, result);
+
+        let (value, kind, message): (LuaValue, String, String) = lua.load(r#"return safe.call({}, "missing")"#)
+            .eval().unwrap();
+        assert_eq!(LuaValue::Nil, value);
+        assert_eq!("InvalidInput", kind);
+        assert_eq!("Object has no method 'missing'", message);
+
+        let (_, kind, _): (LuaValue, String, String) = lua.load(r#"return safe.call(42, "missing")"#)
+            .eval().unwrap();
+        assert_eq!("InvalidInput", kind);
+    }
+}
@@ -0,0 +1,215 @@
+use std::cmp::Ordering;
+
+use mlua::{FromLua, Lua, MetaMethod, UserDataFields, UserDataMethods};
+use mlua::prelude::{LuaError, LuaResult, LuaTable, LuaUserData, LuaValue};
+use semver::{Version, VersionReq};
+
+use crate::error::Error;
+use crate::lua::api::{ApiFields, ApiMethods, Module};
+use crate::lua::error::external_lua_error;
+use crate::version;
+use crate::version::Part;
+
+/// Build the module's exports table, governing what is exposed to Lua.
+pub fn init(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "semver",
+        "Semantic versions, compared by precedence, and requirements they can be matched against. Versions passed as \
+        strings are parsed leniently, to accept the irregular versions mods use.")?;
+
+    exports.function("parse", "(text: string, options: { strict: boolean? }?) -> Version",
+        "Parses a version. Unless `strict` is set, irregular versions are read as closely to semver as possible: a \
+        leading `v` is skipped, missing minor and patch components are zero, components past the patch become build \
+        metadata, and whatever follows the numbers becomes the pre-release, eg. `v3` is `3.0.0` and `1.2b` is \
+        `1.2.0-b`. Fails with `InvalidInput` if the text doesn't start with a number.", parse)?;
+    exports.function("new", "(major: integer, minor: integer?, patch: integer?) -> Version",
+        "Creates a release version from its components, which default to zero.", new)?;
+    exports.function("requirement", "(text: string, options: { minimum: boolean? }?) -> VersionReq",
+        "Parses a version requirement: comma-separated comparators, eg. `>=1.2, <2`, `^1.4` (compatible with 1.4, \
+        below 2.0) or `~1.2` (1.2.x). A bare version is the same as `^`, as in Cargo, unless `minimum` is set, in \
+        which case it's the minimum version, the way the mod loader reads dependencies, eg. `1.2` is `>=1.2`.", requirement)?;
+    exports.function("compare", "(a: string|Version, b: string|Version) -> integer",
+        "Compares two versions by precedence, returning -1, 0 or 1. Pre-releases precede their release, and build \
+        metadata only breaks ties.", compare)?;
+    exports.function("matches", "(requirement: string|VersionReq, version: string|Version) -> boolean",
+        "Whether the version satisfies the requirement. Requirements passed as strings are parsed like \
+        `requirement` does by default. Pre-releases only satisfy requirements that mention a pre-release of the \
+        same version.", matches)?;
+    exports.class::<LuaVersion>("Version",
+        "Semantic version. Versions can be compared with `==`, `<` and `<=` against other versions, and converted to \
+        strings with `tostring`.")?;
+    exports.class::<LuaVersionReq>("VersionReq", "Version requirement, made of comparators all versions matching it satisfy.")?;
+    exports.safe_variants()?;
+
+    Ok(exports)
+}
+
+//region <Exported adapter functions>
+fn parse(_: &Lua, (text, options): (String, Option<LuaTable>)) -> LuaResult<LuaVersion> {
+    let strict = match options {
+        None => false,
+        Some(options) => options.get::<_, Option<bool>>("strict")?.unwrap_or(false)
+    };
+
+    let version = if strict {
+        Version::parse(text.trim()).map_err(Error::from)
+    } else {
+        version::parse_lenient(&text)
+    };
+    version.map(LuaVersion).map_err(external_lua_error)
+}
+
+fn new(_: &Lua, (major, minor, patch): (u64, Option<u64>, Option<u64>)) -> LuaResult<LuaVersion> {
+    Ok(LuaVersion(Version::new(major, minor.unwrap_or(0), patch.unwrap_or(0))))
+}
+
+fn requirement(_: &Lua, (text, options): (String, Option<LuaTable>)) -> LuaResult<LuaVersionReq> {
+    let minimum = match options {
+        None => false,
+        Some(options) => options.get::<_, Option<bool>>("minimum")?.unwrap_or(false)
+    };
+
+    let requirement = if minimum {
+        version::parse_minimum_requirement(&text)
+    } else {
+        VersionReq::parse(&text).map_err(Error::from)
+    };
+    requirement.map(LuaVersionReq).map_err(external_lua_error)
+}
+
+fn compare(_: &Lua, (a, b): (VersionArg, VersionArg)) -> LuaResult<i32> {
+    Ok(match a.0.cmp(&b.0) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    })
+}
+
+fn matches(_: &Lua, (requirement, version): (RequirementArg, VersionArg)) -> LuaResult<bool> {
+    Ok(requirement.0.matches(&version.0))
+}
+//endregion
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LuaVersion(pub Version);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LuaVersionReq(pub VersionReq);
+
+/// Version passed from Lua, either as a [LuaVersion] or as a string, which is parsed leniently.
+pub(crate) struct VersionArg(pub Version);
+
+impl<'lua> FromLua<'lua> for VersionArg {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::String(text) => version::parse_lenient(text.to_str()?)
+                .map(VersionArg)
+                .map_err(external_lua_error),
+            LuaValue::UserData(ref userdata) if userdata.is::<LuaVersion>() => {
+                Ok(VersionArg(userdata.borrow::<LuaVersion>()?.0.clone()))
+            }
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "Version",
+                message: Some("expected a string or a Version".to_string()),
+            })
+        }
+    }
+}
+
+/// Version requirement passed from Lua, either as a [LuaVersionReq] or as a string.
+pub(crate) struct RequirementArg(pub VersionReq);
+
+impl<'lua> FromLua<'lua> for RequirementArg {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::String(text) => VersionReq::parse(text.to_str()?)
+                .map(RequirementArg)
+                .map_err(external_lua_error),
+            LuaValue::UserData(ref userdata) if userdata.is::<LuaVersionReq>() => {
+                Ok(RequirementArg(userdata.borrow::<LuaVersionReq>()?.0.clone()))
+            }
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "VersionReq",
+                message: Some("expected a string or a VersionReq".to_string()),
+            })
+        }
+    }
+}
+
+impl LuaUserData for LuaVersion {
+    fn add_fields<'lua, F: UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.field_get("major", "integer", "Major version, incremented by incompatible changes.", |_, this| {
+            Ok(this.0.major)
+        });
+
+        fields.field_get("minor", "integer", "Minor version, incremented by compatible additions.", |_, this| {
+            Ok(this.0.minor)
+        });
+
+        fields.field_get("patch", "integer", "Patch version, incremented by fixes.", |_, this| {
+            Ok(this.0.patch)
+        });
+
+        fields.field_get("pre", "string", "Pre-release identifiers, eg. `beta.2`, or an empty string for releases.", |_, this| {
+            Ok(this.0.pre.to_string())
+        });
+
+        fields.field_get("build", "string", "Build metadata, which doesn't affect precedence, or an empty string.", |_, this| {
+            Ok(this.0.build.to_string())
+        });
+    }
+
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.method("bump", "(part: \"major\"|\"minor\"|\"patch\") -> Version",
+            "Returns the next release after incrementing the part, and resetting the ones after it. Pre-releases are \
+            bumped to their release if it's the next version of that part, eg. bumping the patch of `1.2.3-beta` \
+            gives `1.2.3`. Fails with `InvalidInput` if the part can't be incremented any further.", |_, this, (part, ): (String, )| {
+            let part = Part::parse(part).map_err(external_lua_error)?;
+            version::bump(&this.0, part)
+                .map(LuaVersion)
+                .map_err(external_lua_error)
+        });
+
+        methods.method("satisfies", "(requirement: string|VersionReq) -> boolean",
+            "Whether this version satisfies the requirement.", |_, this, (requirement, ): (RequirementArg, )| {
+            Ok(requirement.0.matches(&this.0))
+        });
+
+        methods.method("is_prerelease", "() -> boolean", "Whether this version has pre-release identifiers.", |_, this, ()| {
+            Ok(!this.0.pre.is_empty())
+        });
+
+        methods.meta_method(MetaMethod::Eq, "(other: Version) -> boolean",
+            "Whether two versions are identical, including build metadata.", |_, this, (other, ): (LuaVersion, )| {
+            Ok(this.0 == other.0)
+        });
+
+        methods.meta_method(MetaMethod::Lt, "(other: Version) -> boolean",
+            "Whether this version precedes the other.", |_, this, (other, ): (LuaVersion, )| {
+            Ok(this.0 < other.0)
+        });
+
+        methods.meta_method(MetaMethod::Le, "(other: Version) -> boolean",
+            "Whether this version precedes or is identical to the other.", |_, this, (other, ): (LuaVersion, )| {
+            Ok(this.0 <= other.0)
+        });
+
+        methods.meta_method(MetaMethod::ToString, "() -> string", "The version in semver notation.", |_, this, ()| {
+            Ok(this.0.to_string())
+        });
+    }
+}
+
+impl LuaUserData for LuaVersionReq {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.method("matches", "(version: string|Version) -> boolean",
+            "Whether the version satisfies this requirement.", |_, this, (version, ): (VersionArg, )| {
+            Ok(this.0.matches(&version.0))
+        });
+
+        methods.meta_method(MetaMethod::ToString, "() -> string", "The requirement in normalized notation.", |_, this, ()| {
+            Ok(this.0.to_string())
+        });
+    }
+}
@@ -0,0 +1,165 @@
+use ftldat::{Package, PackageEntry};
+use mlua::{Lua, ToLua, UserDataMethods};
+use mlua::prelude::{LuaAnyUserData, LuaResult, LuaTable, LuaUserData, LuaValue};
+
+use crate::buffer::Buffer;
+use crate::io::{Directory, File};
+use crate::lua::api::{ApiMethods, Module};
+use crate::lua::buffer::LuaBytes;
+use crate::lua::error::external_lua_error;
+use crate::lua::ftldat::package_format;
+use crate::lua::io::{inner_path_filters, sandbox};
+use crate::package::{directory_files, extract_all, PackageFormat};
+use crate::progress::aborted;
+use crate::task::{Task, TaskContext};
+
+/// Build the module's exports table, governing what is exposed to Lua.
+pub fn init(lua: &Lua) -> LuaResult<Module<'_>> {
+    let mut exports = Module::new(lua, "task", "Long-running IO and packaging operations, run on a background thread.")?;
+
+    exports.function("spawn_read", "(file: File) -> Task",
+        "Reads the file; the task's result is a `Buffer`.", spawn_read)?;
+    exports.function("spawn_write", "(file: File, data: string|Buffer|integer[]) -> Task",
+        "Writes the data to the file, creating its parent directories.", spawn_write)?;
+    exports.function("spawn_copy", "(file: File, destination: File) -> Task",
+        "Copies the file to the destination, which has to be within the sandbox.", spawn_copy)?;
+    exports.function("spawn_pack", "(directory: Directory, destination: File, options: { format: string?, prefix: string?, filters: string|string[]? }?) -> Task",
+        "Packs the files within the directory matching the filters into a package, under the prefix; the task's result \
+        is the number of entries.", spawn_pack)?;
+    exports.function("spawn_extract", "(file: File, destination: Directory, options: { format: string?, filters: string|string[]? }?) -> Task",
+        "Extracts the entries of a package matching the filters into the directory; the task's result is the number \
+        of extracted entries.", spawn_extract)?;
+    exports.class::<LuaTask>("Task", "Handle of an operation running on a background thread, to be polled from Lua.")?;
+
+    Ok(exports)
+}
+
+//region <Exported adapter functions>
+fn spawn_read(_: &Lua, (file, ): (LuaAnyUserData, )) -> LuaResult<LuaTask> {
+    let file = file_from_lua(file)?;
+    Ok(LuaTask::spawn(move |_| {
+        file.read_to_byte_array()
+            .map(|bytes| TaskOutput::Buffer(Buffer::from(bytes)))
+    }))
+}
+
+fn spawn_write(_: &Lua, (file, content): (LuaAnyUserData, LuaBytes)) -> LuaResult<LuaTask> {
+    let file = file_from_lua(file)?;
+    Ok(LuaTask::spawn(move |_| {
+        file.write_byte_array(content.0)
+            .map(|_| TaskOutput::Nothing)
+    }))
+}
+
+fn spawn_copy(lua: &Lua, (file, destination): (LuaAnyUserData, LuaAnyUserData)) -> LuaResult<LuaTask> {
+    let sandbox = sandbox(lua)?;
+    let file = file_from_lua(file)?;
+    let destination = file_from_lua(destination)?;
+    Ok(LuaTask::spawn(move |_| {
+        file.copy(&sandbox, &destination.path)
+            .map(|_| TaskOutput::Nothing)
+    }))
+}
+
+fn spawn_pack(lua: &Lua, (dir, destination, options): (Directory, LuaAnyUserData, Option<LuaTable>)) -> LuaResult<LuaTask> {
+    let destination = file_from_lua(destination)?;
+    let format = package_format(options.clone())?
+        .unwrap_or(PackageFormat::Dat);
+    let (inner_prefix, filters) = match options {
+        None => (String::new(), Vec::new()),
+        Some(options) => (
+            options.get::<_, Option<String>>("prefix")?.unwrap_or_default(),
+            inner_path_filters(lua, options.get("filters")?)?,
+        )
+    };
+
+    Ok(LuaTask::spawn(move |context| {
+        let files = directory_files(&dir, &inner_prefix, &filters)?;
+        let mut package = Package::new();
+        for (done, (inner_path, file)) in files.iter().enumerate() {
+            if !context.report(done, files.len()) {
+                return Err(aborted());
+            }
+            package.put_entry(PackageEntry::from_byte_array(inner_path, file.read_to_byte_array()?));
+        }
+
+        format.write(package, &destination.path)?;
+        context.report(files.len(), files.len());
+        Ok(TaskOutput::Count(files.len()))
+    }))
+}
+
+fn spawn_extract(lua: &Lua, (file, dest_dir, options): (LuaAnyUserData, Directory, Option<LuaTable>)) -> LuaResult<LuaTask> {
+    let sandbox = sandbox(lua)?;
+    let file = file_from_lua(file)?;
+    let format = package_format(options.clone())?;
+    let filters = match options {
+        None => Vec::new(),
+        Some(options) => inner_path_filters(lua, options.get("filters")?)?
+    };
+
+    Ok(LuaTask::spawn(move |context| {
+        let format = match format {
+            Some(format) => format,
+            None => PackageFormat::detect(&file.path)?
+        };
+        // Packages hold their memory map in an Rc, so they have to be read on the thread that uses them.
+        let package = format.read(&file.path)?;
+
+        let count = extract_all(&sandbox, &package, &dest_dir, &filters, &mut |done, total, _| context.report(done, total))?;
+        context.report(count, count);
+        Ok(TaskOutput::Count(count))
+    }))
+}
+//endregion
+
+/// Files aren't [Clone], so they can't be taken from Lua by value.
+fn file_from_lua(userdata: LuaAnyUserData) -> LuaResult<File> {
+    let file = userdata.borrow::<File>()?;
+    Ok(File::from(file.path.clone()))
+}
+
+enum TaskOutput {
+    Nothing,
+    Count(usize),
+    Buffer(Buffer),
+}
+
+struct LuaTask {
+    task: Task<TaskOutput>,
+}
+
+impl LuaTask {
+    fn spawn<F>(work: F) -> LuaTask
+        where F: FnOnce(&TaskContext) -> crate::error::Result<TaskOutput> + Send + 'static {
+        LuaTask { task: Task::spawn(work) }
+    }
+}
+
+impl LuaUserData for LuaTask {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.method("is_done", "() -> boolean", "Whether the operation has finished, successfully or not.", |_, this, ()| {
+            Ok(this.task.is_done())
+        });
+
+        methods.method("progress", "() -> integer, integer", "Numbers of completed and total steps of the operation.", |_, this, ()| {
+            Ok(this.task.progress())
+        });
+
+        methods.method("cancel", "()", "Stops the operation at its next step; files written up to that point are left in place.", |_, this, ()| {
+            this.task.cancel();
+            Ok(())
+        });
+
+        methods.method_mut("result", "() -> any",
+            "Result of the finished operation, or `nil` if it hasn't finished yet. Raises the operation's error if it failed.", |lua, this, ()| {
+            match this.task.result() {
+                None => Ok(LuaValue::Nil),
+                Some(Err(error)) => Err(external_lua_error(error.clone())),
+                Some(Ok(TaskOutput::Nothing)) => Ok(LuaValue::Nil),
+                Some(Ok(TaskOutput::Count(count))) => count.to_lua(lua),
+                Some(Ok(TaskOutput::Buffer(buffer))) => buffer.clone().to_lua(lua),
+            }
+        });
+    }
+}
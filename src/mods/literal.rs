@@ -0,0 +1,462 @@
+//! Static reading of table literals from Lua source, without executing it.
+use crate::error::{Error, ErrorKind};
+
+/// Value of a Lua expression, as far as it can be known without executing the code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Table(Table),
+    /// Any other expression, eg. a function, a variable or a call.
+    Unknown,
+}
+
+/// Table constructor, with its positional values and its named fields in order of appearance.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub values: Vec<Literal>,
+    pub fields: Vec<(String, Literal)>,
+}
+
+impl Table {
+    /// Value of the last field with the name, the way Lua resolves duplicate fields.
+    pub fn get(&self, name: &str) -> Option<&Literal> {
+        self.fields.iter().rev()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+
+    fn set(&mut self, name: String, value: Literal) {
+        self.fields.push((name, value));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    String(String),
+    Number(f64),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &[
+    "...", "..", "==", "~=", "<=", ">=",
+    "+", "-", "*", "/", "%", "^", "#", "<", ">", "=", "(", ")", "{", "}", "[", "]", ";", ":", ",", ".",
+];
+
+/// Reads the table a chunk returns, either as a constructor, eg. `return { id = "mod" }`, or as a
+/// local variable initialized with one, eg. `local mod = { id = "mod" } ... return mod`. In the
+/// latter case, assignments to the variable's fields at the top level of the chunk, eg.
+/// `mod.version = "1.0"`, are taken into account as well.
+pub fn returned_table(source: &str) -> crate::error::Result<Table> {
+    let tokens = tokenize(source)?;
+    let top_level = top_level_positions(&tokens);
+
+    let return_position = top_level.iter().rev()
+        .copied()
+        .find(|&position| is_name(&tokens, position, "return"))
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Script doesn't return anything at its top level"))?;
+
+    match tokens.get(return_position + 1) {
+        Some(Token::Symbol("{")) => {
+            match Parser::new(&tokens, return_position + 1).value() {
+                Literal::Table(table) => Ok(table),
+                _ => unreachable!("constructors are always read as tables"),
+            }
+        }
+        Some(Token::Name(variable)) => variable_table(&tokens, &top_level, return_position, variable),
+        _ => Err(Error::new(ErrorKind::InvalidData, "Script doesn't return a table or a variable")),
+    }
+}
+
+/// Reads the table assigned to the variable before the position, along with later assignments
+/// to its fields.
+fn variable_table(tokens: &[Token], top_level: &[usize], before: usize, variable: &str) -> crate::error::Result<Table> {
+    let mut result: Option<Table> = None;
+
+    for &position in top_level.iter().take_while(|&&position| position < before) {
+        if !is_name(tokens, position, variable) || is_symbol(tokens, position.wrapping_sub(1), ".") {
+            continue;
+        }
+
+        if is_symbol(tokens, position + 1, "=") && is_symbol(tokens, position + 2, "{") {
+            result = match Parser::new(tokens, position + 2).value() {
+                Literal::Table(table) => Some(table),
+                _ => None,
+            };
+        } else if let Some(table) = result.as_mut() {
+            let field = match (tokens.get(position + 1), tokens.get(position + 2), tokens.get(position + 3)) {
+                (Some(Token::Symbol(".")), Some(Token::Name(name)), Some(Token::Symbol("="))) => Some((name.clone(), position + 4)),
+                (Some(Token::Symbol("[")), Some(Token::String(name)), Some(Token::Symbol("]")))
+                if is_symbol(tokens, position + 4, "=") => Some((name.clone(), position + 5)),
+                _ => None,
+            };
+
+            if let Some((name, value_position)) = field {
+                let mut parser = Parser::new(tokens, value_position);
+                let value = parser.value();
+                // Only take values that aren't followed by the rest of a larger expression
+                let value = match tokens.get(parser.position) {
+                    Some(Token::Symbol(symbol)) if *symbol != ";" => Literal::Unknown,
+                    _ => value,
+                };
+                table.set(name, value);
+            }
+        }
+    }
+
+    result.ok_or_else(|| Error::new(ErrorKind::InvalidData, format!(
+        "Script returns `{}`, which isn't initialized with a table at its top level", variable
+    )))
+}
+
+/// Positions of tokens that aren't within any block, such as a function body or a loop.
+fn top_level_positions(tokens: &[Token]) -> Vec<usize> {
+    let mut result = Vec::new();
+    let mut depth = 0usize;
+
+    for (position, token) in tokens.iter().enumerate() {
+        if depth == 0 {
+            result.push(position);
+        }
+        depth = match block_delta(token) {
+            1 => depth + 1,
+            -1 => depth.saturating_sub(1),
+            _ => depth,
+        };
+    }
+
+    result
+}
+
+/// Whether the token opens (1) or closes (-1) a block. `while` and `for` loops are opened by
+/// their `do`.
+fn block_delta(token: &Token) -> i32 {
+    match token {
+        Token::Name(name) => match name.as_str() {
+            "function" | "if" | "do" | "repeat" => 1,
+            "end" | "until" => -1,
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+fn is_name(tokens: &[Token], position: usize, name: &str) -> bool {
+    matches!(tokens.get(position), Some(Token::Name(token)) if token == name)
+}
+
+fn is_symbol(tokens: &[Token], position: usize, symbol: &str) -> bool {
+    matches!(tokens.get(position), Some(Token::Symbol(token)) if *token == symbol)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [Token], position: usize) -> Parser<'a> {
+        Parser { tokens, position }
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    /// Reads a single operand: a literal, a table constructor, or anything else as unknown.
+    fn value(&mut self) -> Literal {
+        match self.next() {
+            Some(Token::String(value)) => Literal::String(value.clone()),
+            Some(Token::Number(value)) => Literal::Number(*value),
+            Some(Token::Symbol("-")) => match self.tokens.get(self.position) {
+                Some(Token::Number(value)) => {
+                    self.position += 1;
+                    Literal::Number(-value)
+                }
+                _ => Literal::Unknown,
+            },
+            Some(Token::Symbol("{")) => Literal::Table(self.table()),
+            Some(Token::Name(name)) => match name.as_str() {
+                "nil" => Literal::Nil,
+                "true" => Literal::Boolean(true),
+                "false" => Literal::Boolean(false),
+                "function" => {
+                    self.skip_block();
+                    Literal::Unknown
+                }
+                _ => Literal::Unknown,
+            },
+            _ => Literal::Unknown,
+        }
+    }
+
+    /// Reads the fields of a constructor, after its opening brace.
+    fn table(&mut self) -> Table {
+        let mut table = Table::default();
+
+        loop {
+            let key = match (self.tokens.get(self.position), self.tokens.get(self.position + 1)) {
+                (Some(Token::Symbol("}")), _) | (None, _) => {
+                    self.position += 1;
+                    return table;
+                }
+                (Some(Token::Name(name)), Some(Token::Symbol("="))) => {
+                    self.position += 2;
+                    Some(Some(name.clone()))
+                }
+                (Some(Token::Symbol("[")), _) => {
+                    self.position += 1;
+                    let key = match self.value() {
+                        Literal::String(name) => Some(name),
+                        _ => None,
+                    };
+                    self.skip_field();
+                    if !is_symbol(self.tokens, self.position, "]") || !is_symbol(self.tokens, self.position + 1, "=") {
+                        return table;
+                    }
+                    self.position += 2;
+                    Some(key)
+                }
+                _ => None,
+            };
+
+            let value = self.value();
+            let complete = matches!(self.tokens.get(self.position), Some(Token::Symbol("," | ";" | "}")) | None);
+            let value = if complete { value } else { Literal::Unknown };
+            self.skip_field();
+
+            match key {
+                None => table.values.push(value),
+                Some(Some(name)) => table.set(name, value),
+                // Fields under keys other than strings aren't read
+                Some(None) => {}
+            }
+
+            if matches!(self.tokens.get(self.position), Some(Token::Symbol("," | ";"))) {
+                self.position += 1;
+            }
+        }
+    }
+
+    /// Skips to the end of the current field, or the closing bracket of a computed key: the
+    /// next separator, `]` or `}` not nested in brackets or blocks.
+    fn skip_field(&mut self) {
+        let mut depth = 0i32;
+        while let Some(token) = self.tokens.get(self.position) {
+            match token {
+                Token::Symbol("(" | "{" | "[") => depth += 1,
+                Token::Symbol(")" | "}" | "]") | Token::Symbol("," | ";") if depth == 0 => return,
+                Token::Symbol(")" | "}" | "]") => depth -= 1,
+                _ => depth += block_delta(token),
+            }
+            self.position += 1;
+        }
+    }
+
+    /// Skips the rest of a block, up to and including its `end`.
+    fn skip_block(&mut self) {
+        let mut depth = 1;
+        while let Some(token) = self.next() {
+            depth += block_delta(token);
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> crate::error::Result<Vec<Token>> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    while position < bytes.len() {
+        let byte = bytes[position];
+        let rest = &source[position..];
+
+        if byte.is_ascii_whitespace() {
+            position += 1;
+        } else if rest.starts_with("--") {
+            position += 2;
+            position += match long_bracket(&source[position..]) {
+                Some((_, length)) => length,
+                None => source[position..].find('\n').unwrap_or(source.len() - position),
+            };
+        } else if byte == b'"' || byte == b'\'' {
+            let (value, length) = quoted_string(rest)?;
+            tokens.push(Token::String(value));
+            position += length;
+        } else if byte == b'[' && long_bracket(rest).is_some() {
+            let (value, length) = long_bracket(rest).unwrap();
+            tokens.push(Token::String(value));
+            position += length;
+        } else if byte.is_ascii_digit() || (byte == b'.' && bytes.get(position + 1).is_some_and(u8::is_ascii_digit)) {
+            let length = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '.')).unwrap_or(rest.len());
+            // Exponents may be signed, eg. 1e-5
+            let length = match rest.as_bytes().get(length) {
+                Some(b'-' | b'+') if matches!(rest.as_bytes()[length - 1], b'e' | b'E') && !rest.starts_with("0x") => {
+                    length + 1 + rest[length + 1..].find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len() - length - 1)
+                }
+                _ => length,
+            };
+            tokens.push(Token::Number(number(&rest[..length])?));
+            position += length;
+        } else if byte.is_ascii_alphabetic() || byte == b'_' {
+            let length = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..length].to_string()));
+            position += length;
+        } else {
+            let symbol = SYMBOLS.iter()
+                .find(|symbol| rest.starts_with(**symbol))
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Unexpected character '{}'", rest.chars().next().unwrap())))?;
+            tokens.push(Token::Symbol(symbol));
+            position += symbol.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn number(text: &str) -> crate::error::Result<f64> {
+    let invalid = || Error::new(ErrorKind::InvalidData, format!("Malformed number '{}'", text));
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).map(|value| value as f64).map_err(|_| invalid()),
+        None => text.parse().map_err(|_| invalid()),
+    }
+}
+
+/// Reads a long string or comment body, eg. `[==[content]==]`, returning its content and length.
+fn long_bracket(text: &str) -> Option<(String, usize)> {
+    let level = text.strip_prefix('[')?.find(|c: char| c != '=')?;
+    if text.as_bytes().get(level + 1) != Some(&b'[') {
+        return None;
+    }
+
+    let open_length = level + 2;
+    let close = format!("]{}]", "=".repeat(level));
+    let content_length = text[open_length..].find(&close)?;
+    let content = &text[open_length..open_length + content_length];
+    // A newline right after the opening bracket isn't part of the string
+    let content = content.strip_prefix("\r\n").or_else(|| content.strip_prefix('\n')).unwrap_or(content);
+
+    Some((content.to_string(), open_length + content_length + close.len()))
+}
+
+/// Reads a string in single or double quotes, returning its unescaped value and length.
+fn quoted_string(text: &str) -> crate::error::Result<(String, usize)> {
+    let quote = text.chars().next().unwrap();
+    let mut value = String::new();
+    let mut chars = text.char_indices().skip(1).peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            _ if c == quote => return Ok((value, index + 1)),
+            '\n' => break,
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, 'a')) => value.push('\u{7}'),
+                Some((_, 'b')) => value.push('\u{8}'),
+                Some((_, 'f')) => value.push('\u{c}'),
+                Some((_, 'v')) => value.push('\u{b}'),
+                Some((_, digit)) if digit.is_ascii_digit() => {
+                    let mut code = digit.to_digit(10).unwrap();
+                    for _ in 0..2 {
+                        match chars.peek() {
+                            Some((_, digit)) if digit.is_ascii_digit() => {
+                                code = code * 10 + digit.to_digit(10).unwrap();
+                                chars.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    value.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                Some((_, escaped)) => value.push(escaped),
+                None => break,
+            },
+            _ => value.push(c),
+        }
+    }
+
+    Err(Error::new(ErrorKind::InvalidData, "Unfinished string"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use crate::mods::literal::{Literal, returned_table};
+
+    #[test]
+    fn returned_constructor_should_be_read_without_executing_functions() {
+        let table = returned_table(r#"
+            -- init.lua
+            return {
+                id = "my_mod",
+                name = 'My "Mod"',
+                version = 1.5,
+                requirements = { "a", [[b]] },
+                ["icon"] = "img/icon.png",
+                init = function(self)
+                    local t = { id = "not_this" }
+                    if t then return t end
+                end,
+                computed = "a" .. "b",
+                hidden = false;
+            }
+        "#).unwrap();
+
+        assert_eq!(Some(&Literal::String("my_mod".to_string())), table.get("id"));
+        assert_eq!(Some(&Literal::String("My \"Mod\"".to_string())), table.get("name"));
+        assert_eq!(Some(&Literal::Number(1.5)), table.get("version"));
+        assert_eq!(Some(&Literal::String("img/icon.png".to_string())), table.get("icon"));
+        assert_eq!(Some(&Literal::Unknown), table.get("init"));
+        assert_eq!(Some(&Literal::Unknown), table.get("computed"));
+        assert_eq!(Some(&Literal::Boolean(false)), table.get("hidden"));
+        match table.get("requirements") {
+            Some(Literal::Table(requirements)) => assert_eq!(
+                vec![Literal::String("a".to_string()), Literal::String("b".to_string())],
+                requirements.values
+            ),
+            other => panic!("Unexpected requirements: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn returned_variable_should_include_later_field_assignments() {
+        let table = returned_table(r#"
+            local mod = {
+                id = "my_mod",
+                version = "1.0",
+            }
+            mod.version = "1.1"
+            mod["name"] = "Name"
+
+            function mod:init()
+                mod.version = "ignored"
+                return {}
+            end
+
+            mod.description = "Part of " .. "a call"
+            return mod
+        "#).unwrap();
+
+        assert_eq!(Some(&Literal::String("1.1".to_string())), table.get("version"));
+        assert_eq!(Some(&Literal::String("Name".to_string())), table.get("name"));
+        assert_eq!(Some(&Literal::Unknown), table.get("description"));
+    }
+
+    #[test]
+    fn scripts_not_returning_tables_should_be_rejected() {
+        assert_eq!(ErrorKind::InvalidData, returned_table("local x = 1").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidData, returned_table("return make_mod()").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidData, returned_table("return \"unfinished").unwrap_err().kind());
+    }
+}
@@ -0,0 +1,134 @@
+//! Mod metadata, from the table returned by a mod's init script.
+use crate::error::{Error, ErrorKind};
+use crate::mods::{Dependency, ModInfo, parse_requirement, parse_version};
+use crate::mods::literal::{Literal, returned_table, Table};
+
+/// Reads the metadata of a mod from its init script. Required dependencies are listed in
+/// `requirements`, or in `dependencies`, either as a list of ids or as a table of version
+/// requirements by id; optional ones are listed the same way in `optionalDependencies`, and
+/// mods this one can't be loaded along with in `conflicts`.
+pub fn read_metadata(source: &str) -> crate::error::Result<ModInfo> {
+    let table = returned_table(source)?;
+
+    let id = match table.get("id") {
+        Some(Literal::String(id)) if !id.is_empty() => id.clone(),
+        _ => return Err(Error::new(ErrorKind::InvalidData, "Mod doesn't have a literal string `id`")),
+    };
+    let version_text = text(table.get("version"));
+    let version = version_text.as_deref().and_then(parse_version);
+
+    let mut dependencies = Vec::new();
+    for id in ids(&table, "requirements")? {
+        dependencies.push(Dependency { id, requirement: None, optional: false });
+    }
+    dependencies.extend(self::dependencies(&table, "dependencies", false)?);
+    dependencies.extend(self::dependencies(&table, "optionalDependencies", true)?);
+
+    Ok(ModInfo {
+        id,
+        name: text(table.get("name")),
+        version_text,
+        version,
+        directory: None,
+        dependencies,
+        conflicts: ids(&table, "conflicts")?,
+    })
+}
+
+/// Reads a list of ids, or a table of version requirements by id, where `true` stands for any
+/// version.
+fn dependencies(table: &Table, field: &str, optional: bool) -> crate::error::Result<Vec<Dependency>> {
+    let mut result: Vec<Dependency> = ids(table, field)?.into_iter()
+        .map(|id| Dependency { id, requirement: None, optional })
+        .collect();
+
+    if let Some(Literal::Table(dependencies)) = table.get(field) {
+        for (id, requirement) in &dependencies.fields {
+            let requirement = match requirement {
+                Literal::Boolean(true) => None,
+                literal => match text(Some(literal)) {
+                    Some(requirement) => Some(parse_requirement(&requirement)?),
+                    None => return Err(invalid_field(field)),
+                },
+            };
+            result.push(Dependency { id: id.clone(), requirement, optional });
+        }
+    }
+
+    Ok(result)
+}
+
+/// Reads the positional values of the field's table, which have to be strings.
+fn ids(table: &Table, field: &str) -> crate::error::Result<Vec<String>> {
+    match table.get(field) {
+        None | Some(Literal::Nil) => Ok(Vec::new()),
+        Some(Literal::Table(ids)) => ids.values.iter()
+            .map(|id| match id {
+                Literal::String(id) => Ok(id.clone()),
+                _ => Err(invalid_field(field)),
+            })
+            .collect(),
+        Some(_) => Err(invalid_field(field)),
+    }
+}
+
+/// Reads a string, or a number as it'd be written in Lua, eg. the version `1.5`.
+fn text(literal: Option<&Literal>) -> Option<String> {
+    match literal {
+        Some(Literal::String(text)) => Some(text.clone()),
+        Some(Literal::Number(number)) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+fn invalid_field(field: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Mod's `{}` isn't a literal table of mod ids or version requirements", field))
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use crate::error::ErrorKind;
+    use crate::mods::metadata::read_metadata;
+
+    #[test]
+    fn metadata_should_include_all_kinds_of_dependencies() {
+        let info = read_metadata(r#"
+            local mod = {
+                id = "my_mod",
+                name = "My Mod",
+                version = 1.5,
+                requirements = { "mod_loader" },
+                dependencies = { modApiExt = "1.18", memedit = true },
+                optionalDependencies = { "weather" },
+                conflicts = { "old_mod" },
+            }
+            function mod:init() end
+            return mod
+        "#).unwrap();
+
+        assert_eq!("my_mod", info.id);
+        assert_eq!(Some("My Mod".to_string()), info.name);
+        assert_eq!(Some("1.5".to_string()), info.version_text);
+        assert_eq!(Some(Version::new(1, 5, 0)), info.version);
+        assert_eq!(vec!["old_mod".to_string()], info.conflicts);
+
+        let dependencies: Vec<(&str, Option<String>, bool)> = info.dependencies.iter()
+            .map(|dependency| (dependency.id.as_str(), dependency.requirement.as_ref().map(|r| r.to_string()), dependency.optional))
+            .collect();
+        assert_eq!(vec![
+            ("mod_loader", None, false),
+            ("modApiExt", Some(">=1.18".to_string()), false),
+            ("memedit", None, false),
+            ("weather", None, true),
+        ], dependencies);
+    }
+
+    #[test]
+    fn malformed_metadata_should_be_rejected() {
+        assert_eq!(ErrorKind::InvalidData, read_metadata("return { id = make_id() }").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidData, read_metadata("return { id = 'a', requirements = 'b' }").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidData, read_metadata("return { id = 'a', dependencies = { b = 'latest' } }").unwrap_err().kind());
+    }
+}
@@ -0,0 +1,160 @@
+//! Discovery of Into the Breach mods and resolution of the order to load them in, from the
+//! metadata in their `scripts/init.lua`, which is read without executing it.
+use std::path::{Path, PathBuf};
+
+use semver::{Version, VersionReq};
+
+use crate::error::{Error, ErrorKind, WithPath};
+use crate::io::Directory;
+pub use order::{LoadOrder, load_order};
+
+mod literal;
+mod metadata;
+mod order;
+
+/// Path of the script holding a mod's metadata, relative to the mod's directory.
+pub const INIT_SCRIPT: &str = "scripts/init.lua";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModInfo {
+    pub id: String,
+    pub name: Option<String>,
+    /// Version as written in the metadata.
+    pub version_text: Option<String>,
+    /// Version parsed as semver, if it could be.
+    pub version: Option<Version>,
+    /// Directory of the mod, if it was scanned from one.
+    pub directory: Option<PathBuf>,
+    pub dependencies: Vec<Dependency>,
+    /// Ids of mods this one can't be loaded along with.
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub id: String,
+    pub requirement: Option<VersionReq>,
+    /// Optional dependencies are loaded first if they're installed, but aren't required.
+    pub optional: bool,
+}
+
+impl Dependency {
+    /// Whether the installed version of the dependency satisfies the requirement. Mods without a
+    /// parseable version only satisfy dependencies without a requirement.
+    pub fn is_satisfied_by(&self, version: Option<&Version>) -> bool {
+        match (&self.requirement, version) {
+            (None, _) => true,
+            (Some(requirement), Some(version)) => requirement.matches(version),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Mod that couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMod {
+    pub directory: PathBuf,
+    pub error: Error,
+}
+
+/// Reads the metadata of the mods in the subdirectories of the mods directory, ordered by name.
+/// Subdirectories without an init script aren't mods, and are left out.
+pub fn scan(mods_directory: &Directory) -> crate::error::Result<(Vec<ModInfo>, Vec<InvalidMod>)> {
+    let mut directories = mods_directory.directories()?;
+    directories.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut mods = Vec::new();
+    let mut invalid = Vec::new();
+    for directory in directories {
+        let init_script = directory.path.join(INIT_SCRIPT);
+        if !init_script.is_file() {
+            continue;
+        }
+
+        match read_mod(&init_script) {
+            Ok(mut info) => {
+                info.directory = Some(directory.path);
+                mods.push(info);
+            }
+            Err(error) => invalid.push(InvalidMod { directory: directory.path, error }),
+        }
+    }
+
+    Ok((mods, invalid))
+}
+
+fn read_mod(init_script: &Path) -> crate::error::Result<ModInfo> {
+    let source = std::fs::read(init_script).with_path(init_script)?;
+    metadata::read_metadata(&String::from_utf8_lossy(&source)).with_path(init_script)
+}
+
+/// Reads the metadata of a mod from the source of its init script, without executing it.
+pub fn read_metadata(source: &str) -> crate::error::Result<ModInfo> {
+    metadata::read_metadata(source)
+}
+
+/// Parses a mod's version leniently, see [crate::version::parse_lenient], eg. `v1.2` or `1.2b`.
+pub fn parse_version(text: &str) -> Option<Version> {
+    crate::version::parse_lenient(text).ok()
+}
+
+/// Parses a dependency's version requirement. A bare version is the minimum version, the way
+/// the mod loader treats it, eg. `1.2` is the same as `>=1.2`.
+pub fn parse_requirement(text: &str) -> crate::error::Result<VersionReq> {
+    crate::version::parse_minimum_requirement(text).map_err(|error| Error::new(ErrorKind::InvalidData, format!(
+        "Malformed version requirement '{}': {}", text.trim(), error
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+    use tempfile::TempDir;
+
+    use crate::error::ErrorKind;
+    use crate::io::Directory;
+    use crate::mods::{parse_requirement, parse_version, scan};
+
+    #[test]
+    fn versions_should_be_parsed_leniently() {
+        assert_eq!(Some(Version::new(1, 2, 3)), parse_version("1.2.3"));
+        assert_eq!(Some(Version::new(1, 2, 0)), parse_version("v1.2"));
+        assert_eq!(Some(Version::new(3, 0, 0)), parse_version(" 3 "));
+        assert_eq!(Some(Version::parse("1.2.0-b").unwrap()), parse_version("1.2b"));
+        assert_eq!(None, parse_version("beta"));
+    }
+
+    #[test]
+    fn bare_versions_should_be_minimum_requirements() {
+        let requirement = parse_requirement("1.2").unwrap();
+
+        assert!(requirement.matches(&Version::new(1, 2, 0)));
+        assert!(requirement.matches(&Version::new(2, 0, 0)));
+        assert!(!requirement.matches(&Version::new(1, 1, 9)));
+        assert!(!parse_requirement("^1.2").unwrap().matches(&Version::new(2, 0, 0)));
+        assert_eq!(ErrorKind::InvalidData, parse_requirement("newest").unwrap_err().kind());
+    }
+
+    #[test]
+    fn scan_should_read_mods_and_report_invalid_ones() {
+        let tmp_dir = TempDir::new().unwrap();
+        let write = |mod_directory: &str, source: &str| {
+            let scripts = tmp_dir.path().join(mod_directory).join("scripts");
+            std::fs::create_dir_all(&scripts).unwrap();
+            std::fs::write(scripts.join("init.lua"), source).unwrap();
+        };
+        write("b_mod", r#"return { id = "b", version = "1.0" }"#);
+        write("a_mod", r#"return { id = "a" }"#);
+        write("broken", r#"return { name = "No id" }"#);
+        std::fs::create_dir_all(tmp_dir.path().join("not_a_mod")).unwrap();
+
+        let (mods, invalid) = scan(&Directory::from(tmp_dir.path())).unwrap();
+
+        let ids: Vec<&str> = mods.iter().map(|info| info.id.as_str()).collect();
+        assert_eq!(vec!["a", "b"], ids);
+        assert_eq!(Some(tmp_dir.path().join("a_mod")), mods[0].directory);
+        assert_eq!(1, invalid.len());
+        assert_eq!(tmp_dir.path().join("broken"), invalid[0].directory);
+        assert_eq!(ErrorKind::InvalidData, invalid[0].error.kind());
+    }
+}
@@ -0,0 +1,332 @@
+//! Order to load mods in, so that each one is loaded after its dependencies.
+use std::collections::{HashMap, HashSet};
+
+use semver::VersionReq;
+
+use crate::mods::ModInfo;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadOrder {
+    /// Ids of the mods to load, in order.
+    pub order: Vec<String>,
+    /// Ids of mods that can't be loaded, because of their own problems or their dependencies'.
+    pub excluded: Vec<String>,
+    pub missing: Vec<MissingDependency>,
+    /// Required dependencies that depend on each other, each cycle in the order of the mods.
+    pub cycles: Vec<Vec<String>>,
+    pub conflicts: Vec<Conflict>,
+    /// Ids used by more than one mod, of which only the first one is loaded.
+    pub duplicates: Vec<String>,
+}
+
+/// Required dependency that isn't installed, or whose installed version doesn't satisfy the
+/// requirement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingDependency {
+    pub id: String,
+    pub dependency: String,
+    pub requirement: Option<VersionReq>,
+    /// Version of the installed dependency, as written in its metadata.
+    pub installed: Option<String>,
+}
+
+/// Mod excluded because another mod it conflicts with is installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub id: String,
+    pub conflict: String,
+}
+
+/// Resolves the order to load the mods in. Mods are excluded if a required dependency is missing
+/// or excluded itself, if they're in a cycle of required dependencies, or if they conflict with
+/// another mod that isn't excluded. Conflicts are only checked once the other reasons have been
+/// applied, so that mods aren't excluded over conflicts with mods that can't be loaded anyway.
+/// Optional dependencies are loaded first if they can be, and ignored if they'd form a cycle.
+/// Otherwise, mods keep the order they're listed in.
+pub fn load_order(mods: &[ModInfo]) -> LoadOrder {
+    let mut result = LoadOrder::default();
+
+    let mut index: HashMap<&str, &ModInfo> = HashMap::new();
+    let mut candidates: Vec<&ModInfo> = Vec::new();
+    for info in mods {
+        if index.contains_key(info.id.as_str()) {
+            result.duplicates.push(info.id.clone());
+        } else {
+            index.insert(&info.id, info);
+            candidates.push(info);
+        }
+    }
+
+    let mut excluded: HashSet<&str> = HashSet::new();
+    for info in &candidates {
+        for dependency in info.dependencies.iter().filter(|dependency| !dependency.optional) {
+            let installed = index.get(dependency.id.as_str());
+            if !installed.is_some_and(|installed| dependency.is_satisfied_by(installed.version.as_ref())) {
+                result.missing.push(MissingDependency {
+                    id: info.id.clone(),
+                    dependency: dependency.id.clone(),
+                    requirement: dependency.requirement.clone(),
+                    installed: installed.and_then(|installed| installed.version_text.clone()),
+                });
+                excluded.insert(&info.id);
+            }
+        }
+    }
+
+    for cycle in required_cycles(&candidates) {
+        excluded.extend(cycle.iter().copied());
+        result.cycles.push(cycle.into_iter().map(str::to_string).collect());
+    }
+    exclude_dependents(&candidates, &mut excluded);
+
+    for info in &candidates {
+        if excluded.contains(info.id.as_str()) {
+            continue;
+        }
+        let conflict = info.conflicts.iter()
+            .find(|conflict| index.contains_key(conflict.as_str()) && !excluded.contains(conflict.as_str()));
+        if let Some(conflict) = conflict {
+            result.conflicts.push(Conflict { id: info.id.clone(), conflict: conflict.clone() });
+            excluded.insert(&info.id);
+        }
+    }
+    exclude_dependents(&candidates, &mut excluded);
+
+    let mut remaining: Vec<&ModInfo> = Vec::new();
+    for info in candidates {
+        if excluded.contains(info.id.as_str()) {
+            result.excluded.push(info.id.clone());
+        } else {
+            remaining.push(info);
+        }
+    }
+
+    let mut loaded: HashSet<&str> = HashSet::new();
+    while !remaining.is_empty() {
+        let is_ready = |info: &&ModInfo, with_optional: bool| info.dependencies.iter()
+            .filter(|dependency| with_optional || !dependency.optional)
+            .all(|dependency| loaded.contains(dependency.id.as_str())
+                || (dependency.optional && !remaining.iter().any(|other| other.id == dependency.id)));
+
+        // Required dependencies of remaining mods don't form cycles, so one of them is always ready
+        // when optional dependencies are ignored
+        let position = remaining.iter().position(|info| is_ready(info, true))
+            .or_else(|| remaining.iter().position(|info| is_ready(info, false)))
+            .expect("remaining mods have no cycles of required dependencies");
+
+        let info = remaining.remove(position);
+        loaded.insert(&info.id);
+        result.order.push(info.id.clone());
+    }
+
+    result
+}
+
+/// Excludes mods requiring excluded mods, since they can't be loaded either, until there are no
+/// more of them.
+fn exclude_dependents<'a>(candidates: &[&'a ModInfo], excluded: &mut HashSet<&'a str>) {
+    loop {
+        let newly_excluded: Vec<&str> = candidates.iter()
+            .filter(|info| !excluded.contains(info.id.as_str()))
+            .filter(|info| required(info).any(|dependency| excluded.contains(dependency)))
+            .map(|info| info.id.as_str())
+            .collect();
+        if newly_excluded.is_empty() {
+            break;
+        }
+        excluded.extend(newly_excluded);
+    }
+}
+
+fn required(info: &ModInfo) -> impl Iterator<Item=&str> {
+    info.dependencies.iter()
+        .filter(|dependency| !dependency.optional)
+        .map(|dependency| dependency.id.as_str())
+}
+
+/// Finds the cycles of required dependencies among the mods, as strongly connected components of
+/// the dependency graph, with Tarjan's algorithm.
+fn required_cycles<'a>(mods: &[&'a ModInfo]) -> Vec<Vec<&'a str>> {
+    struct State<'a, 'b> {
+        mods: &'b [&'a ModInfo],
+        positions: HashMap<&'a str, usize>,
+        indices: Vec<Option<usize>>,
+        low_links: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        next_index: usize,
+        cycles: Vec<Vec<&'a str>>,
+    }
+
+    fn visit(state: &mut State, node: usize) {
+        state.indices[node] = Some(state.next_index);
+        state.low_links[node] = state.next_index;
+        state.next_index += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+
+        let mut self_dependent = false;
+        for dependency in required(state.mods[node]) {
+            let Some(&dependency) = state.positions.get(dependency) else {
+                continue;
+            };
+            self_dependent |= dependency == node;
+            match state.indices[dependency] {
+                None => {
+                    visit(state, dependency);
+                    state.low_links[node] = state.low_links[node].min(state.low_links[dependency]);
+                }
+                Some(index) if state.on_stack[dependency] => {
+                    state.low_links[node] = state.low_links[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(state.low_links[node]) == state.indices[node] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+
+            if component.len() > 1 || self_dependent {
+                component.sort_unstable();
+                state.cycles.push(component.into_iter().map(|member| state.mods[member].id.as_str()).collect());
+            }
+        }
+    }
+
+    let mut state = State {
+        mods,
+        positions: mods.iter().enumerate().map(|(position, info)| (info.id.as_str(), position)).collect(),
+        indices: vec![None; mods.len()],
+        low_links: vec![0; mods.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; mods.len()],
+        next_index: 0,
+        cycles: Vec::new(),
+    };
+    for node in 0..mods.len() {
+        if state.indices[node].is_none() {
+            visit(&mut state, node);
+        }
+    }
+
+    state.cycles.sort_by_key(|cycle| state.positions[cycle[0]]);
+    state.cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mods::{Dependency, ModInfo, parse_requirement, parse_version};
+    use crate::mods::order::load_order;
+
+    fn info(id: &str, version: &str, dependencies: &[&str]) -> ModInfo {
+        ModInfo {
+            id: id.to_string(),
+            name: None,
+            version_text: Some(version.to_string()),
+            version: parse_version(version),
+            directory: None,
+            dependencies: dependencies.iter()
+                .map(|dependency| {
+                    let (dependency, optional) = match dependency.strip_suffix('?') {
+                        Some(dependency) => (dependency, true),
+                        None => (*dependency, false),
+                    };
+                    let (id, requirement) = match dependency.split_once(' ') {
+                        Some((id, requirement)) => (id, Some(parse_requirement(requirement).unwrap())),
+                        None => (dependency, None),
+                    };
+                    Dependency { id: id.to_string(), requirement, optional }
+                })
+                .collect(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn dependencies_should_be_loaded_first() {
+        let mods = [
+            info("a", "1.0", &["b", "c?"]),
+            info("b", "1.0", &["c >=1.2"]),
+            info("c", "1.2", &[]),
+            info("d", "1.0", &["missing?"]),
+        ];
+
+        let order = load_order(&mods);
+
+        assert_eq!(vec!["c", "b", "a", "d"], order.order);
+        assert!(order.excluded.is_empty());
+    }
+
+    #[test]
+    fn missing_dependencies_should_exclude_dependents() {
+        let mods = [
+            info("a", "1.0", &["b"]),
+            info("b", "1.0", &["c >=2"]),
+            info("c", "1.2", &[]),
+            info("d", "1.0", &["missing"]),
+        ];
+
+        let order = load_order(&mods);
+
+        assert_eq!(vec!["c"], order.order);
+        assert_eq!(vec!["a", "b", "d"], order.excluded);
+        assert_eq!(2, order.missing.len());
+        assert_eq!(("b", "c", Some("1.2".to_string())), (order.missing[0].id.as_str(), order.missing[0].dependency.as_str(), order.missing[0].installed.clone()));
+        assert_eq!(("d", "missing", None), (order.missing[1].id.as_str(), order.missing[1].dependency.as_str(), order.missing[1].installed.clone()));
+    }
+
+    #[test]
+    fn cycles_should_be_reported_unless_optional() {
+        let mods = [
+            info("a", "1.0", &["b"]),
+            info("b", "1.0", &["a"]),
+            info("c", "1.0", &["c"]),
+            info("d", "1.0", &["e?"]),
+            info("e", "1.0", &["d?"]),
+            info("f", "1.0", &["a"]),
+        ];
+
+        let order = load_order(&mods);
+
+        assert_eq!(vec![vec!["a", "b"], vec!["c"]], order.cycles);
+        assert_eq!(vec!["a", "b", "c", "f"], order.excluded);
+        assert_eq!(vec!["d", "e"], order.order);
+    }
+
+    #[test]
+    fn conflicts_and_duplicates_should_exclude_mods() {
+        let mut conflicting = info("a", "1.0", &[]);
+        conflicting.conflicts.push("b".to_string());
+        let mods = [conflicting, info("b", "1.0", &[]), info("b", "2.0", &[])];
+
+        let order = load_order(&mods);
+
+        assert_eq!(vec!["b"], order.order);
+        assert_eq!(vec!["a"], order.excluded);
+        assert_eq!(("a", "b"), (order.conflicts[0].id.as_str(), order.conflicts[0].conflict.as_str()));
+        assert_eq!(vec!["b"], order.duplicates);
+    }
+
+    #[test]
+    fn conflicts_with_excluded_mods_should_be_ignored() {
+        let mut conflicting = info("a", "1.0", &[]);
+        conflicting.conflicts.push("b".to_string());
+        let mut dependent = info("d", "1.0", &["a"]);
+        dependent.conflicts.push("e".to_string());
+        let mods = [conflicting, info("b", "1.0", &["c"]), info("c", "1.0", &["missing"]), dependent, info("e", "1.0", &[])];
+
+        let order = load_order(&mods);
+
+        assert_eq!(vec!["a", "e"], order.order);
+        assert_eq!(vec!["b", "c", "d"], order.excluded);
+        assert_eq!(1, order.conflicts.len());
+        assert_eq!(("d", "e"), (order.conflicts[0].id.as_str(), order.conflicts[0].conflict.as_str()));
+    }
+}
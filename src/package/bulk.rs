@@ -0,0 +1,137 @@
+use ftldat::Package;
+use path_absolutize::Absolutize;
+
+use crate::error::{Error, ErrorKind};
+use crate::io::{Directory, File, Sandbox};
+use crate::package::InnerPathFilter;
+use crate::progress::{aborted, Progress};
+
+pub fn matches_any<S: AsRef<str>>(filters: &[InnerPathFilter], path: S) -> bool {
+    filters.is_empty() || filters.iter().any(|filter| filter.matches(&path))
+}
+
+/// Lists all files within the specified directory that match the filters, paired with the
+/// inner path they should be stored under in a package.
+pub fn directory_files(dir: &Directory, inner_prefix: &str, filters: &[InnerPathFilter]) -> crate::error::Result<Vec<(String, File)>> {
+    let inner_prefix = inner_prefix.trim_end_matches('/');
+    let mut result = Vec::new();
+
+    for file in dir.files_recursive()? {
+        let relative_path = dir.relativize(&file.path)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "File is not within the directory").with_path(&file.path))?;
+        if !matches_any(filters, &relative_path) {
+            continue;
+        }
+
+        let inner_path = if inner_prefix.is_empty() {
+            relative_path
+        } else {
+            format!("{}/{}", inner_prefix, relative_path)
+        };
+        result.push((inner_path, file));
+    }
+
+    Ok(result)
+}
+
+/// Writes all entries of the package that match the filters into the specified directory,
+/// preserving their inner paths. Returns the number of files written.
+///
+/// A relative destination is resolved against the sandbox's game directory, like every other
+/// path checked against the sandbox. Nothing is written if any entry would end up outside of the
+/// destination directory. If `progress` aborts the operation, files written up to that point are
+/// left in place.
+pub fn extract_all(sandbox: &Sandbox, package: &Package, dest_dir: &Directory, filters: &[InnerPathFilter], progress: &mut Progress) -> crate::error::Result<usize> {
+    let dest_path = sandbox.resolve(&dest_dir.path)?;
+    if !sandbox.is_whitelisted(&dest_path)? {
+        return Err(Error::new(ErrorKind::NotAllowed, "Path does not point to an allowed directory").with_path(&dest_dir.path));
+    }
+
+    let mut targets = Vec::new();
+    for inner_path in package.inner_paths() {
+        if !matches_any(filters, &inner_path) {
+            continue;
+        }
+
+        let target = dest_path.join(&inner_path).absolutize()?.to_path_buf();
+        if !target.starts_with(&dest_path) {
+            return Err(Error::new(ErrorKind::NotAllowed, format!("Entry '{}' points outside of the destination directory", inner_path)));
+        }
+        targets.push((inner_path, File::from(target)));
+    }
+
+    for (done, (inner_path, file)) in targets.iter().enumerate() {
+        if !progress(done, targets.len(), inner_path) {
+            return Err(aborted());
+        }
+
+        let content = package.content_by_path(inner_path)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Entry '{}' doesn't exist", inner_path)))?;
+        file.write_byte_array(content)?;
+    }
+
+    progress(targets.len(), targets.len(), "");
+    Ok(targets.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use ftldat::{Package, PackageEntry};
+
+    use crate::error::ErrorKind;
+    use crate::io::{Directory, Sandbox};
+    use crate::package::bulk::{directory_files, extract_all};
+    use crate::package::InnerPathFilter;
+
+    #[test]
+    fn directory_files_should_apply_prefix_and_filters() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(tmp_dir.path().join("scripts")).unwrap();
+        std::fs::write(tmp_dir.path().join("scripts/init.lua"), "").unwrap();
+        std::fs::write(tmp_dir.path().join("readme.txt"), "").unwrap();
+
+        let filters = vec![InnerPathFilter::parse("scripts/").unwrap()];
+        let inner_paths: Vec<String> = directory_files(&Directory::from(tmp_dir.path()), "mods/", &filters)
+            .unwrap()
+            .into_iter()
+            .map(|(inner_path, _)| inner_path)
+            .collect();
+
+        assert_eq!(vec!["mods/scripts/init.lua".to_string()], inner_paths);
+    }
+
+    #[test]
+    fn extract_all_should_stop_when_progress_aborts() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let sandbox = Sandbox::new(tmp_dir.path(), tmp_dir.path().join("save")).unwrap();
+        let mut package = Package::new();
+        package.put_entry(PackageEntry::from_string("a.txt", "a"));
+        package.put_entry(PackageEntry::from_string("b.txt", "b"));
+
+        let mut calls = Vec::new();
+        let result = extract_all(&sandbox, &package, &Directory::from(tmp_dir.path()), &[], &mut |done, total, _| {
+            calls.push((done, total));
+            done < 1
+        });
+
+        assert_eq!(ErrorKind::Aborted, result.unwrap_err().kind());
+        assert_eq!(vec![(0, 2), (1, 2)], calls);
+        assert_eq!(1, std::fs::read_dir(tmp_dir.path()).unwrap().count());
+    }
+
+    #[test]
+    fn extract_all_should_resolve_relative_destination_against_game_directory() {
+        // Tests run in the crate's directory, which lies outside of the sandbox
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let sandbox = Sandbox::new(tmp_dir.path().join("game"), tmp_dir.path().join("save")).unwrap();
+        let mut package = Package::new();
+        package.put_entry(PackageEntry::from_string("x.txt", "x"));
+        let dest_dir = Directory::from("extract_all_relative_destination");
+
+        let count = extract_all(&sandbox, &package, &dest_dir, &[], &mut |_, _, _| true).unwrap();
+
+        assert_eq!(1, count);
+        assert!(tmp_dir.path().join("game/extract_all_relative_destination/x.txt").exists());
+        assert!(!std::env::current_dir().unwrap().join("extract_all_relative_destination").exists());
+    }
+}
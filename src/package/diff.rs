@@ -0,0 +1,251 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use ftldat::{Package, PackageEntry};
+
+use crate::error::{Error, ErrorKind};
+
+/// Differences between two packages, as seen from the first one.
+/// Paths are listed in the order in which they appear in their respective packages.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PackageDiff {
+    /// Inner paths present only in the second package.
+    pub added: Vec<String>,
+    /// Inner paths present only in the first package.
+    pub removed: Vec<String>,
+    /// Inner paths present in both packages, but with different content.
+    pub modified: Vec<String>,
+}
+
+/// Decides what happens to entries present in both packages with different content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    KeepBase,
+    TakeOther,
+    Error,
+}
+
+impl MergeStrategy {
+    pub fn parse<S: AsRef<str>>(strategy: S) -> crate::error::Result<MergeStrategy> {
+        match strategy.as_ref() {
+            "keep_base" => Ok(MergeStrategy::KeepBase),
+            "take_other" => Ok(MergeStrategy::TakeOther),
+            "error" => Ok(MergeStrategy::Error),
+            other => Err(Error::new(ErrorKind::InvalidInput, format!(
+                "Unknown merge strategy '{}', expected one of: keep_base, take_other, error", other
+            )))
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeResult {
+    /// Inner paths that were not present in the base package.
+    pub added: Vec<String>,
+    /// Inner paths whose content in the base package was replaced.
+    pub replaced: Vec<String>,
+    /// Inner paths whose content differed, but was kept as it was in the base package.
+    pub kept: Vec<String>,
+}
+
+pub fn diff(a: &Package, b: &Package) -> crate::error::Result<PackageDiff> {
+    let a_entries = entries_by_path(a);
+    let b_entries = entries_by_path(b);
+    let mut result = PackageDiff::default();
+
+    for entry in a.iter() {
+        if !b_entries.contains_key(entry.inner_path()) {
+            result.removed.push(entry.inner_path().to_string());
+        }
+    }
+
+    for entry in b.iter() {
+        match a_entries.get(entry.inner_path()) {
+            None => result.added.push(entry.inner_path().to_string()),
+            Some(a_entry) => {
+                if !same_content(a_entry, entry)? {
+                    result.modified.push(entry.inner_path().to_string());
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Copies entries of `other` into `base`, resolving entries that differ between the two according
+/// to the specified strategy. With [MergeStrategy::Error], `base` is left untouched if any
+/// conflicts are found.
+pub fn merge(base: &mut Package, other: &Package, strategy: MergeStrategy) -> crate::error::Result<MergeResult> {
+    let changes = diff(base, other)?;
+
+    if strategy == MergeStrategy::Error && !changes.modified.is_empty() {
+        return Err(Error::new(ErrorKind::AlreadyExists, format!(
+            "Packages have conflicting entries: {}", changes.modified.join(", ")
+        )));
+    }
+
+    let mut result = MergeResult::default();
+    for inner_path in changes.added {
+        copy_entry(base, other, &inner_path)?;
+        result.added.push(inner_path);
+    }
+
+    for inner_path in changes.modified {
+        if strategy == MergeStrategy::TakeOther {
+            copy_entry(base, other, &inner_path)?;
+            result.replaced.push(inner_path);
+        } else {
+            result.kept.push(inner_path);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Removes the entry under the specified inner path from the package, returning whether it existed.
+///
+/// [Package::remove_entry] doesn't update the package's lookup table, which leaves it pointing at
+/// the wrong entries afterwards, so the package is rebuilt from its remaining entries instead.
+/// Entries can't be moved out of a package, so `lazy_entry` is asked to recreate each one without
+/// reading its content, eg. from the file it was read from. Only the content of entries it can't
+/// recreate is copied, which should be limited to those held in memory.
+pub fn remove_entry<F>(package: &mut Package, inner_path: &str, mut lazy_entry: F) -> crate::error::Result<bool>
+    where F: FnMut(&str) -> Option<PackageEntry> {
+    if !package.entry_exists(inner_path) {
+        return Ok(false);
+    }
+
+    let mut rebuilt = Package::with_capacity(package.entry_count() - 1);
+    for entry in package.iter().filter(|entry| entry.inner_path() != inner_path) {
+        let entry = match lazy_entry(entry.inner_path()) {
+            Some(entry) => entry,
+            None => PackageEntry::from_byte_array(entry.inner_path(), entry.content()?),
+        };
+        rebuilt.put_entry(entry);
+    }
+    *package = rebuilt;
+    Ok(true)
+}
+
+/// Hashes the specified content. The hash is only meant to be compared against other hashes
+/// computed within the same process.
+pub fn content_hash<C: AsRef<[u8]>>(content: C) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.as_ref().hash(&mut hasher);
+    hasher.finish()
+}
+
+fn entries_by_path(package: &Package) -> HashMap<&str, &PackageEntry> {
+    package.iter()
+        .map(|entry| (entry.inner_path(), entry))
+        .collect()
+}
+
+fn same_content(a: &PackageEntry, b: &PackageEntry) -> crate::error::Result<bool> {
+    let a_content = a.content()?;
+    let b_content = b.content()?;
+
+    Ok(a_content.len() == b_content.len() && content_hash(a_content) == content_hash(b_content))
+}
+
+fn copy_entry(base: &mut Package, other: &Package, inner_path: &str) -> crate::error::Result<()> {
+    let content = other.content_by_path(inner_path)
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "Entry doesn't exist"))?;
+    base.put_entry(PackageEntry::from_byte_array(inner_path, content));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ftldat::{Package, PackageEntry};
+
+    use crate::package::{diff, merge, MergeStrategy, remove_entry};
+
+    fn package(entries: &[(&str, &str)]) -> Package {
+        let mut package = Package::new();
+        for (inner_path, content) in entries {
+            package.add_entry(PackageEntry::from_string(inner_path, *content)).unwrap();
+        }
+        package
+    }
+
+    #[test]
+    fn diff_should_report_added_removed_and_modified_paths() {
+        let a = package(&[("same.txt", "1"), ("changed.txt", "old"), ("gone.txt", "x")]);
+        let b = package(&[("same.txt", "1"), ("changed.txt", "new"), ("new.txt", "y")]);
+
+        let result = diff(&a, &b).unwrap();
+
+        assert_eq!(vec!["new.txt"], result.added);
+        assert_eq!(vec!["gone.txt"], result.removed);
+        assert_eq!(vec!["changed.txt"], result.modified);
+    }
+
+    #[test]
+    fn merge_keep_base_should_only_add_missing_entries() {
+        let mut base = package(&[("a.txt", "base")]);
+        let other = package(&[("a.txt", "other"), ("b.txt", "other")]);
+
+        let result = merge(&mut base, &other, MergeStrategy::KeepBase).unwrap();
+
+        assert_eq!(vec!["b.txt"], result.added);
+        assert_eq!(vec!["a.txt"], result.kept);
+        assert_eq!(b"base".to_vec(), base.content_by_path("a.txt").unwrap());
+        assert_eq!(b"other".to_vec(), base.content_by_path("b.txt").unwrap());
+    }
+
+    #[test]
+    fn merge_take_other_should_replace_conflicting_entries() {
+        let mut base = package(&[("a.txt", "base")]);
+        let other = package(&[("a.txt", "other")]);
+
+        let result = merge(&mut base, &other, MergeStrategy::TakeOther).unwrap();
+
+        assert_eq!(vec!["a.txt"], result.replaced);
+        assert_eq!(b"other".to_vec(), base.content_by_path("a.txt").unwrap());
+    }
+
+    #[test]
+    fn merge_error_should_leave_base_untouched_on_conflict() {
+        let mut base = package(&[("a.txt", "base")]);
+        let other = package(&[("a.txt", "other"), ("b.txt", "other")]);
+
+        let result = merge(&mut base, &other, MergeStrategy::Error);
+
+        assert!(result.is_err());
+        assert_eq!(1, base.entry_count());
+        assert_eq!(b"base".to_vec(), base.content_by_path("a.txt").unwrap());
+    }
+
+    #[test]
+    fn remove_entry_should_keep_remaining_entries_reachable() {
+        let mut package = package(&[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")]);
+
+        assert!(remove_entry(&mut package, "a.txt", |_| None).unwrap());
+        assert!(!remove_entry(&mut package, "a.txt", |_| None).unwrap());
+
+        assert!(!package.entry_exists("a.txt"));
+        assert_eq!(vec!["b.txt", "c.txt"], package.inner_paths());
+        assert_eq!(b"c".to_vec(), package.content_by_path("c.txt").unwrap());
+    }
+
+    #[test]
+    fn remove_entry_should_not_read_recreated_entries() {
+        let mut package = package(&[("a.txt", "a")]);
+        package.add_entry(PackageEntry::from_file("missing.txt", "does/not/exist.txt")).unwrap();
+
+        let removed = remove_entry(&mut package, "a.txt", |inner_path| {
+            Some(PackageEntry::from_file(inner_path, "does/not/exist.txt"))
+        });
+
+        assert!(removed.unwrap());
+        assert_eq!(vec!["missing.txt"], package.inner_paths());
+    }
+
+    #[test]
+    fn unknown_merge_strategy_should_be_rejected() {
+        assert!(MergeStrategy::parse("overwrite").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use ftldat::Package;
+
+use crate::error::{Error, ErrorKind, WithPath};
+use crate::io::audit;
+use crate::io::audit::AuditOperation;
+
+/// Signature at the start of every PKG package.
+pub(super) const PKG_SIGNATURE: [u8; 4] = *b"PKG\n";
+
+/// On-disk layouts of packages supported by ftldat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageFormat {
+    /// Used by Into the Breach, and by Faster Than Light up to version 1.6.1.
+    Dat,
+    /// Used by Faster Than Light since version 1.6.1.
+    Pkg,
+}
+
+impl PackageFormat {
+    /// Parses the name of a format, as accepted from Lua.
+    /// Returns `None` for `auto`, which means that the format should be detected from the file.
+    pub fn parse<S: AsRef<str>>(name: S) -> crate::error::Result<Option<PackageFormat>> {
+        match name.as_ref() {
+            "auto" => Ok(None),
+            "itb" | "dat" => Ok(Some(PackageFormat::Dat)),
+            "ftl" | "pkg" => Ok(Some(PackageFormat::Pkg)),
+            other => Err(Error::new(ErrorKind::InvalidInput, format!(
+                "Unknown package format '{}', expected one of: auto, itb, dat, ftl, pkg", other
+            )))
+        }
+    }
+
+    /// Detects the format of the package at the specified path, based on its signature.
+    pub fn detect<P: AsRef<Path>>(path: P) -> crate::error::Result<PackageFormat> {
+        let mut signature = Vec::with_capacity(PKG_SIGNATURE.len());
+        File::open(&path)
+            .and_then(|file| file.take(PKG_SIGNATURE.len() as u64).read_to_end(&mut signature))
+            .with_path(&path)?;
+
+        if signature == PKG_SIGNATURE {
+            Ok(PackageFormat::Pkg)
+        } else {
+            // DAT packages don't have a signature; they start directly with the entry count.
+            Ok(PackageFormat::Dat)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageFormat::Dat => "dat",
+            PackageFormat::Pkg => "pkg",
+        }
+    }
+
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> crate::error::Result<Package> {
+        let path = path.as_ref();
+        // ftldat's errors don't preserve what went wrong, so check for the most common failure
+        if !path.exists() {
+            return Err(Error::new(ErrorKind::NotFound, "Package doesn't exist").with_path(path));
+        }
+
+        match self {
+            PackageFormat::Dat => Package::from_path_dat(path),
+            PackageFormat::Pkg => Package::from_path_pkg(path),
+        }.with_path(path)
+    }
+
+    /// Consumes and writes the package to the specified path in this format.
+    pub fn write<P: AsRef<Path>>(&self, package: Package, path: P) -> crate::error::Result<()> {
+        let path = path.as_ref();
+        // The consuming variants write to a temporary file and then replace the destination,
+        // which fails if the destination doesn't exist yet. Nothing can be holding a file that
+        // doesn't exist, so the non-consuming variants are safe to use in that case.
+        let result = match (self, path.exists()) {
+            (PackageFormat::Dat, true) => package.into_path_dat(path),
+            (PackageFormat::Dat, false) => package.to_path_dat(path),
+            (PackageFormat::Pkg, true) => package.into_path_pkg(path),
+            (PackageFormat::Pkg, false) => package.to_path_pkg(path),
+        };
+        result.with_path(path)?;
+
+        audit::record(AuditOperation::Write, path, None);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ftldat::{Package, PackageEntry};
+    use tempfile::tempdir;
+
+    use crate::package::PackageFormat;
+
+    fn sample_package() -> Package {
+        let mut package = Package::new();
+        package.add_entry(PackageEntry::from_string("a/b.txt", "qwe")).unwrap();
+        package
+    }
+
+    #[test]
+    fn format_names_should_be_parsed_with_game_aliases() {
+        assert_eq!(None, PackageFormat::parse("auto").unwrap());
+        assert_eq!(Some(PackageFormat::Dat), PackageFormat::parse("itb").unwrap());
+        assert_eq!(Some(PackageFormat::Pkg), PackageFormat::parse("ftl").unwrap());
+        assert!(PackageFormat::parse("zip").is_err());
+    }
+
+    #[test]
+    fn detect_should_recognize_written_formats() {
+        let tmp_dir = tempdir().unwrap();
+        let dat_path = tmp_dir.path().join("test.dat");
+        let pkg_path = tmp_dir.path().join("test.pkg");
+
+        PackageFormat::Dat.write(sample_package(), &dat_path).unwrap();
+        PackageFormat::Pkg.write(sample_package(), &pkg_path).unwrap();
+
+        assert_eq!(PackageFormat::Dat, PackageFormat::detect(&dat_path).unwrap());
+        assert_eq!(PackageFormat::Pkg, PackageFormat::detect(&pkg_path).unwrap());
+    }
+
+    #[test]
+    fn package_should_survive_conversion_between_formats() {
+        let tmp_dir = tempdir().unwrap();
+        let dat_path = tmp_dir.path().join("test.dat");
+        let pkg_path = tmp_dir.path().join("test.pkg");
+
+        PackageFormat::Dat.write(sample_package(), &dat_path).unwrap();
+        let package = PackageFormat::Dat.read(&dat_path).unwrap();
+        PackageFormat::Pkg.write(package, &pkg_path).unwrap();
+        let package = PackageFormat::Pkg.read(&pkg_path).unwrap();
+
+        assert_eq!(b"qwe".to_vec(), package.content_by_path("a/b.txt").unwrap());
+    }
+
+    #[test]
+    fn write_should_overwrite_existing_file() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("test.dat");
+        std::fs::write(&path, "garbage").unwrap();
+
+        PackageFormat::Dat.write(sample_package(), &path).unwrap();
+
+        assert_eq!(1, PackageFormat::Dat.read(&path).unwrap().entry_count());
+    }
+}
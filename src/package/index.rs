@@ -0,0 +1,206 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::error::{Error, ErrorKind, WithPath};
+use crate::package::format::PKG_SIGNATURE;
+use crate::package::PackageFormat;
+
+/// Location of a single entry's content within a package file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub inner_path: String,
+    /// Offset of the entry's content, relative to the start of the package file.
+    pub offset: u64,
+    /// Length of the entry's content, in bytes.
+    pub size: u64,
+}
+
+/// Reads the index of the package at the specified path, without reading any entry's content.
+pub fn read_index<P: AsRef<Path>>(path: P, format: PackageFormat) -> crate::error::Result<Vec<IndexEntry>> {
+    match format {
+        PackageFormat::Dat => read_dat_index(&path),
+        PackageFormat::Pkg => read_pkg_index(&path),
+    }.with_path(&path)
+}
+
+/// Reads the index of the DAT package at the specified path, without reading any entry's content.
+///
+/// DAT packages have the following structure:
+/// - `entry_count` := number of entries (1x u32)
+/// - offsets to Entries (`entry_count` x u32)
+/// - Entries, each consisting of `data_size` (1x u32), `str_len` (1x u32), inner path
+///   (`str_len` x u8) and content (`data_size` x u8)
+pub fn read_dat_index<P: AsRef<Path>>(path: P) -> crate::error::Result<Vec<IndexEntry>> {
+    let mut input = BufReader::new(File::open(path)?);
+
+    let entry_count = read_u32(&mut input)? as usize;
+    let mut entry_offsets = Vec::with_capacity(entry_count);
+    for _ in 0..entry_count {
+        entry_offsets.push(read_u32(&mut input)? as u64);
+    }
+
+    let mut result = Vec::with_capacity(entry_count);
+    for entry_offset in entry_offsets {
+        input.seek(SeekFrom::Start(entry_offset))?;
+        let size = read_u32(&mut input)? as u64;
+        let inner_path_length = read_u32(&mut input)? as u64;
+
+        let mut inner_path = vec![0u8; inner_path_length as usize];
+        input.read_exact(&mut inner_path)?;
+        let inner_path = String::from_utf8(inner_path)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+
+        result.push(IndexEntry {
+            inner_path,
+            offset: entry_offset + 8 + inner_path_length,
+            size,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Reads the index of the PKG package at the specified path, without reading any entry's content.
+///
+/// PKG packages have the following structure (all numbers are big endian):
+/// - `PKG\n` signature (4x u8)
+/// - `index_size` (1x u16) and `entry_size` (1x u16)
+/// - `entry_count` (1x u32) and `path_region_size` (1x u32)
+/// - Entry headers (`entry_count` x `entry_size`), each consisting of inner path hash (1x u32),
+///   options (1x u8), inner path offset (1x u24), `data_offset` (1x u32), `data_size` (1x u32)
+///   and unpacked data size (1x u32)
+/// - path region, containing null-terminated inner paths (`path_region_size` x u8)
+/// - Entries' content
+pub fn read_pkg_index<P: AsRef<Path>>(path: P) -> crate::error::Result<Vec<IndexEntry>> {
+    let mut input = BufReader::new(File::open(path)?);
+
+    let mut signature = [0u8; 4];
+    input.read_exact(&mut signature)?;
+    if signature != PKG_SIGNATURE {
+        return Err(Error::new(ErrorKind::InvalidData, "Not a PKG package"));
+    }
+
+    let index_size = read_u16_be(&mut input)? as u64;
+    let entry_size = read_u16_be(&mut input)? as u64;
+    let entry_count = read_u32_be(&mut input)? as usize;
+    let path_region_size = read_u32_be(&mut input)? as usize;
+
+    let mut headers = Vec::with_capacity(entry_count);
+    for i in 0..entry_count {
+        input.seek(SeekFrom::Start(index_size + entry_size * i as u64))?;
+        let _inner_path_hash = read_u32_be(&mut input)?;
+        // Upper byte holds entry options, lower three bytes the inner path offset
+        let inner_path_offset = (read_u32_be(&mut input)? & 0x00FF_FFFF) as usize;
+        let data_offset = read_u32_be(&mut input)? as u64;
+        let data_size = read_u32_be(&mut input)? as u64;
+        headers.push((inner_path_offset, data_offset, data_size));
+    }
+
+    input.seek(SeekFrom::Start(index_size + entry_size * entry_count as u64))?;
+    let mut path_region = vec![0u8; path_region_size];
+    input.read_exact(&mut path_region)?;
+
+    let mut result = Vec::with_capacity(entry_count);
+    for (inner_path_offset, offset, size) in headers {
+        let inner_path = path_region.get(inner_path_offset..)
+            .and_then(|region| region.split(|byte| *byte == 0).next())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Inner path offset points outside of the path region"))?;
+        let inner_path = String::from_utf8(inner_path.to_vec())
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+
+        result.push(IndexEntry {
+            inner_path,
+            offset,
+            size,
+        });
+    }
+
+    Ok(result)
+}
+
+fn read_u32(input: &mut impl Read) -> crate::error::Result<u32> {
+    let mut buffer = [0u8; 4];
+    input.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+fn read_u32_be(input: &mut impl Read) -> crate::error::Result<u32> {
+    let mut buffer = [0u8; 4];
+    input.read_exact(&mut buffer)?;
+    Ok(u32::from_be_bytes(buffer))
+}
+
+fn read_u16_be(input: &mut impl Read) -> crate::error::Result<u16> {
+    let mut buffer = [0u8; 2];
+    input.read_exact(&mut buffer)?;
+    Ok(u16::from_be_bytes(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use ftldat::{Package, PackageEntry};
+    use tempfile::tempdir;
+
+    use crate::package::{PackageFormat, read_index};
+    use crate::package::index::read_dat_index;
+
+    #[test]
+    fn index_should_report_entries_in_package_order() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("test.dat");
+        let mut package = Package::new();
+        package.add_entry(PackageEntry::from_string("b.txt", "qwe")).unwrap();
+        package.add_entry(PackageEntry::from_string("a/c.txt", "asdfgh")).unwrap();
+        package.to_path_dat(&path).unwrap();
+
+        let index = read_dat_index(&path).unwrap();
+
+        let inner_paths: Vec<&str> = index.iter().map(|entry| entry.inner_path.as_str()).collect();
+        assert_eq!(vec!["b.txt", "a/c.txt"], inner_paths);
+        assert_eq!(3, index[0].size);
+        assert_eq!(6, index[1].size);
+    }
+
+    #[test]
+    fn index_offsets_should_point_at_entry_content() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("test.dat");
+        let mut package = Package::new();
+        package.add_entry(PackageEntry::from_string("b.txt", "qwe")).unwrap();
+        package.add_entry(PackageEntry::from_string("a/c.txt", "asdfgh")).unwrap();
+        package.to_path_dat(&path).unwrap();
+
+        let index = read_dat_index(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        for entry in index {
+            let start = entry.offset as usize;
+            let content = &bytes[start..start + entry.size as usize];
+            let expected = package.content_by_path(&entry.inner_path).unwrap();
+            assert_eq!(expected.as_slice(), content);
+        }
+    }
+
+    #[test]
+    fn pkg_index_offsets_should_point_at_entry_content() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("test.pkg");
+        let mut package = Package::new();
+        package.add_entry(PackageEntry::from_string("b.txt", "qwe")).unwrap();
+        package.add_entry(PackageEntry::from_string("a/c.txt", "asdfgh")).unwrap();
+        package.to_path_pkg(&path).unwrap();
+
+        let index = read_index(&path, PackageFormat::Pkg).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        let inner_paths: Vec<&str> = index.iter().map(|entry| entry.inner_path.as_str()).collect();
+        assert_eq!(vec!["b.txt", "a/c.txt"], inner_paths);
+        for entry in index {
+            let start = entry.offset as usize;
+            let content = &bytes[start..start + entry.size as usize];
+            let expected = package.content_by_path(&entry.inner_path).unwrap();
+            assert_eq!(expected.as_slice(), content);
+        }
+    }
+}
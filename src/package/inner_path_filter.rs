@@ -0,0 +1,70 @@
+use glob::{MatchOptions, Pattern, PatternError};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    // Don't let `*` match across directories, so that `img/units/*.png` doesn't
+    // match `img/units/player/mech.png`. Use `**` for that instead.
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Selects inner paths of a package, either by a plain prefix, or by a glob pattern if the filter
+/// contains any glob metacharacters.
+#[derive(Debug)]
+pub enum InnerPathFilter {
+    Prefix(String),
+    Glob(Pattern),
+}
+
+impl InnerPathFilter {
+    pub fn parse<S: AsRef<str>>(filter: S) -> Result<InnerPathFilter, PatternError> {
+        let filter = filter.as_ref();
+        if filter.contains(['*', '?', '[']) {
+            Pattern::new(filter).map(InnerPathFilter::Glob)
+        } else {
+            Ok(InnerPathFilter::Prefix(filter.to_string()))
+        }
+    }
+
+    pub fn matches<S: AsRef<str>>(&self, inner_path: S) -> bool {
+        let inner_path = inner_path.as_ref();
+        match self {
+            InnerPathFilter::Prefix(prefix) => inner_path.starts_with(prefix),
+            InnerPathFilter::Glob(pattern) => pattern.matches_with(inner_path, MATCH_OPTIONS),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::package::InnerPathFilter;
+
+    #[test]
+    fn filter_without_metacharacters_should_match_by_prefix() {
+        let filter = InnerPathFilter::parse("img/units/").unwrap();
+
+        assert!(filter.matches("img/units/player/mech.png"));
+        assert!(!filter.matches("img/effects/smoke.png"));
+    }
+
+    #[test]
+    fn glob_star_should_not_match_across_directories() {
+        let filter = InnerPathFilter::parse("img/units/player/*.png").unwrap();
+
+        assert!(filter.matches("img/units/player/mech.png"));
+        assert!(!filter.matches("img/units/player/anim/mech.png"));
+        assert!(!filter.matches("img/units/player/mech.lua"));
+    }
+
+    #[test]
+    fn glob_double_star_should_match_across_directories() {
+        let filter = InnerPathFilter::parse("img/**/*.png").unwrap();
+
+        assert!(filter.matches("img/units/player/anim/mech.png"));
+    }
+
+    #[test]
+    fn invalid_glob_should_be_rejected() {
+        assert!(InnerPathFilter::parse("img/[").is_err());
+    }
+}
@@ -0,0 +1,24 @@
+#[cfg(feature = "ftldat")]
+pub use bulk::{directory_files, extract_all};
+#[cfg(feature = "ftldat")]
+pub use diff::{diff, merge, MergeStrategy, remove_entry};
+#[cfg(feature = "ftldat")]
+pub use format::PackageFormat;
+#[cfg(feature = "ftldat")]
+pub use index::{IndexEntry, read_index};
+pub use inner_path_filter::InnerPathFilter;
+#[cfg(feature = "ftldat")]
+pub use verify::{salvage, verify_path};
+
+// Inner path filters are also used by the io module's watchers, the rest needs ftldat.
+#[cfg(feature = "ftldat")]
+mod bulk;
+#[cfg(feature = "ftldat")]
+mod diff;
+#[cfg(feature = "ftldat")]
+mod format;
+#[cfg(feature = "ftldat")]
+mod index;
+mod inner_path_filter;
+#[cfg(feature = "ftldat")]
+mod verify;
@@ -0,0 +1,472 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use ftldat::{Package, PackageEntry};
+
+use crate::error::WithPath;
+use crate::package::format::PKG_SIGNATURE;
+use crate::package::{IndexEntry, PackageFormat};
+
+const DAT_ENTRY_HEADER_SIZE: u64 = 8;
+const PKG_INDEX_SIZE: u64 = 16;
+const PKG_ENTRY_SIZE: u64 = 20;
+const PKG_DEFLATED: u8 = 0x01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProblemKind {
+    /// The package's header is missing or malformed. Entries may not be readable at all.
+    Header,
+    /// An entry's offset points outside of the area where entries are stored.
+    OffsetOutOfBounds,
+    /// An entry extends past the end of the file.
+    Truncated,
+    /// An entry's inner path is not valid UTF-8, or cannot be located.
+    InvalidPath,
+    /// An entry's stored inner path hash doesn't match its inner path (PKG only).
+    PathHashMismatch,
+    /// An entry uses features that are not supported, eg. deflated content (PKG only).
+    Unsupported,
+    /// An entry has the same inner path as an earlier entry.
+    Duplicate,
+    /// An entry's content overlaps with another entry.
+    Overlap,
+}
+
+impl ProblemKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProblemKind::Header => "header",
+            ProblemKind::OffsetOutOfBounds => "offset_out_of_bounds",
+            ProblemKind::Truncated => "truncated",
+            ProblemKind::InvalidPath => "invalid_path",
+            ProblemKind::PathHashMismatch => "path_hash_mismatch",
+            ProblemKind::Unsupported => "unsupported",
+            ProblemKind::Duplicate => "duplicate",
+            ProblemKind::Overlap => "overlap",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub kind: ProblemKind,
+    /// Position of the affected entry in the package's index, if the problem concerns an entry.
+    pub entry: Option<usize>,
+    pub inner_path: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub struct VerifyReport {
+    pub format: PackageFormat,
+    /// Number of entries the package's header claims to contain.
+    pub declared_entry_count: usize,
+    /// Entries whose content can be read in full, in index order.
+    pub readable: Vec<IndexEntry>,
+    pub problems: Vec<Problem>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn problem<S: Into<String>>(&mut self, kind: ProblemKind, entry: Option<usize>, inner_path: Option<&str>, message: S) {
+        self.problems.push(Problem {
+            kind,
+            entry,
+            inner_path: inner_path.map(|it| it.to_string()),
+            message: message.into(),
+        });
+    }
+}
+
+/// An entry that passed the per-entry checks, along with the range of bytes it occupies.
+struct Candidate {
+    index: usize,
+    start: u64,
+    end: u64,
+    entry: IndexEntry,
+}
+
+/// Verifies the package at the specified path, collecting all problems found instead of stopping
+/// at the first one.
+pub fn verify_path<P: AsRef<Path>>(path: P, format: PackageFormat) -> crate::error::Result<(VerifyReport, Vec<u8>)> {
+    let bytes = std::fs::read(&path).with_path(&path)?;
+    Ok((verify(&bytes, format), bytes))
+}
+
+/// Verifies the package stored in `bytes`, collecting all problems found instead of stopping
+/// at the first one.
+pub fn verify(bytes: &[u8], format: PackageFormat) -> VerifyReport {
+    let mut report = VerifyReport {
+        format,
+        declared_entry_count: 0,
+        readable: Vec::new(),
+        problems: Vec::new(),
+    };
+
+    let candidates = match format {
+        PackageFormat::Dat => verify_dat(bytes, &mut report),
+        PackageFormat::Pkg => verify_pkg(bytes, &mut report),
+    };
+    check_duplicates_and_overlaps(candidates, &mut report);
+
+    report
+}
+
+/// Builds a new package out of all entries that the report found to be readable.
+pub fn salvage(bytes: &[u8], report: &VerifyReport) -> Package {
+    let mut package = Package::with_capacity(report.readable.len());
+    for entry in &report.readable {
+        let start = entry.offset as usize;
+        let content = bytes[start..start + entry.size as usize].to_vec();
+        package.put_entry(PackageEntry::from_byte_array(&entry.inner_path, content));
+    }
+    package
+}
+
+fn verify_dat(bytes: &[u8], report: &mut VerifyReport) -> Vec<Candidate> {
+    let len = bytes.len() as u64;
+    let mut candidates = Vec::new();
+
+    let entry_count = match read_u32_le(bytes, 0) {
+        Some(entry_count) => entry_count as usize,
+        None => {
+            report.problem(ProblemKind::Header, None, None, "File is too short to contain the entry count");
+            return candidates;
+        }
+    };
+    report.declared_entry_count = entry_count;
+
+    let entries_start = 4 + 4 * entry_count as u64;
+    if entries_start > len {
+        report.problem(ProblemKind::Header, None, None, format!(
+            "Offset table of {} entries extends past the end of the file", entry_count
+        ));
+    }
+
+    for index in 0..entry_count {
+        let entry_offset = match read_u32_le(bytes, 4 + 4 * index as u64) {
+            Some(entry_offset) => entry_offset as u64,
+            // Already reported as a header problem.
+            None => break
+        };
+
+        if entry_offset < entries_start || entry_offset + DAT_ENTRY_HEADER_SIZE > len {
+            report.problem(ProblemKind::OffsetOutOfBounds, Some(index), None, format!(
+                "Entry offset {} is outside of the entry area", entry_offset
+            ));
+            continue;
+        }
+
+        let size = read_u32_le(bytes, entry_offset).unwrap() as u64;
+        let inner_path_length = read_u32_le(bytes, entry_offset + 4).unwrap() as u64;
+        let inner_path_start = entry_offset + DAT_ENTRY_HEADER_SIZE;
+        let data_start = inner_path_start + inner_path_length;
+        if data_start > len {
+            report.problem(ProblemKind::Truncated, Some(index), None, "Inner path extends past the end of the file");
+            continue;
+        }
+
+        let inner_path = match std::str::from_utf8(&bytes[inner_path_start as usize..data_start as usize]) {
+            Ok(inner_path) => inner_path.to_string(),
+            Err(_) => {
+                report.problem(ProblemKind::InvalidPath, Some(index), None, "Inner path is not valid UTF-8");
+                continue;
+            }
+        };
+
+        let data_end = data_start + size;
+        if data_end > len {
+            report.problem(ProblemKind::Truncated, Some(index), Some(&inner_path), format!(
+                "Content of {} bytes extends past the end of the file", size
+            ));
+            continue;
+        }
+
+        candidates.push(Candidate {
+            index,
+            start: entry_offset,
+            end: data_end,
+            entry: IndexEntry { inner_path, offset: data_start, size },
+        });
+    }
+
+    candidates
+}
+
+fn verify_pkg(bytes: &[u8], report: &mut VerifyReport) -> Vec<Candidate> {
+    let len = bytes.len() as u64;
+    let mut candidates = Vec::new();
+
+    if len < PKG_INDEX_SIZE {
+        report.problem(ProblemKind::Header, None, None, "File is too short to contain the header");
+        return candidates;
+    }
+    if bytes[..4] != PKG_SIGNATURE {
+        report.problem(ProblemKind::Header, None, None, "File doesn't start with the PKG signature");
+        return candidates;
+    }
+
+    let index_size = read_u16_be(bytes, 4).unwrap() as u64;
+    let entry_size = read_u16_be(bytes, 6).unwrap() as u64;
+    if index_size != PKG_INDEX_SIZE || entry_size != PKG_ENTRY_SIZE {
+        report.problem(ProblemKind::Header, None, None, format!(
+            "Expected header size {} and entry size {}, but found {} and {}",
+            PKG_INDEX_SIZE, PKG_ENTRY_SIZE, index_size, entry_size
+        ));
+        return candidates;
+    }
+
+    let entry_count = read_u32_be(bytes, 8).unwrap() as usize;
+    let path_region_size = read_u32_be(bytes, 12).unwrap() as u64;
+    report.declared_entry_count = entry_count;
+
+    let path_region_start = PKG_INDEX_SIZE + PKG_ENTRY_SIZE * entry_count as u64;
+    let path_region_end = path_region_start + path_region_size;
+    if path_region_start > len {
+        report.problem(ProblemKind::Header, None, None, format!(
+            "Entry table of {} entries extends past the end of the file", entry_count
+        ));
+    } else if path_region_end > len {
+        report.problem(ProblemKind::Header, None, None, "Path region extends past the end of the file");
+    }
+    let path_region = &bytes[path_region_start.min(len) as usize..path_region_end.min(len) as usize];
+
+    for index in 0..entry_count {
+        let header_start = PKG_INDEX_SIZE + PKG_ENTRY_SIZE * index as u64;
+        if header_start + PKG_ENTRY_SIZE > len {
+            // Already reported as a header problem.
+            break;
+        }
+
+        let inner_path_hash = read_u32_be(bytes, header_start).unwrap();
+        let entry_options = bytes[header_start as usize + 4];
+        let inner_path_offset = (read_u32_be(bytes, header_start + 4).unwrap() & 0x00FF_FFFF) as usize;
+        let data_start = read_u32_be(bytes, header_start + 8).unwrap() as u64;
+        let size = read_u32_be(bytes, header_start + 12).unwrap() as u64;
+
+        let inner_path = match path_region.get(inner_path_offset..)
+            .and_then(|region| region.iter().position(|byte| *byte == 0).map(|end| &region[..end]))
+        {
+            Some(inner_path) => inner_path,
+            None => {
+                report.problem(ProblemKind::InvalidPath, Some(index), None, format!(
+                    "Inner path at offset {} is not terminated within the path region", inner_path_offset
+                ));
+                continue;
+            }
+        };
+        let inner_path = match std::str::from_utf8(inner_path) {
+            Ok(inner_path) => inner_path.to_string(),
+            Err(_) => {
+                report.problem(ProblemKind::InvalidPath, Some(index), None, "Inner path is not valid UTF-8");
+                continue;
+            }
+        };
+
+        if pkg_path_hash(&inner_path) != inner_path_hash {
+            // The content itself may still be fine, so the entry remains readable.
+            report.problem(ProblemKind::PathHashMismatch, Some(index), Some(&inner_path), "Stored inner path hash doesn't match the inner path");
+        }
+
+        if entry_options & PKG_DEFLATED != 0 {
+            report.problem(ProblemKind::Unsupported, Some(index), Some(&inner_path), "Deflated entries are not supported");
+            continue;
+        }
+
+        if data_start < path_region_end {
+            report.problem(ProblemKind::OffsetOutOfBounds, Some(index), Some(&inner_path), format!(
+                "Data offset {} is outside of the data region", data_start
+            ));
+            continue;
+        }
+
+        let data_end = data_start + size;
+        if data_end > len {
+            report.problem(ProblemKind::Truncated, Some(index), Some(&inner_path), format!(
+                "Content of {} bytes extends past the end of the file", size
+            ));
+            continue;
+        }
+
+        candidates.push(Candidate {
+            index,
+            start: data_start,
+            end: data_end,
+            entry: IndexEntry { inner_path, offset: data_start, size },
+        });
+    }
+
+    candidates
+}
+
+fn check_duplicates_and_overlaps(candidates: Vec<Candidate>, report: &mut VerifyReport) {
+    let mut seen = HashSet::new();
+    let mut ranges = Vec::with_capacity(candidates.len());
+
+    for candidate in candidates {
+        if !seen.insert(candidate.entry.inner_path.clone()) {
+            report.problem(ProblemKind::Duplicate, Some(candidate.index), Some(&candidate.entry.inner_path),
+                           "Inner path already used by an earlier entry");
+            continue;
+        }
+
+        ranges.push((candidate.start, candidate.end, candidate.index, candidate.entry.inner_path.clone()));
+        report.readable.push(candidate.entry);
+    }
+
+    // Empty entries can't overlap with anything.
+    ranges.retain(|(start, end, _, _)| start < end);
+    ranges.sort();
+    let mut furthest: Option<(u64, String)> = None;
+    for (start, end, index, inner_path) in ranges {
+        if let Some((furthest_end, furthest_path)) = &furthest {
+            if start < *furthest_end {
+                report.problem(ProblemKind::Overlap, Some(index), Some(&inner_path), format!(
+                    "Overlaps with entry '{}'", furthest_path
+                ));
+            }
+            if end <= *furthest_end {
+                continue;
+            }
+        }
+        furthest = Some((end, inner_path));
+    }
+}
+
+fn pkg_path_hash(inner_path: &str) -> u32 {
+    inner_path.to_lowercase().chars()
+        .fold(0u32, |hash, char| hash.rotate_right(5) ^ char as u32)
+}
+
+fn read_u32_le(bytes: &[u8], offset: u64) -> Option<u32> {
+    bytes.get(offset as usize..offset as usize + 4)
+        .map(|slice| u32::from_le_bytes(slice.try_into().unwrap()))
+}
+
+fn read_u32_be(bytes: &[u8], offset: u64) -> Option<u32> {
+    bytes.get(offset as usize..offset as usize + 4)
+        .map(|slice| u32::from_be_bytes(slice.try_into().unwrap()))
+}
+
+fn read_u16_be(bytes: &[u8], offset: u64) -> Option<u16> {
+    bytes.get(offset as usize..offset as usize + 2)
+        .map(|slice| u16::from_be_bytes(slice.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ftldat::{Package, PackageEntry};
+    use tempfile::tempdir;
+
+    use crate::package::PackageFormat;
+    use crate::package::verify::{ProblemKind, salvage, verify};
+
+    fn sample_bytes(format: PackageFormat) -> Vec<u8> {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("test");
+        let mut package = Package::new();
+        package.add_entry(PackageEntry::from_string("a.txt", "first")).unwrap();
+        package.add_entry(PackageEntry::from_string("b.txt", "second")).unwrap();
+        format.write(package, &path).unwrap();
+        std::fs::read(&path).unwrap()
+    }
+
+    fn kinds(problems: &[crate::package::verify::Problem]) -> Vec<ProblemKind> {
+        problems.iter().map(|problem| problem.kind).collect()
+    }
+
+    #[test]
+    fn valid_packages_should_have_no_problems() {
+        for format in [PackageFormat::Dat, PackageFormat::Pkg] {
+            let report = verify(&sample_bytes(format), format);
+
+            assert!(report.is_ok(), "{:?}", report.problems);
+            assert_eq!(2, report.readable.len());
+        }
+    }
+
+    #[test]
+    fn truncated_dat_should_keep_readable_entries() {
+        let mut bytes = sample_bytes(PackageFormat::Dat);
+        bytes.truncate(bytes.len() - 2);
+
+        let report = verify(&bytes, PackageFormat::Dat);
+
+        assert_eq!(vec![ProblemKind::Truncated], kinds(&report.problems));
+        assert_eq!(Some("b.txt".to_string()), report.problems[0].inner_path);
+        assert_eq!(1, report.readable.len());
+        assert_eq!("a.txt", report.readable[0].inner_path);
+    }
+
+    #[test]
+    fn dat_with_offset_past_end_should_be_reported() {
+        let mut bytes = sample_bytes(PackageFormat::Dat);
+        bytes[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let report = verify(&bytes, PackageFormat::Dat);
+
+        assert_eq!(vec![ProblemKind::OffsetOutOfBounds], kinds(&report.problems));
+        assert_eq!(Some(0), report.problems[0].entry);
+    }
+
+    #[test]
+    fn dat_with_repeated_offset_should_report_duplicate() {
+        let mut bytes = sample_bytes(PackageFormat::Dat);
+        let first_offset = bytes[4..8].to_vec();
+        bytes[8..12].copy_from_slice(&first_offset);
+
+        let report = verify(&bytes, PackageFormat::Dat);
+
+        assert_eq!(vec![ProblemKind::Duplicate], kinds(&report.problems));
+    }
+
+    #[test]
+    fn dat_with_overlapping_entries_should_be_reported() {
+        let mut bytes = sample_bytes(PackageFormat::Dat);
+        let first_offset = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        // Grow the first entry's content so that it runs into the second entry
+        bytes[first_offset..first_offset + 4].copy_from_slice(&10u32.to_le_bytes());
+
+        let report = verify(&bytes, PackageFormat::Dat);
+
+        assert_eq!(vec![ProblemKind::Overlap], kinds(&report.problems));
+        assert_eq!(Some("b.txt".to_string()), report.problems[0].inner_path);
+    }
+
+    #[test]
+    fn pkg_with_bad_signature_should_report_header_problem() {
+        let mut bytes = sample_bytes(PackageFormat::Pkg);
+        bytes[0] = b'X';
+
+        let report = verify(&bytes, PackageFormat::Pkg);
+
+        assert_eq!(vec![ProblemKind::Header], kinds(&report.problems));
+        assert!(report.readable.is_empty());
+    }
+
+    #[test]
+    fn truncated_pkg_should_keep_readable_entries() {
+        let mut bytes = sample_bytes(PackageFormat::Pkg);
+        bytes.truncate(bytes.len() - 2);
+
+        let report = verify(&bytes, PackageFormat::Pkg);
+
+        assert_eq!(vec![ProblemKind::Truncated], kinds(&report.problems));
+        assert_eq!(1, report.readable.len());
+    }
+
+    #[test]
+    fn salvage_should_contain_only_readable_entries() {
+        let mut bytes = sample_bytes(PackageFormat::Dat);
+        bytes.truncate(bytes.len() - 2);
+        let report = verify(&bytes, PackageFormat::Dat);
+
+        let package = salvage(&bytes, &report);
+
+        assert_eq!(vec!["a.txt".to_string()], package.inner_paths());
+        assert_eq!(b"first".to_vec(), package.content_by_path("a.txt").unwrap());
+    }
+}
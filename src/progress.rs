@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, ErrorKind};
+
+/// Receives the number of items processed so far, the total number of items, and the path of
+/// the item about to be processed. Returning `false` aborts the operation.
+///
+/// Operations report once more with `done == total` after the last item; returning `false`
+/// at that point has no effect.
+pub type Progress<'a> = dyn FnMut(usize, usize, &str) -> bool + 'a;
+
+/// Minimum time between two progress reports forwarded to Lua.
+pub const DEFAULT_THROTTLE_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn aborted() -> Error {
+    Error::new(ErrorKind::Aborted, "Operation was aborted")
+}
+
+/// Limits how often progress is reported, so that operations on many small items don't spend
+/// most of their time reporting. The first and the final report always go through.
+pub struct Throttle {
+    interval: Duration,
+    last_report: Option<Instant>,
+}
+
+impl Throttle {
+    pub fn new(interval: Duration) -> Throttle {
+        Throttle {
+            interval,
+            last_report: None,
+        }
+    }
+
+    /// Returns whether progress should be reported at this point.
+    pub fn ready(&mut self, done: usize, total: usize, now: Instant) -> bool {
+        let ready = match self.last_report {
+            None => true,
+            Some(last_report) => done >= total || now.duration_since(last_report) >= self.interval
+        };
+
+        if ready {
+            self.last_report = Some(now);
+        }
+        ready
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::progress::Throttle;
+
+    #[test]
+    fn throttle_should_let_through_first_final_and_spaced_reports() {
+        let start = Instant::now();
+        let mut throttle = Throttle::new(Duration::from_millis(100));
+
+        assert!(throttle.ready(0, 10, start));
+        assert!(!throttle.ready(1, 10, start + Duration::from_millis(50)));
+        assert!(throttle.ready(2, 10, start + Duration::from_millis(100)));
+        assert!(!throttle.ready(3, 10, start + Duration::from_millis(150)));
+        assert!(throttle.ready(10, 10, start + Duration::from_millis(160)));
+    }
+}
@@ -0,0 +1,109 @@
+-- Minimal assertion library of the test runner. Tests are registered with `test(name, fn)`, and
+-- collected into the table returned by this chunk.
+local tests = {}
+
+function test(name, fn)
+    table.insert(tests, { name = name, fn = fn })
+end
+
+local function describe(value)
+    if type(value) == "string" then
+        return string.format("%q", value)
+    end
+    return tostring(value)
+end
+
+local function message(custom, default)
+    if custom then
+        return custom .. ": " .. default
+    end
+    return default
+end
+
+local function deep_equal(a, b)
+    if a == b then
+        return true
+    end
+    if type(a) ~= "table" or type(b) ~= "table" then
+        return false
+    end
+    for key, value in pairs(a) do
+        if not deep_equal(value, b[key]) then
+            return false
+        end
+    end
+    for key in pairs(b) do
+        if a[key] == nil then
+            return false
+        end
+    end
+    return true
+end
+
+function fail(msg)
+    error(msg or "Test failed", 2)
+end
+
+-- Tables are compared by content.
+function assert_eq(expected, actual, msg)
+    if not deep_equal(expected, actual) then
+        error(message(msg, "expected " .. describe(expected) .. ", got " .. describe(actual)), 2)
+    end
+end
+
+function assert_ne(unexpected, actual, msg)
+    if deep_equal(unexpected, actual) then
+        error(message(msg, "expected anything but " .. describe(unexpected)), 2)
+    end
+end
+
+function assert_true(value, msg)
+    if value ~= true then
+        error(message(msg, "expected true, got " .. describe(value)), 2)
+    end
+end
+
+function assert_false(value, msg)
+    if value ~= false then
+        error(message(msg, "expected false, got " .. describe(value)), 2)
+    end
+end
+
+function assert_nil(value, msg)
+    if value ~= nil then
+        error(message(msg, "expected nil, got " .. describe(value)), 2)
+    end
+end
+
+function assert_not_nil(value, msg)
+    if value == nil then
+        error(message(msg, "expected a value, got nil"), 2)
+    end
+end
+
+-- Returns the error raised by `fn`, optionally checking that its message matches a pattern.
+function assert_error(fn, pattern, msg)
+    local ok, err = pcall(fn)
+    if ok then
+        error(message(msg, "expected an error"), 2)
+    end
+    if pattern and not string.find(tostring(err), pattern) then
+        error(message(msg, "expected an error matching " .. describe(pattern) .. ", got " .. describe(tostring(err))), 2)
+    end
+    return err
+end
+
+-- Checks that calling `fn` with the given arguments raises an error of the given kind (see
+-- `itb_rs.error`), and returns the error.
+function assert_error_kind(kind, fn, ...)
+    local ok, err = itb_rs.error.pcall(fn, ...)
+    if ok then
+        error("expected an error of kind " .. kind, 2)
+    end
+    if err.kind ~= kind then
+        error("expected an error of kind " .. kind .. ", got " .. tostring(err), 2)
+    end
+    return err
+end
+
+return tests
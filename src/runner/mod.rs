@@ -0,0 +1,321 @@
+//! Headless runner for Lua tests of mods, so that they can be run outside of the game, e.g. on CI.
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use mlua::Lua;
+use mlua::prelude::{LuaFunction, LuaResult, LuaTable};
+use walkdir::WalkDir;
+
+use crate::error::{Error, ErrorKind, WithPath};
+use crate::io::save_data::DiscoveryContext;
+use crate::lua;
+pub use crate::io::Sandbox;
+pub use report::{Format, TestResult};
+
+mod report;
+
+const ASSERT_LIBRARY: &str = include_str!("assert.lua");
+const TEST_FILE_SUFFIX: &str = "_test.lua";
+
+pub const USAGE: &str = "\
+Usage: itb_rs_test [options] <path>...
+
+Runs Lua scripts, or all *_test.lua files in the given directories, with the itb_rs module loaded.
+
+Options:
+  --game-dir <dir>    Game directory of the sandbox, relative paths are resolved against it
+                      (default: working directory)
+  --save-dir <dir>    Save data directory of the sandbox (default: discovered)
+  --format <format>   Report format, tap or junit (default: tap)
+  --output <file>     File to write the report to (default: standard output)
+  --help              Print this message";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub game_directory: PathBuf,
+    pub save_data_directory: Option<PathBuf>,
+    pub format: Format,
+    pub output: Option<PathBuf>,
+    /// Test scripts, or directories to search for test files.
+    pub paths: Vec<PathBuf>,
+}
+
+impl Options {
+    pub fn parse<I: IntoIterator<Item=String>>(args: I) -> crate::error::Result<Options> {
+        let mut options = Options {
+            game_directory: std::env::current_dir()?,
+            save_data_directory: None,
+            format: Format::Tap,
+            output: None,
+            paths: Vec::new(),
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Missing value of option {}", arg)));
+
+            match arg.as_str() {
+                "--game-dir" => options.game_directory = PathBuf::from(value()?),
+                "--save-dir" => options.save_data_directory = Some(PathBuf::from(value()?)),
+                "--format" => {
+                    let name = value()?;
+                    options.format = Format::parse(&name)
+                        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Unknown report format: {}", name)))?;
+                }
+                "--output" => options.output = Some(PathBuf::from(value()?)),
+                _ if arg.starts_with("--") => {
+                    return Err(Error::new(ErrorKind::InvalidInput, format!("Unknown option: {}", arg)));
+                }
+                _ => options.paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        if options.paths.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "No test scripts specified"));
+        }
+
+        Ok(options)
+    }
+
+    pub fn sandbox(&self) -> crate::error::Result<Sandbox> {
+        match &self.save_data_directory {
+            Some(save_data_directory) => Sandbox::new(&self.game_directory, save_data_directory),
+            None => Sandbox::discover_in(DiscoveryContext::from_environment(self.game_directory.clone())),
+        }
+    }
+}
+
+/// Entry point of the test runner binary, returning its exit code.
+pub fn main() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let result = Options::parse(args)
+        .map_err(|error| format!("{}\n\n{}", error, USAGE))
+        .and_then(|options| run(&options).map_err(|error| error.to_string()));
+
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(message) => {
+            eprintln!("{}", message);
+            2
+        }
+    }
+}
+
+/// Runs all tests and writes the report. Returns whether all tests passed.
+pub fn run(options: &Options) -> crate::error::Result<bool> {
+    let sandbox = options.sandbox()?;
+    let files = test_files(&options.paths)?;
+    if files.is_empty() {
+        return Err(Error::new(ErrorKind::NotFound, "No test files found"));
+    }
+
+    let results: Vec<TestResult> = files.iter()
+        .flat_map(|file| run_file(&sandbox, file))
+        .collect();
+
+    let report = options.format.report(&results);
+    match &options.output {
+        Some(output) => std::fs::write(output, report).with_path(output)?,
+        None => print!("{}", report),
+    }
+
+    Ok(results.iter().all(TestResult::passed))
+}
+
+/// Lists test scripts to run: files are taken as they are, directories are searched for files
+/// ending with `_test.lua`.
+pub fn test_files(paths: &[PathBuf]) -> crate::error::Result<Vec<PathBuf>> {
+    let mut result = Vec::new();
+
+    for path in paths {
+        if path.is_dir() {
+            for entry in WalkDir::new(path).sort_by_file_name() {
+                let entry = entry?;
+                if entry.file_type().is_file() && entry.file_name().to_string_lossy().ends_with(TEST_FILE_SUFFIX) {
+                    result.push(entry.into_path());
+                }
+            }
+        } else if path.is_file() {
+            result.push(path.clone());
+        } else {
+            return Err(Error::new(ErrorKind::NotFound, "Test script doesn't exist").with_path(path));
+        }
+    }
+
+    Ok(result)
+}
+
+/// Runs a test script in a fresh Lua state. Scripts that don't register any tests with `test`
+/// count as a single test, which passes if the script runs without errors.
+pub fn run_file(sandbox: &Sandbox, path: &Path) -> Vec<TestResult> {
+    let file = path.to_string_lossy().replace('\\', "/");
+    let result = |name: &str, started: Instant, outcome: LuaResult<()>| TestResult {
+        file: file.clone(),
+        name: name.to_string(),
+        duration: started.elapsed(),
+        failure: outcome.err().map(|error| error.to_string()),
+    };
+
+    let started = Instant::now();
+    let lua = Lua::new();
+    let tests = match load_file(&lua, sandbox, path) {
+        Ok(tests) => tests,
+        Err(error) => return vec![result("<load>", started, Err(error))],
+    };
+    if tests.is_empty() {
+        return vec![result("<script>", started, Ok(()))];
+    }
+
+    tests.into_iter()
+        .map(|(name, function)| {
+            let started = Instant::now();
+            result(&name, started, function.call(()))
+        })
+        .collect()
+}
+
+/// Sets up the Lua state the way the game would, runs the script, and returns the tests it
+/// registered.
+fn load_file<'lua>(lua: &'lua Lua, sandbox: &Sandbox, path: &Path) -> LuaResult<Vec<(String, LuaFunction<'lua>)>> {
+    lua.set_app_data(sandbox.clone());
+    lua.globals().set(lua::exports::GLOBAL_NAME, lua::exports::init(lua)?)?;
+
+    let script_directory = path.parent().unwrap_or_else(|| Path::new("."));
+    let package: LuaTable = lua.globals().get("package")?;
+    let package_path: String = package.get("path")?;
+    package.set("path", format!(
+        "{};{};{}",
+        script_directory.join("?.lua").to_string_lossy(),
+        sandbox.game_directory().join("?.lua").to_string_lossy(),
+        package_path
+    ))?;
+
+    let tests: LuaTable = lua.load(ASSERT_LIBRARY).set_name("@assert.lua")?.eval()?;
+
+    let source = std::fs::read(path)?;
+    lua.load(&source).set_name(format!("@{}", path.to_string_lossy()))?.exec()?;
+
+    tests.sequence_values::<LuaTable>()
+        .map(|test| {
+            let test = test?;
+            Ok((test.get("name")?, test.get("fn")?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use crate::error::ErrorKind;
+    use crate::io::Sandbox;
+    use crate::runner::{Format, Options, run_file, test_files};
+
+    fn sandbox(tmp_dir: &TempDir) -> Sandbox {
+        std::fs::create_dir_all(tmp_dir.path().join("game")).unwrap();
+        std::fs::create_dir_all(tmp_dir.path().join("save")).unwrap();
+        Sandbox::new(tmp_dir.path().join("game"), tmp_dir.path().join("save")).unwrap()
+    }
+
+    fn script(tmp_dir: &TempDir, name: &str, source: &str) -> PathBuf {
+        let path = tmp_dir.path().join(name);
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    #[test]
+    fn options_should_be_parsed() {
+        let args = ["--format", "junit", "--save-dir", "save", "tests", "--game-dir", "game"]
+            .map(String::from);
+
+        let options = Options::parse(args).unwrap();
+
+        assert_eq!(Format::JUnit, options.format);
+        assert_eq!(PathBuf::from("game"), options.game_directory);
+        assert_eq!(Some(PathBuf::from("save")), options.save_data_directory);
+        assert_eq!(vec![PathBuf::from("tests")], options.paths);
+    }
+
+    #[test]
+    fn options_without_paths_should_be_rejected() {
+        let error = Options::parse(["--format".to_string(), "tap".to_string()]).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+    }
+
+    #[test]
+    fn test_files_should_only_include_test_scripts_from_directories() {
+        let tmp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp_dir.path().join("tests/nested")).unwrap();
+        std::fs::write(tmp_dir.path().join("tests/b_test.lua"), "").unwrap();
+        std::fs::write(tmp_dir.path().join("tests/nested/a_test.lua"), "").unwrap();
+        std::fs::write(tmp_dir.path().join("tests/helpers.lua"), "").unwrap();
+
+        let files = test_files(&[tmp_dir.path().join("tests")]).unwrap();
+
+        assert_eq!(vec![
+            tmp_dir.path().join("tests/b_test.lua"),
+            tmp_dir.path().join("tests/nested/a_test.lua"),
+        ], files);
+    }
+
+    #[test]
+    fn registered_tests_should_be_reported_individually() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = script(&tmp_dir, "math_test.lua", r#"
+            test("passes", function() assert_eq({ 1, { 2 } }, { 1, { 2 } }) end)
+            test("fails", function() assert_eq(1, 2) end)
+        "#);
+
+        let results = run_file(&sandbox(&tmp_dir), &path);
+
+        assert_eq!(2, results.len());
+        assert!(results[0].passed());
+        assert_eq!("fails", results[1].name);
+        assert!(results[1].failure.as_ref().unwrap().contains("expected 1, got 2"));
+    }
+
+    #[test]
+    fn script_without_tests_should_count_as_single_test() {
+        let tmp_dir = TempDir::new().unwrap();
+        let passing = script(&tmp_dir, "passing.lua", "assert(true)");
+        let failing = script(&tmp_dir, "failing.lua", "error('broken')");
+
+        let sandbox = sandbox(&tmp_dir);
+        let passing_results = run_file(&sandbox, &passing);
+        let failing_results = run_file(&sandbox, &failing);
+
+        assert_eq!(1, passing_results.len());
+        assert!(passing_results[0].passed());
+        assert_eq!(1, failing_results.len());
+        assert!(failing_results[0].failure.as_ref().unwrap().contains("broken"));
+    }
+
+    #[test]
+    fn scripts_should_be_confined_to_sandbox() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = script(&tmp_dir, "io_test.lua", r#"
+            test("writes within game directory", function()
+                itb_rs.io.file("mod.txt"):write_string("content")
+                assert_eq("content", itb_rs.io.file("mod.txt"):read_to_string())
+            end)
+            test("rejects paths outside of sandbox", function()
+                assert_error(function() itb_rs.io.file("../outside.txt") end, "not within an allowed directory")
+            end)
+        "#);
+
+        let results = run_file(&sandbox(&tmp_dir), &path);
+
+        assert!(results.iter().all(|result| result.passed()), "{:?}", results);
+        assert!(tmp_dir.path().join("game/mod.txt").exists());
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Outcome of a single test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    /// Test file the test was defined in.
+    pub file: String,
+    pub name: String,
+    pub duration: Duration,
+    /// Error message, if the test failed.
+    pub failure: Option<String>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Tap,
+    JUnit,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "tap" => Some(Format::Tap),
+            "junit" => Some(Format::JUnit),
+            _ => None
+        }
+    }
+
+    pub fn report(&self, results: &[TestResult]) -> String {
+        match self {
+            Format::Tap => tap(results),
+            Format::JUnit => junit(results),
+        }
+    }
+}
+
+/// Formats results according to the Test Anything Protocol, version 13.
+fn tap(results: &[TestResult]) -> String {
+    let mut out = String::new();
+    writeln!(out, "TAP version 13").unwrap();
+    writeln!(out, "1..{}", results.len()).unwrap();
+
+    for (index, result) in results.iter().enumerate() {
+        let status = if result.passed() { "ok" } else { "not ok" };
+        writeln!(out, "{} {} - {}: {}", status, index + 1, tap_description(&result.file), tap_description(&result.name)).unwrap();
+
+        if let Some(failure) = &result.failure {
+            writeln!(out, "  ---").unwrap();
+            writeln!(out, "  message: |").unwrap();
+            for line in failure.lines() {
+                writeln!(out, "    {}", line).unwrap();
+            }
+            writeln!(out, "  ...").unwrap();
+        }
+    }
+
+    out
+}
+
+/// Escapes text for a test line's description, in which `#` would start a directive, and a line
+/// break would end the line.
+fn tap_description(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('#', "\\#")
+        .replace("\r\n", " ")
+        .replace(['\r', '\n'], " ")
+}
+
+/// Formats results as JUnit XML, with a test suite per test file.
+fn junit(results: &[TestResult]) -> String {
+    let mut files: Vec<&str> = Vec::new();
+    for result in results {
+        if !files.contains(&result.file.as_str()) {
+            files.push(&result.file);
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(out, r#"<testsuites tests="{}" failures="{}" time="{}">"#,
+             results.len(), failures(results.iter()), seconds(results.iter())).unwrap();
+
+    for file in files {
+        let suite = || results.iter().filter(move |result| result.file == file);
+        writeln!(out, r#"  <testsuite name="{}" tests="{}" failures="{}" time="{}">"#,
+                 escape(file), suite().count(), failures(suite()), seconds(suite())).unwrap();
+
+        for result in suite() {
+            let attributes = format!(r#"name="{}" classname="{}" time="{}""#,
+                                     escape(&result.name), escape(file), seconds(std::iter::once(result)));
+            match &result.failure {
+                None => writeln!(out, "    <testcase {}/>", attributes).unwrap(),
+                Some(failure) => {
+                    let summary = failure.lines().next().unwrap_or_default();
+                    writeln!(out, "    <testcase {}>", attributes).unwrap();
+                    writeln!(out, r#"      <failure message="{}">{}</failure>"#, escape(summary), escape(failure)).unwrap();
+                    writeln!(out, "    </testcase>").unwrap();
+                }
+            }
+        }
+
+        writeln!(out, "  </testsuite>").unwrap();
+    }
+
+    writeln!(out, "</testsuites>").unwrap();
+    out
+}
+
+fn failures<'a, I: Iterator<Item=&'a TestResult>>(results: I) -> usize {
+    results.filter(|result| !result.passed()).count()
+}
+
+fn seconds<'a, I: Iterator<Item=&'a TestResult>>(results: I) -> String {
+    let total: Duration = results.map(|result| result.duration).sum();
+    format!("{:.3}", total.as_secs_f64())
+}
+
+/// Escapes text for XML, replacing the control characters XML doesn't allow, which error messages
+/// raised from Lua may contain, with the replacement character.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+        .replace(|c: char| !is_xml_char(c), "\u{FFFD}")
+}
+
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..='\u{10FFFF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::runner::report::{Format, TestResult};
+
+    fn results() -> Vec<TestResult> {
+        vec![
+            TestResult {
+                file: "io_test.lua".to_string(),
+                name: "reads files".to_string(),
+                duration: Duration::from_millis(5),
+                failure: None,
+            },
+            TestResult {
+                file: "io_test.lua".to_string(),
+                name: "writes <files>".to_string(),
+                duration: Duration::from_millis(10),
+                failure: Some("expected 1, got 2\nstack traceback:".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn tap_should_report_failures_with_diagnostics() {
+        let report = Format::Tap.report(&results());
+
+        assert_eq!("TAP version 13\n\
+                    1..2\n\
+                    ok 1 - io_test.lua: reads files\n\
+                    not ok 2 - io_test.lua: writes <files>\n  \
+                    ---\n  \
+                    message: |\n    \
+                    expected 1, got 2\n    \
+                    stack traceback:\n  \
+                    ...\n", report);
+    }
+
+    #[test]
+    fn junit_should_group_tests_by_file_and_escape_text() {
+        let report = Format::JUnit.report(&results());
+
+        assert!(report.contains(r#"<testsuites tests="2" failures="1" time="0.015">"#), "{}", report);
+        assert!(report.contains(r#"<testsuite name="io_test.lua" tests="2" failures="1" time="0.015">"#), "{}", report);
+        assert!(report.contains(r#"<testcase name="reads files" classname="io_test.lua" time="0.005"/>"#), "{}", report);
+        assert!(report.contains(r#"<testcase name="writes &lt;files&gt;""#), "{}", report);
+        assert!(report.contains(r#"<failure message="expected 1, got 2">"#), "{}", report);
+    }
+
+    #[test]
+    fn tap_should_escape_directives_and_line_breaks_in_descriptions() {
+        let mut result = results().remove(0);
+        result.name = "counts # of \\ lines\nin files".to_string();
+
+        let report = Format::Tap.report(&[result]);
+
+        assert!(report.contains("ok 1 - io_test.lua: counts \\# of \\\\ lines in files\n"), "{}", report);
+        assert_eq!(3, report.lines().count());
+    }
+
+    #[test]
+    fn junit_should_replace_characters_xml_does_not_allow() {
+        let mut result = results().remove(1);
+        result.failure = Some("bad\u{0}byte\u{1b}[31m\ttab".to_string());
+
+        let report = Format::JUnit.report(&[result]);
+
+        assert!(report.contains("<failure message=\"bad\u{FFFD}byte\u{FFFD}[31m\ttab\">"), "{}", report);
+        assert!(!report.contains('\u{0}'), "{}", report);
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+
+use crate::error::{Error, ErrorKind};
+
+/// State shared between a [Task] and the thread running it.
+#[derive(Default)]
+pub struct TaskContext {
+    done: AtomicUsize,
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl TaskContext {
+    /// Records the task's progress. Returns `false` once the task has been cancelled, so that
+    /// it can back the progress callbacks of bulk package operations.
+    pub fn report(&self, done: usize, total: usize) -> bool {
+        self.done.store(done, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+        !self.is_cancelled()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Work running on a background thread, so that it doesn't block the thread owning the Lua
+/// state. The work itself never touches Lua; its result is only converted into Lua values once
+/// retrieved with [Task::result].
+///
+/// Cancellation is cooperative: the work is expected to check [TaskContext::report] or
+/// [TaskContext::is_cancelled] between steps. Dropping the task cancels it.
+pub struct Task<T> {
+    context: Arc<TaskContext>,
+    handle: Option<JoinHandle<crate::error::Result<T>>>,
+    result: Option<crate::error::Result<T>>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    pub fn spawn<F>(work: F) -> Task<T>
+        where F: FnOnce(&TaskContext) -> crate::error::Result<T> + Send + 'static {
+        let context = Arc::new(TaskContext::default());
+        let thread_context = context.clone();
+        let handle = std::thread::spawn(move || work(&thread_context));
+
+        Task {
+            context,
+            handle: Some(handle),
+            result: None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        match &self.handle {
+            None => true,
+            Some(handle) => handle.is_finished()
+        }
+    }
+
+    /// Returns the number of steps completed so far, and the total number of steps, as last
+    /// reported by the task.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.context.done.load(Ordering::Relaxed), self.context.total.load(Ordering::Relaxed))
+    }
+
+    pub fn cancel(&self) {
+        self.context.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the task's result, or `None` if it is still running.
+    pub fn result(&mut self) -> Option<&crate::error::Result<T>> {
+        if self.result.is_none() && self.is_done() {
+            if let Some(handle) = self.handle.take() {
+                self.result = Some(handle.join()
+                    .unwrap_or_else(|_| Err(Error::new(ErrorKind::Io, "Task panicked"))));
+            }
+        }
+
+        self.result.as_ref()
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        self.context.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    use crate::error::{Error, ErrorKind};
+    use crate::task::Task;
+
+    fn wait_until_done<T: Send + 'static>(task: &Task<T>) {
+        while !task.is_done() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn result_should_be_available_once_done() {
+        let (sender, receiver) = channel::<()>();
+        let mut task = Task::spawn(move |context| {
+            context.report(1, 2);
+            receiver.recv().unwrap();
+            Ok(42)
+        });
+
+        assert!(task.result().is_none());
+
+        sender.send(()).unwrap();
+        wait_until_done(&task);
+
+        assert_eq!(42, *task.result().unwrap().as_ref().unwrap());
+        assert_eq!(42, *task.result().unwrap().as_ref().unwrap());
+        assert_eq!((1, 2), task.progress());
+    }
+
+    #[test]
+    fn cancelled_task_should_observe_cancellation() {
+        let mut task = Task::spawn(|context| {
+            let mut done = 0;
+            while context.report(done, usize::MAX) {
+                done += 1;
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Err::<(), _>(Error::new(ErrorKind::Aborted, "cancelled"))
+        });
+
+        task.cancel();
+        wait_until_done(&task);
+
+        assert_eq!(ErrorKind::Aborted, task.result().unwrap().as_ref().unwrap_err().kind());
+    }
+
+    #[test]
+    fn panicking_task_should_report_error() {
+        let mut task = Task::spawn(|_| -> crate::error::Result<()> { panic!("boom") });
+        wait_until_done(&task);
+
+        assert!(task.result().unwrap().is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Semantic versions, with lenient parsing of the irregular versions mods use.
+use semver::{BuildMetadata, Prerelease, Version, VersionReq};
+
+use crate::error::{Error, ErrorKind};
+
+/// Component of a version to increment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl Part {
+    pub fn parse<S: AsRef<str>>(name: S) -> crate::error::Result<Part> {
+        match name.as_ref() {
+            "major" => Ok(Part::Major),
+            "minor" => Ok(Part::Minor),
+            "patch" => Ok(Part::Patch),
+            other => Err(Error::new(ErrorKind::InvalidInput, format!(
+                "Unknown version part '{}', expected one of: major, minor, patch", other
+            )))
+        }
+    }
+}
+
+/// Parses a version, falling back to reading irregular versions as closely to semver as possible:
+/// a leading `v` is skipped, missing minor and patch components are zero, components past the
+/// patch become build metadata, and whatever follows the numbers becomes the pre-release, eg.
+/// `v3` is `3.0.0`, `1.2b` is `1.2.0-b` and `1.2.3.4` is `1.2.3+4`.
+pub fn parse_lenient(text: &str) -> crate::error::Result<Version> {
+    let text = text.trim();
+    let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+    if let Ok(version) = Version::parse(text) {
+        return Ok(version);
+    }
+
+    let mut numbers = Vec::new();
+    let mut rest = text;
+    loop {
+        let length = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if length == 0 {
+            break;
+        }
+        let number = rest[..length].parse::<u64>()
+            .map_err(|error| Error::new(ErrorKind::InvalidInput, format!("Malformed version '{}': {}", text, error)))?;
+        numbers.push(number);
+        rest = &rest[length..];
+
+        match rest.strip_prefix('.') {
+            Some(next) if next.starts_with(|c: char| c.is_ascii_digit()) => rest = next,
+            _ => break,
+        }
+    }
+    if numbers.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Malformed version '{}': doesn't start with a number", text)));
+    }
+
+    let (pre, build) = match rest.split_once('+') {
+        Some((pre, build)) => (pre, build),
+        None => (rest, ""),
+    };
+    let extra_numbers: Vec<String> = numbers.iter().skip(3).map(u64::to_string).collect();
+    let build = identifiers(&format!("{}.{}", extra_numbers.join("."), build));
+
+    Ok(Version {
+        major: numbers[0],
+        minor: numbers.get(1).copied().unwrap_or(0),
+        patch: numbers.get(2).copied().unwrap_or(0),
+        pre: Prerelease::new(&identifiers(pre)).expect("identifiers are valid pre-release"),
+        build: BuildMetadata::new(&build).expect("identifiers are valid build metadata"),
+    })
+}
+
+/// Turns free-form text into dot-separated semver identifiers, made of alphanumerics and hyphens,
+/// with numeric identifiers stripped of leading zeros.
+fn identifiers(text: &str) -> String {
+    let text: String = text.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '-' })
+        .collect();
+
+    text.split('.')
+        .map(|identifier| identifier.trim_matches('-'))
+        .filter(|identifier| !identifier.is_empty())
+        .map(|identifier| if identifier.bytes().all(|byte| byte.is_ascii_digit()) {
+            let trimmed = identifier.trim_start_matches('0');
+            if trimmed.is_empty() { "0" } else { trimmed }
+        } else {
+            identifier
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Parses a version requirement in which a bare version is the minimum version, the way the mod
+/// loader treats it, eg. `1.2` is the same as `>=1.2` rather than `^1.2` as in Cargo.
+pub fn parse_minimum_requirement(text: &str) -> crate::error::Result<VersionReq> {
+    let text = text.trim();
+    let version = text.strip_prefix(['v', 'V']).unwrap_or(text);
+    let requirement = if version.starts_with(|c: char| c.is_ascii_digit()) {
+        VersionReq::parse(&format!(">={}", version))
+    } else {
+        VersionReq::parse(text)
+    };
+    Ok(requirement?)
+}
+
+/// Next version after incrementing the part. Pre-releases are bumped to their release instead if
+/// it's the next version of that part, eg. bumping the patch of `1.2.3-beta` gives `1.2.3`.
+/// Fails with `InvalidInput` if the part is already at its largest value.
+pub fn bump(version: &Version, part: Part) -> crate::error::Result<Version> {
+    let release = !version.pre.is_empty();
+    let (major, minor, patch) = (version.major, version.minor, version.patch);
+    let increment = |value: u64| value.checked_add(1)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Version {} can't be bumped any further", version)));
+
+    let (major, minor, patch) = match part {
+        Part::Major if release && minor == 0 && patch == 0 => (major, 0, 0),
+        Part::Major => (increment(major)?, 0, 0),
+        Part::Minor if release && patch == 0 => (major, minor, 0),
+        Part::Minor => (major, increment(minor)?, 0),
+        Part::Patch if release => (major, minor, patch),
+        Part::Patch => (major, minor, increment(patch)?),
+    };
+    Ok(Version::new(major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use crate::error::ErrorKind;
+    use crate::version::{bump, parse_lenient, parse_minimum_requirement, Part};
+
+    #[test]
+    fn irregular_versions_should_be_parsed_leniently() {
+        let parse = |text| parse_lenient(text).unwrap().to_string();
+
+        assert_eq!("2.7.0-beta", parse("2.7.0-beta"));
+        assert_eq!("3.0.0", parse("v3"));
+        assert_eq!("1.2.0-b", parse("1.2b"));
+        assert_eq!("1.2.3+4", parse("1.2.3.4"));
+        assert_eq!("1.0.0-beta-2", parse(" 1.0 beta 2 "));
+        assert_eq!("1.2.0-rc.1+build", parse("01.02-rc.01+build"));
+    }
+
+    #[test]
+    fn text_without_version_numbers_should_be_rejected() {
+        assert_eq!(ErrorKind::InvalidInput, parse_lenient("beta").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, parse_lenient("").unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, parse_lenient("99999999999999999999").unwrap_err().kind());
+    }
+
+    #[test]
+    fn lenient_versions_should_order_like_semver() {
+        let parse = |text| parse_lenient(text).unwrap();
+
+        assert!(parse("2.6.4") < parse("2.7.0-beta"));
+        assert!(parse("2.7.0-beta") < parse("2.7.0"));
+        assert!(parse("1.10") > parse("1.9"));
+        assert!(parse("1.2b") < parse("1.2"));
+    }
+
+    #[test]
+    fn bump_should_increment_part_or_release_prerelease() {
+        let version = Version::parse("1.2.3").unwrap();
+        let prerelease = Version::parse("2.0.0-rc.1").unwrap();
+
+        assert_eq!("2.0.0", bump(&version, Part::Major).unwrap().to_string());
+        assert_eq!("1.3.0", bump(&version, Part::Minor).unwrap().to_string());
+        assert_eq!("1.2.4", bump(&version, Part::Patch).unwrap().to_string());
+        assert_eq!("2.0.0", bump(&prerelease, Part::Major).unwrap().to_string());
+        assert_eq!("2.0.0", bump(&prerelease, Part::Patch).unwrap().to_string());
+        assert_eq!(ErrorKind::InvalidInput, Part::parse("build").unwrap_err().kind());
+    }
+
+    #[test]
+    fn bump_should_reject_parts_that_would_overflow() {
+        let largest = parse_lenient("18446744073709551615").unwrap();
+
+        assert_eq!(ErrorKind::InvalidInput, bump(&largest, Part::Major).unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, bump(&Version::new(1, u64::MAX, 0), Part::Minor).unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidInput, bump(&Version::new(1, 2, u64::MAX), Part::Patch).unwrap_err().kind());
+        assert_eq!("18446744073709551615.1.0", bump(&largest, Part::Minor).unwrap().to_string());
+    }
+
+    #[test]
+    fn bare_versions_should_be_minimum_requirements() {
+        let requirement = parse_minimum_requirement("v1.2").unwrap();
+
+        assert_eq!(">=1.2", requirement.to_string());
+        assert!(requirement.matches(&Version::new(2, 0, 0)));
+        assert!(!parse_minimum_requirement("^1.2").unwrap().matches(&Version::new(2, 0, 0)));
+        assert_eq!(ErrorKind::InvalidInput, parse_minimum_requirement("newest").unwrap_err().kind());
+    }
+}
@@ -0,0 +1,110 @@
+local buffer = itb_rs.buffer
+
+test("buffers are created empty or from binary content", function()
+    assert_eq(0, buffer.new():len())
+    assert_eq("text", buffer.new("text"):to_string())
+    assert_eq("\0\1\2", buffer.new({ 0, 1, 2 }):tostring())
+    assert_eq("copy", buffer.new(buffer.new("copy")):to_string())
+    assert_error_kind("InvalidInput", buffer.new, true)
+end)
+
+test("numbers are written and read back in either endianness", function()
+    local data = buffer.new()
+    data:write_u8(255)
+    data:write_i8(-1)
+    data:write_u16(0x1234, "be")
+    data:write_i16(-2, "little")
+    data:write_u32(0xDEADBEEF)
+    data:write_i32(-3, "big")
+    data:write_f32(1.5)
+    data:write_f64(-0.25, "be")
+
+    assert_eq(26, data:len())
+    assert_eq(27, data:tell())
+    data:seek(1)
+    assert_eq(255, data:read_u8())
+    assert_eq(-1, data:read_i8())
+    assert_eq(0x1234, data:read_u16(nil, "be"))
+    assert_eq(-2, data:read_i16())
+    assert_eq(0xDEADBEEF, data:read_u32(nil, "le"))
+    assert_eq(-3, data:read_i32(nil, "be"))
+    assert_eq(1.5, data:read_f32())
+    assert_eq(-0.25, data:read_f64(nil, "big"))
+    assert_eq({ 0x12, 0x34 }, { data:byte(3, 4) })
+    assert_eq(0x3412, data:read_u16(3))
+end)
+
+test("reads are checked against the buffer's bounds and endianness names", function()
+    local data = buffer.new("ab")
+
+    assert_error_kind("InvalidData", function() data:read_u32() end)
+    assert_error_kind("InvalidInput", function() data:read_u8(nil, "middle") end)
+    assert_error(function() data:read_u8(0) end, "at least 1")
+    assert_error(function() data:skip(-1) end, "before the start")
+end)
+
+test("the cursor can't be moved past the largest buffer size", function()
+    local data = buffer.new("ab")
+
+    assert_error_kind("InvalidInput", function() data:seek(1e12) end)
+    assert_error_kind("InvalidInput", function() data:skip(1e12) end)
+    assert_eq(1, data:tell())
+
+    data:seek(256 * 1024 * 1024 + 1)
+    assert_error_kind("InvalidInput", function() data:write_u8(1) end)
+    assert_error_kind("InvalidInput", function() data:write_bytes("x") end)
+    assert_eq(2, data:len())
+end)
+
+test("the cursor can be moved", function()
+    local data = buffer.new("abcdef")
+
+    assert_eq(1, data:tell())
+    data:skip(2)
+    assert_eq(3, data:tell())
+    assert_eq("cd", data:read_string(2))
+    assert_eq(5, data:tell())
+    data:seek(2)
+    assert_eq("bc", data:read_bytes(2):to_string())
+    assert_eq("ef", data:read_string(2, 5))
+    assert_eq(4, data:tell())
+end)
+
+test("bytes are written at the cursor", function()
+    local data = buffer.new()
+
+    data:write_bytes("abc")
+    data:write_bytes({ 100 })
+    data:write_bytes(buffer.new("e"))
+
+    assert_eq("abcde", data:to_string())
+end)
+
+test("substrings and bytes follow the rules of the string library", function()
+    local data = buffer.new("abcdef")
+
+    assert_eq("bcd", data:sub(2, 4):to_string())
+    assert_eq("def", data:sub(-3):to_string())
+    assert_eq("", data:sub(4, 2):to_string())
+    assert_eq(97, data:byte())
+    assert_eq({ 101, 102 }, { data:byte(-2, -1) })
+end)
+
+test("content can be searched", function()
+    local data = buffer.new("abcabc")
+
+    assert_eq({ 2, 3 }, { data:find("bc") })
+    assert_eq({ 5, 6 }, { data:find("bc", 3) })
+    assert_eq({ nil, nil }, { data:find("x") })
+end)
+
+test("metamethods behave like those of strings", function()
+    local data = buffer.new("abc")
+
+    assert_eq(3, #data)
+    assert_eq("abc", tostring(data))
+    assert_true(data == buffer.new("abc"))
+    assert_false(data == buffer.new("abd"))
+    assert_eq("abcdef", (data .. "def"):to_string())
+    assert_eq("xyzabc", ("xyz" .. data):to_string())
+end)
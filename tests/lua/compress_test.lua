@@ -0,0 +1,33 @@
+local compress = itb_rs.compress
+
+local content = string.rep("Into the Breach ", 64)
+
+test("content survives a round trip through every algorithm", function()
+    for _, algorithm in ipairs({ "deflate", "zlib", "gzip", "lz4" }) do
+        local compressed = compress.compress(content, algorithm)
+
+        assert_true(compressed:len() < #content, algorithm)
+        assert_eq(content, compress.decompress(compressed, algorithm):tostring(), algorithm)
+    end
+end)
+
+test("compression level and input type are taken into account", function()
+    local fast = compress.compress(itb_rs.buffer.new(content), "zlib", 1)
+    local best = compress.compress({ string.byte(content, 1, -1) }, "zlib", 9)
+
+    assert_eq(content, compress.decompress(fast, "zlib"):tostring())
+    assert_eq(content, compress.decompress(best, "zlib"):tostring())
+end)
+
+test("decompression is limited in size", function()
+    local compressed = compress.compress(content, "gzip")
+
+    assert_eq(64 * 1024 * 1024, compress.default_size_limit)
+    assert_error_kind("InvalidData", compress.decompress, compressed, "gzip", 16)
+    assert_eq(#content, compress.decompress(compressed, "gzip", #content):len())
+end)
+
+test("invalid input is rejected", function()
+    assert_error_kind("InvalidInput", compress.compress, content, "zip")
+    assert_error(function() compress.decompress("not compressed", "zlib") end, "corrupt")
+end)
@@ -0,0 +1,35 @@
+local encoding = itb_rs.encoding
+
+test("base64 uses the standard alphabet with padding by default", function()
+    assert_eq("Pz8/Pw==", encoding.base64_encode("????"))
+    assert_eq("????", encoding.base64_decode("Pz8/Pw=="))
+end)
+
+test("base64 can use the url-safe alphabet without padding", function()
+    local options = { url_safe = true, padding = false }
+
+    assert_eq("Pz8_Pw", encoding.base64_encode("????", options))
+    assert_eq("????", encoding.base64_decode("Pz8_Pw", options))
+    assert_error_kind("InvalidData", encoding.base64_decode, "Pz8_Pw")
+end)
+
+test("hex is lowercase unless asked otherwise", function()
+    assert_eq("00ff1a", encoding.hex_encode({ 0, 255, 26 }))
+    assert_eq("00FF1A", encoding.hex_encode("\0\255\26", { uppercase = true }))
+    assert_eq("\0\255\26", encoding.hex_decode("00FF1a"))
+    assert_error_kind("InvalidData", encoding.hex_decode, "0g")
+end)
+
+test("percent-encoding escapes reserved characters", function()
+    local encoded = encoding.percent_encode("a b/c?")
+
+    assert_eq("a%20b%2Fc%3F", encoded)
+    assert_eq("a b/c?", encoding.percent_decode(encoded))
+end)
+
+test("encoders accept buffers and decoders are binary-safe", function()
+    local data = itb_rs.buffer.new("\0binary\0")
+
+    assert_eq("\0binary\0", encoding.base64_decode(encoding.base64_encode(data)))
+    assert_eq("\0binary\0", encoding.hex_decode(encoding.hex_encode(data)))
+end)
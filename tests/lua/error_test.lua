@@ -0,0 +1,54 @@
+local error_module = itb_rs.error
+
+local function missing_file()
+    return itb_rs.io.directory():file("missing.txt")
+end
+
+test("errors raised by the library can be inspected", function()
+    local file = missing_file()
+    local ok, raised = pcall(file.read_to_string, file)
+
+    local err = error_module.inspect(raised)
+
+    assert_false(ok)
+    assert_eq("NotFound", err.kind)
+    assert_eq("string", type(err.message))
+    assert_eq(file:path(), err.path)
+    assert_eq("table", type(err.causes))
+    assert_true(tostring(err):find("^NotFound: ") ~= nil)
+end)
+
+test("plain Lua errors are inspected as Lua errors", function()
+    local ok, raised = pcall(error, "plain")
+
+    local err = error_module.inspect(raised)
+
+    assert_false(ok)
+    assert_eq("Lua", err.kind)
+    assert_nil(err.path)
+    assert_eq("Lua", error_module.inspect({}).kind)
+end)
+
+test("inspecting an inspected error returns it unchanged", function()
+    local _, err = error_module.pcall(missing_file().read_to_string, missing_file())
+
+    assert_eq("NotFound", error_module.inspect(err).kind)
+end)
+
+test("pcall returns the results of successful calls", function()
+    assert_eq({ true, 1, 2 }, { error_module.pcall(function(a, b) return a, b end, 1, 2) })
+end)
+
+test("pcall returns inspected errors", function()
+    local ok, err = error_module.pcall(itb_rs.io.file, "../outside.txt")
+
+    assert_false(ok)
+    assert_eq("NotAllowed", err.kind)
+end)
+
+test("wrong argument types are reported as invalid input", function()
+    local ok, err = error_module.pcall(itb_rs.io.file, {})
+
+    assert_false(ok)
+    assert_eq("InvalidInput", err.kind)
+end)
@@ -0,0 +1,40 @@
+test("version of the library and of ftldat are reported", function()
+    local version = itb_rs.version
+
+    assert_eq(version.major .. "." .. version.minor .. "." .. version.patch, version.itb_rs)
+    assert_true(version.ftldat:find("^%d+%.%d+%.%d+") ~= nil)
+end)
+
+test("features list the optional modules in the exports table", function()
+    assert_true(#itb_rs.features > 0)
+    for _, name in ipairs(itb_rs.features) do
+        assert_eq("table", type(itb_rs[name]), name)
+        assert_true(itb_rs.has(name), name)
+        -- The error module is always there, so it isn't a feature
+        assert_true(name ~= "error")
+    end
+end)
+
+test("has looks up functions by their path", function()
+    assert_true(itb_rs.has("error.pcall"))
+    assert_true(itb_rs.has("has"))
+    assert_false(itb_rs.has("error.missing"))
+    assert_false(itb_rs.has("missing.pcall"))
+    assert_false(itb_rs.has("version.major.minor"))
+end)
+
+test("has looks up methods of classes", function()
+    assert_eq(itb_rs.has("ftldat"), itb_rs.has("Package:remove"))
+    assert_false(itb_rs.has("Package:missing"))
+    assert_false(itb_rs.has("Missing:remove"))
+end)
+
+test("require_version raises an error unless the requirement is satisfied", function()
+    assert_eq(itb_rs.version.itb_rs, itb_rs.require_version(">=" .. itb_rs.version.itb_rs))
+    assert_eq(itb_rs.version.itb_rs, itb_rs.require_version("^" .. itb_rs.version.major .. "." .. itb_rs.version.minor))
+
+    local err = assert_error_kind("Unsupported", itb_rs.require_version, ">=100.0")
+    assert_true(err.message:find(">=100.0", 1, true) ~= nil)
+    assert_error_kind("InvalidInput", itb_rs.require_version, "latest")
+    assert_true(itb_rs.has("require_version"))
+end)
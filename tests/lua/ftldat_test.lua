@@ -0,0 +1,256 @@
+local ftldat = itb_rs.ftldat
+
+local function game_dir()
+    return itb_rs.io.directory()
+end
+
+-- Paths taken by the ftldat module are resolved like the io module's, so absolute ones work as well
+local function path(name)
+    return game_dir():file(name):path()
+end
+
+local function sample_package(format)
+    local package = ftldat.new_package({ format = format })
+    package:add_entry_from_string("data/text.txt", "text")
+    package:add_entry_from_byte_array("data/bytes.bin", { 0, 1, 2 })
+    package:add_entry_from_byte_array("img/buffer.bin", itb_rs.buffer.new("buffer"))
+    return package
+end
+
+local function sorted(list)
+    table.sort(list)
+    return list
+end
+
+test("entries can be added, read and removed", function()
+    local package = sample_package()
+
+    assert_eq(3, package:len())
+    assert_eq(3, package:entry_count())
+    assert_true(package:exists("data/text.txt"))
+    assert_eq("text", package:read_content_as_string("data/text.txt"))
+    assert_eq({ 0, 1, 2 }, package:read_content_as_byte_array("data/bytes.bin"))
+    assert_eq("buffer", package:read_content_as_buffer("img/buffer.bin"):tostring())
+    assert_nil(package:read_content_as_string("missing.txt"))
+    assert_eq(4, package:entry_size("data/text.txt"))
+    assert_nil(package:entry_size("missing.txt"))
+
+    assert_true(package:remove("data/text.txt"))
+    assert_false(package:exists("data/text.txt"))
+    package:clear()
+    assert_eq(0, package:len())
+end)
+
+test("adding an existing entry fails, putting it replaces it", function()
+    local package = sample_package()
+
+    assert_error_kind("AlreadyExists", function() package:add_entry_from_string("data/text.txt", "other") end)
+
+    package:put_entry_from_string("data/text.txt", "string")
+    package:put_entry_from_byte_array("data/bytes.bin", { 3 })
+    package:put_entry_from_byte_array("img/buffer.bin", itb_rs.buffer.new("other"))
+    assert_eq("string", package:read_content_as_string("data/text.txt"))
+    assert_eq({ 3 }, package:read_content_as_byte_array("data/bytes.bin"))
+    assert_eq("other", package:read_content_as_string("img/buffer.bin"))
+end)
+
+test("entries can be added from files", function()
+    game_dir():file("source.txt"):write_string("from file")
+    local package = ftldat.new_package()
+
+    package:add_entry_from_file("a.txt", path("source.txt"))
+    package:put_entry_from_file("b.txt", path("source.txt"))
+
+    assert_eq("from file", package:read_content_as_string("a.txt"))
+    assert_eq("from file", package:read_content_as_string("b.txt"))
+end)
+
+test("removing an entry leaves the others unread", function()
+    sample_package():to_file(path("removal.dat"))
+    local package = ftldat.read_package(path("removal.dat"))
+    package:add_entry_from_file("missing.txt", path("missing.txt"))
+
+    assert_true(package:remove("data/text.txt"))
+
+    assert_eq({ "data/bytes.bin", "img/buffer.bin", "missing.txt" }, sorted(package:inner_paths()))
+    assert_eq("buffer", package:read_content_as_string("img/buffer.bin"))
+    assert_true(package:exists("missing.txt"))
+end)
+
+test("inner paths can be listed and filtered", function()
+    local package = sample_package()
+
+    assert_eq({ "data/bytes.bin", "data/text.txt", "img/buffer.bin" }, sorted(package:inner_paths()))
+    assert_eq({ "data/bytes.bin", "data/text.txt" }, sorted(package:inner_paths("data/")))
+    assert_eq({ "data/text.txt" }, package:inner_paths("**/*.txt"))
+end)
+
+test("packages can be written and read in both formats", function()
+    for _, format in ipairs({ "dat", "pkg" }) do
+        local file = path("resource." .. format)
+        local progress = {}
+        sample_package(format):to_file(file, nil, function(done, total)
+            table.insert(progress, { done, total })
+        end)
+
+        assert_eq({ { 0, 3 }, { 3, 3 } }, progress)
+
+        assert_eq(format, ftldat.detect_format(file))
+        local package = ftldat.read_package(file)
+        assert_eq(format, package:format())
+        assert_eq("text", package:read_content_as_string("data/text.txt"))
+
+        local entries = {}
+        for inner_path, size, offset in package:entries() do
+            entries[inner_path] = { size, type(offset) }
+        end
+        assert_eq({ 4, "number" }, entries["data/text.txt"])
+    end
+end)
+
+test("packages can be converted between formats", function()
+    sample_package("itb"):to_file(path("converted.pkg"), { format = "ftl" })
+
+    local package = ftldat.read_package(path("converted.pkg"), { format = "pkg" })
+
+    assert_eq("pkg", package:format())
+    assert_eq(3, package:len())
+end)
+
+test("written packages are closed", function()
+    local package = sample_package()
+    package:to_file(path("closed.dat"))
+
+    assert_error_kind("Closed", function() package:len() end)
+
+    local destroyed = sample_package()
+    destroyed:destroy()
+    assert_error_kind("Closed", function() destroyed:inner_paths() end)
+end)
+
+test("aborted writes keep the package open", function()
+    local package = sample_package()
+
+    assert_error_kind("Aborted", function() package:to_file(path("aborted.dat"), nil, function() return false end) end)
+
+    assert_false(game_dir():file("aborted.dat"):exists())
+    assert_eq(3, package:len())
+end)
+
+test("writes read the files added to the package", function()
+    game_dir():file("source.txt"):write_string("from file")
+    local package = sample_package()
+    package:add_entry_from_file("file.txt", path("source.txt"))
+    package:add_entry_from_file("missing.txt", path("missing.txt"))
+
+    assert_error_kind("NotFound", function() package:to_file(path("unreadable.dat")) end)
+    assert_eq(5, package:len())
+
+    package:remove("missing.txt")
+    package:to_file(path("readable.dat"))
+    assert_eq("from file", ftldat.read_package(path("readable.dat")):read_content_as_string("file.txt"))
+end)
+
+test("missing packages are reported", function()
+    assert_error_kind("NotFound", ftldat.read_package, path("missing.dat"))
+end)
+
+test("directories can be added with progress and filters", function()
+    local source = game_dir():directory("source")
+    source:file("scripts", "init.lua"):write_string("return {}")
+    source:file("img", "unit.png"):write_string("png")
+    local package = sample_package()
+    local reports = 0
+
+    assert_eq({ 1, 0 }, { package:add_directory(source, "mods/", "scripts/", function() reports = reports + 1 end) })
+
+    assert_true(reports > 0)
+    assert_true(package:exists("mods/scripts/init.lua"))
+    assert_false(package:exists("mods/img/unit.png"))
+    assert_error_kind("Aborted", function() package:add_directory(source, "other/", nil, function() return false end) end)
+    assert_false(package:exists("other/img/unit.png"))
+end)
+
+test("entries can be extracted", function()
+    local package = sample_package()
+
+    package:extract(game_dir():directory("extracted"):path())
+    local count = package:extract_all(game_dir():directory("filtered"), { "img/" })
+
+    assert_eq("text", game_dir():file("extracted", "data", "text.txt"):read_to_string())
+    assert_eq(1, count)
+    assert_true(game_dir():file("filtered", "img", "buffer.bin"):exists())
+    assert_false(game_dir():file("filtered", "data", "text.txt"):exists())
+end)
+
+test("relative extraction paths are resolved against the game directory", function()
+    -- The working directory of the tests lies outside of the sandbox
+    assert_eq(3, sample_package():extract("relative"))
+
+    assert_eq("text", game_dir():file("relative", "data", "text.txt"):read_to_string())
+    assert_error_kind("NotAllowed", sample_package().extract, sample_package(), "../outside")
+end)
+
+test("packages can be diffed and merged", function()
+    local base = sample_package()
+    local other = ftldat.new_package()
+    other:add_entry_from_string("data/text.txt", "changed")
+    other:add_entry_from_string("new.txt", "new")
+
+    local diff = ftldat.diff(base, other)
+    assert_eq({ "new.txt" }, diff.added)
+    assert_eq({ "data/bytes.bin", "img/buffer.bin" }, sorted(diff.removed))
+    assert_eq({ "data/text.txt" }, diff.modified)
+
+    assert_error_kind("AlreadyExists", ftldat.merge, base, other)
+
+    local merge = ftldat.merge(base, other, "take_other")
+    assert_eq({ "new.txt" }, merge.added)
+    assert_eq({ "data/text.txt" }, merge.replaced)
+    assert_eq({}, merge.kept)
+    assert_eq("changed", base:read_content_as_string("data/text.txt"))
+end)
+
+test("packages can be verified and repaired", function()
+    sample_package():to_file(path("valid.dat"))
+    local content = game_dir():file("valid.dat"):read_to_byte_array()
+    -- Cut off the end of the last entry
+    game_dir():file("broken.dat"):write_byte_array({ unpack(content, 1, #content - 2) })
+
+    local valid = ftldat.verify(path("valid.dat"))
+    local broken = ftldat.verify(path("broken.dat"), { repair = path("repaired.dat") })
+
+    assert_true(valid.ok)
+    assert_eq("dat", valid.format)
+    assert_eq(3, valid.entry_count)
+    assert_eq(3, valid.readable_count)
+    assert_false(broken.ok)
+    assert_true(#broken.problems > 0)
+    assert_eq("string", type(broken.problems[1].kind))
+    assert_eq(2, broken.repaired_count)
+    assert_eq(2, ftldat.read_package(path("repaired.dat")):len())
+end)
+
+test("paths outside of the sandbox are not allowed", function()
+    sample_package():to_file("inside.dat")
+    local package = ftldat.read_package("inside.dat")
+
+    assert_error_kind("NotAllowed", ftldat.read_package, "../outside.dat")
+    assert_error_kind("NotAllowed", ftldat.verify, "inside.dat", { repair = "../repaired.dat" })
+    assert_error_kind("NotAllowed", package.add_entry_from_file, package, "a.txt", "../outside.txt")
+    assert_error_kind("NotAllowed", package.put_entry_from_file, package, "a.txt", "../outside.txt")
+    assert_error_kind("NotAllowed", package.to_file, package, "../outside.dat")
+    assert_eq(3, package:len())
+end)
+
+test("safe variants return errors instead of raising them", function()
+    local package, kind = ftldat.safe.read_package(path("missing.dat"))
+    assert_nil(package)
+    assert_eq("NotFound", kind)
+
+    local result, add_kind = ftldat.safe.call(sample_package(), "add_entry_from_string", "data/text.txt", "")
+    assert_nil(result)
+    assert_eq("AlreadyExists", add_kind)
+
+    assert_eq("dat", ftldat.safe.new_package():format())
+end)
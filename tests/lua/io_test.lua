@@ -0,0 +1,256 @@
+local io = itb_rs.io
+
+local function game_dir()
+    return io.directory()
+end
+
+local function wait_for_events(watcher)
+    local deadline = os.clock() + 5
+    local events = {}
+    while #events == 0 and os.clock() < deadline do
+        events = watcher:poll()
+    end
+    return events
+end
+
+test("file and directory default to the game directory", function()
+    local dir = io.directory()
+    local file = io.file()
+
+    assert_eq(dir:path(), file:path() .. "/")
+    assert_eq(dir:path(), dir:root():path())
+end)
+
+test("directories report paths with a trailing slash, files without", function()
+    local dir = game_dir():directory("mods", "my_mod")
+    local file = dir:file("scripts", "init.lua")
+
+    assert_true(dir:path():sub(-1) == "/")
+    -- Relative paths only get the trailing slash once the directory exists
+    assert_eq("mods/my_mod", dir:relative_path())
+    dir:make_directories()
+    assert_eq("mods/my_mod/", dir:relative_path())
+    assert_eq("mods/my_mod/scripts/init.lua", file:relative_path())
+    assert_eq("my_mod", dir:name())
+    assert_eq("init.lua", file:name())
+    assert_eq("init", file:name_without_extension())
+    assert_eq("lua", file:extension())
+    assert_eq(dir:path() .. "scripts/", file:parent():path())
+    assert_eq(game_dir():path(), file:root():path())
+end)
+
+test("relative paths are resolved against the game directory", function()
+    io.file("relative.txt"):write_string("content")
+
+    assert_true(game_dir():file("relative.txt"):exists())
+    assert_eq("content", game_dir():file("relative.txt"):read_to_string())
+end)
+
+test("files can be written and read", function()
+    local file = game_dir():file("data", "file.txt")
+
+    assert_false(file:exists())
+    file:write_string("first")
+    file:append_string(" second")
+
+    assert_true(file:exists())
+    assert_eq("first second", file:read_to_string())
+    assert_eq({ 102, 105, 114, 115, 116 }, { unpack(file:read_to_byte_array(), 1, 5) })
+
+    file:write_byte_array({ 0, 1, 255 })
+    assert_eq({ 0, 1, 255 }, file:read_to_byte_array())
+
+    file:write_byte_array(itb_rs.buffer.new("\0binary"))
+    local buffer = file:read_to_buffer()
+    assert_eq(7, buffer:len())
+    assert_eq("\0binary", buffer:tostring())
+
+    file:delete()
+    assert_false(file:exists())
+end)
+
+test("files can be gzipped", function()
+    if not itb_rs.has("compress") then
+        assert_false(itb_rs.has("File:write_gzip"))
+        return
+    end
+
+    local file = game_dir():file("data.gz")
+
+    file:write_gzip(string.rep("abc", 100), 9)
+
+    assert_true(#file:read_to_byte_array() < 300)
+    assert_eq(string.rep("abc", 100), file:read_gzip():tostring())
+    assert_error_kind("InvalidData", function() file:read_gzip(10) end)
+end)
+
+test("files can be copied and moved within the sandbox", function()
+    local file = game_dir():file("original.txt")
+    file:write_string("content")
+
+    file:copy(game_dir():path() .. "copies/copy.txt")
+    assert_eq("content", game_dir():file("copies", "copy.txt"):read_to_string())
+
+    file:move("moved.txt")
+    assert_false(file:exists())
+    assert_eq("content", game_dir():file("moved.txt"):read_to_string())
+end)
+
+test("paths outside of the sandbox are not allowed", function()
+    local file = game_dir():file("inside.txt")
+    file:write_string("content")
+
+    assert_error_kind("NotAllowed", io.file, "../outside.txt")
+    assert_error_kind("NotAllowed", io.directory, "../outside")
+    assert_error_kind("NotAllowed", function() game_dir():file("..", "outside.txt") end)
+    assert_error_kind("NotAllowed", function() game_dir():directory("..") end)
+    assert_error_kind("NotAllowed", function() game_dir():parent() end)
+    assert_error_kind("NotAllowed", function() file:copy("../outside.txt") end)
+    assert_error_kind("NotAllowed", function() file:move("../outside.txt") end)
+end)
+
+test("missing files report errors with their path", function()
+    local file = game_dir():file("missing.txt")
+
+    local err = assert_error_kind("NotFound", function() file:read_to_string() end)
+
+    assert_eq(file:path(), err.path)
+end)
+
+test("directories list their content", function()
+    local dir = game_dir():directory("listing")
+    assert_false(dir:exists())
+    dir:make_directories()
+    assert_true(dir:exists())
+    dir:file("a.txt"):write_string("a")
+    dir:directory("sub"):make_directories()
+
+    local files = dir:files()
+    local directories = dir:directories()
+
+    assert_eq(1, #files)
+    assert_eq("a.txt", files[1]:name())
+    assert_eq(1, #directories)
+    assert_eq("sub", directories[1]:name())
+end)
+
+test("directories relativize paths", function()
+    local dir = game_dir():directory("mods")
+
+    assert_eq("my_mod/init.lua", dir:relativize(dir:path() .. "my_mod/init.lua"))
+    assert_true(dir:is_ancestor(dir:path() .. "my_mod/init.lua"))
+    assert_false(dir:is_ancestor(game_dir():path() .. "other"))
+    assert_error_kind("InvalidInput", function() dir:is_ancestor("relative") end)
+end)
+
+test("directories can be deleted with progress", function()
+    local dir = game_dir():directory("to_delete")
+    dir:file("a.txt"):write_string("a")
+    dir:file("b.txt"):write_string("b")
+    local reports = 0
+
+    dir:delete(function(done, total, path)
+        reports = reports + 1
+        assert_true(done <= total)
+    end)
+
+    assert_false(dir:exists())
+    assert_true(reports >= 2)
+end)
+
+test("aborted deletion raises an error", function()
+    local dir = game_dir():directory("kept")
+    dir:file("a.txt"):write_string("a")
+
+    assert_error_kind("Aborted", function() dir:delete(function() return false end) end)
+    assert_true(dir:exists())
+
+    dir:delete()
+    assert_false(dir:exists())
+end)
+
+test("save data directory can only be switched to discovered locations", function()
+    local original = io.save_data_directory()
+    local other = game_dir():directory("other_save")
+    other:file("io_test.txt"):write_string("")
+
+    assert_error_kind("InvalidInput", io.set_save_data_directory, game_dir():directory("not_save"):path())
+    assert_error_kind("NotAllowed", io.set_save_data_directory, other:path())
+    assert_eq(original:path(), io.save_data_directory():path())
+end)
+
+test("save data candidates are listed", function()
+    local candidates = io.save_data_candidates()
+
+    assert_true(#candidates > 0)
+    for _, candidate in ipairs(candidates) do
+        assert_eq("string", type(candidate.path))
+        assert_eq("string", type(candidate.source))
+        assert_eq("boolean", type(candidate.valid))
+    end
+end)
+
+test("watchers report changes", function()
+    local dir = game_dir():directory("watched")
+    dir:make_directories()
+    local watcher = io.watch(dir, { debounce = 0, filters = "*.lua" })
+
+    dir:file("init.lua"):write_string("return {}")
+    dir:file("notes.txt"):write_string("")
+    local events = wait_for_events(watcher)
+
+    assert_true(#events > 0)
+    for _, event in ipairs(events) do
+        assert_eq("init.lua", event.path)
+    end
+
+    assert_false(watcher:is_closed())
+    watcher:close()
+    assert_true(watcher:is_closed())
+end)
+
+test("audit log records changes", function()
+    local audit = io.audit
+    assert_false(audit.is_enabled())
+
+    local log = audit.enable({ max_file_size = 4096, max_files = 2 })
+    audit.set_mod("my_mod")
+    game_dir():file("audited.txt"):write_string("content")
+    game_dir():file("audited.txt"):delete()
+
+    assert_true(audit.is_enabled())
+    assert_eq("my_mod", audit.current_mod())
+    assert_eq(io.save_data_directory():path() .. "itb_rs_audit.log", log:path())
+    -- Other tests may be writing files at the same time
+    local recent = {}
+    for _, entry in ipairs(audit.recent(50)) do
+        if entry.path:find("audited.txt", 1, true) then
+            table.insert(recent, entry)
+        end
+    end
+    assert_eq(2, #recent)
+    assert_eq("write", recent[1].operation)
+    assert_eq("delete", recent[2].operation)
+    assert_eq("my_mod", recent[2].mod)
+    assert_eq("number", type(recent[2].time))
+
+    audit.set_mod(nil)
+    audit.disable()
+    assert_false(audit.is_enabled())
+    assert_nil(audit.current_mod())
+end)
+
+test("safe variants return errors instead of raising them", function()
+    local file, kind, message = io.safe.file("../outside.txt")
+    assert_nil(file)
+    assert_eq("NotAllowed", kind)
+    assert_eq("string", type(message))
+
+    assert_eq(game_dir():path(), io.safe.directory():path())
+
+    local content, read_kind = io.safe.call(game_dir():file("missing.txt"), "read_to_string")
+    assert_nil(content)
+    assert_eq("NotFound", read_kind)
+
+    assert_true(io.safe.call(game_dir():file("safe.txt"), "write_string", "content"))
+end)
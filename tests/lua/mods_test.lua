@@ -0,0 +1,62 @@
+local mods = itb_rs.mods
+
+local function write_mod(directory, source)
+    itb_rs.io.directory("mods"):file(directory, "scripts", "init.lua"):write_string(source)
+end
+
+test("mods are scanned without executing their init scripts", function()
+    write_mod("library", [[
+        local mod = { id = "library", name = "Library", version = "1.2" }
+        function mod:init() error("executed") end
+        return mod
+    ]])
+    write_mod("consumer", [[
+        return {
+            id = "consumer",
+            version = "0.1.0",
+            dependencies = { library = "1.1" },
+            optionalDependencies = { "extras" },
+        }
+    ]])
+    write_mod("broken", "return { id = compute_id() }")
+
+    local result = mods.scan(itb_rs.io.directory("mods"))
+
+    assert_eq(2, #result.mods)
+    local consumer, library = result.mods[1], result.mods[2]
+    assert_eq("consumer", consumer.id)
+    assert_eq("library", consumer.dependencies[1].id)
+    assert_eq(">=1.1", consumer.dependencies[1].requirement)
+    assert_true(consumer.dependencies[2].optional)
+    assert_eq("1.2", library.version)
+    assert_eq("1.2.0", library.semver)
+    assert_eq("library", library.directory:name())
+    assert_eq(1, #result.invalid)
+    assert_eq("broken", result.invalid[1].directory:name())
+    assert_eq("InvalidData", result.invalid[1].error.kind)
+
+    assert_eq({ "library", "consumer" }, mods.load_order(result.mods).order)
+end)
+
+test("load order reports missing and cyclic dependencies", function()
+    local report = mods.load_order({
+        mods.read_metadata([[return { id = "a", requirements = { "b" } }]]),
+        mods.read_metadata([[return { id = "b", requirements = { "a" } }]]),
+        mods.read_metadata([[return { id = "c", dependencies = { d = ">=2" } }]]),
+        mods.read_metadata([[return { id = "d", version = "1.5" }]]),
+        { id = "e", dependencies = { "d" } },
+    })
+
+    assert_eq({ "d", "e" }, report.order)
+    assert_eq({ "a", "b", "c" }, report.excluded)
+    assert_eq({ { "a", "b" } }, report.cycles)
+    assert_eq(1, #report.missing)
+    assert_eq("c", report.missing[1].id)
+    assert_eq(">=2", report.missing[1].requirement)
+    assert_eq("1.5", report.missing[1].installed)
+end)
+
+test("malformed metadata is rejected", function()
+    assert_error_kind("InvalidData", mods.read_metadata, "return { name = 'No id' }")
+    assert_error_kind("InvalidInput", mods.load_order, { { name = "No id" } })
+end)
@@ -0,0 +1,70 @@
+local semver = itb_rs.semver
+
+test("versions are ordered by precedence", function()
+    local release, beta, previous = semver.parse("2.7.0"), semver.parse("2.7.0-beta"), semver.parse("2.6.4")
+
+    assert_true(previous < beta)
+    assert_true(beta < release)
+    assert_true(beta <= beta)
+    assert_false(release < beta)
+    assert_true(semver.parse("1.10") > semver.parse("1.9"))
+    assert_true(semver.parse("1.2.0") == semver.new(1, 2))
+    assert_eq(-1, semver.compare("2.6.4", "2.7.0-beta"))
+    assert_eq(1, semver.compare(release, "2.7.0-rc.1"))
+    assert_eq(0, semver.compare("v3", "3.0.0"))
+end)
+
+test("irregular versions are parsed leniently", function()
+    local version = semver.parse("1.2b")
+
+    assert_eq(1, version.major)
+    assert_eq(2, version.minor)
+    assert_eq(0, version.patch)
+    assert_eq("b", version.pre)
+    assert_true(version:is_prerelease())
+    assert_eq("3.0.0", tostring(semver.parse("v3")))
+    assert_eq("1.2.3+4", tostring(semver.parse("1.2.3.4")))
+    assert_error_kind("InvalidInput", semver.parse, "1.2b", { strict = true })
+    assert_error_kind("InvalidInput", semver.parse, "beta")
+end)
+
+test("versions are bumped", function()
+    local version = semver.parse("1.2.3-beta+build")
+
+    assert_eq("2.0.0", tostring(version:bump("major")))
+    assert_eq("1.3.0", tostring(version:bump("minor")))
+    assert_eq("1.2.3", tostring(version:bump("patch")))
+    assert_eq("1.2.4", tostring(version:bump("patch"):bump("patch")))
+    assert_error_kind("InvalidInput", version.bump, version, "build")
+    local largest = semver.parse("18446744073709551615")
+    assert_error_kind("InvalidInput", largest.bump, largest, "major")
+end)
+
+test("requirements are matched against versions", function()
+    local range = semver.requirement(">=1.2, <2")
+
+    assert_true(range:matches("1.2"))
+    assert_true(range:matches(semver.parse("1.9.9")))
+    assert_false(range:matches("2.0.0"))
+    assert_true(semver.matches("^1.4", "1.5.2"))
+    assert_false(semver.matches("^1.4", "1.3.9"))
+    assert_true(semver.matches("~1.2", "1.2.7"))
+    assert_false(semver.matches("~1.2", "1.3.0"))
+    assert_false(semver.matches(">=2.6", "2.7.0-beta"))
+    assert_true(semver.parse("2.7.0"):satisfies(">=2.6"))
+    assert_eq(">=1.2, <2", tostring(range))
+    assert_error_kind("InvalidInput", semver.requirement, "newest")
+end)
+
+test("bare versions are caret requirements unless minimum is set", function()
+    assert_false(semver.matches("1.2", "2.0.0"))
+    assert_false(semver.requirement("1.2"):matches("2.0.0"))
+
+    local minimum = semver.requirement("1.2", { minimum = true })
+
+    assert_true(minimum:matches("2.0.0"))
+    assert_false(minimum:matches("1.1.9"))
+    assert_eq(">=1.2", tostring(minimum))
+    assert_eq("^1.4", tostring(semver.requirement("^1.4", { minimum = true })))
+    assert_error_kind("InvalidInput", semver.requirement, "newest", { minimum = true })
+end)
@@ -0,0 +1,75 @@
+local task = itb_rs.task
+
+local function game_dir()
+    return itb_rs.io.directory()
+end
+
+local function wait(handle)
+    local deadline = os.clock() + 10
+    while not handle:is_done() and os.clock() < deadline do
+    end
+    assert_true(handle:is_done(), "task didn't finish in time")
+    return handle:result()
+end
+
+test("files can be written and read in the background", function()
+    local file = game_dir():file("task", "file.txt")
+
+    assert_nil(wait(task.spawn_write(file, "content")))
+    local content = wait(task.spawn_read(file))
+
+    assert_eq("content", content:tostring())
+end)
+
+test("files can be copied in the background", function()
+    local file = game_dir():file("task", "original.txt")
+    local destination = game_dir():file("task", "copy.txt")
+    file:write_string("content")
+
+    wait(task.spawn_copy(file, destination))
+
+    assert_eq("content", destination:read_to_string())
+end)
+
+test("directories can be packed and extracted in the background", function()
+    local source = game_dir():directory("task", "source")
+    source:file("scripts", "init.lua"):write_string("return {}")
+    source:file("img", "unit.png"):write_string("png")
+    local package = game_dir():file("task", "packed.pkg")
+
+    local pack = task.spawn_pack(source, package, { format = "pkg", prefix = "mods/", filters = "scripts/" })
+    assert_eq(1, wait(pack))
+    assert_eq({ 1, 1 }, { pack:progress() })
+    assert_eq({ "mods/scripts/init.lua" }, itb_rs.ftldat.read_package(package:path()):inner_paths())
+
+    local destination = game_dir():directory("task", "extracted")
+    assert_eq(1, wait(task.spawn_extract(package, destination)))
+    assert_eq("return {}", destination:file("mods", "scripts", "init.lua"):read_to_string())
+end)
+
+test("errors are raised by result", function()
+    local handle = task.spawn_read(game_dir():file("task", "missing.txt"))
+
+    local deadline = os.clock() + 10
+    while not handle:is_done() and os.clock() < deadline do
+    end
+    assert_error_kind("NotFound", function() handle:result() end)
+end)
+
+test("tasks can be cancelled", function()
+    local source = game_dir():directory("task", "many")
+    for i = 1, 200 do
+        source:file(i .. ".txt"):write_string(string.rep("x", 1024))
+    end
+
+    local handle = task.spawn_pack(source, game_dir():file("task", "cancelled.dat"))
+    handle:cancel()
+
+    local deadline = os.clock() + 10
+    while not handle:is_done() and os.clock() < deadline do
+    end
+    local ok, err = itb_rs.error.pcall(handle.result, handle)
+    if not ok then
+        assert_eq("Aborted", err.kind)
+    end
+end)
@@ -0,0 +1,88 @@
+//! Drives the API exposed to Lua with the scripts in `tests/lua`, each run in a fresh Lua state
+//! confined to temporary game and save data directories.
+// The test runner sets up the sandbox of the io module, so it needs the module even for scripts
+// that don't use it.
+#![cfg(all(feature = "vendored", feature = "io"))]
+
+use std::path::Path;
+
+use tempfile::TempDir;
+
+use itb_rs_lua::runner::{run_file, Sandbox};
+
+fn run(script: &str) {
+    let tmp_dir = TempDir::new().unwrap();
+    let game_directory = tmp_dir.path().join("game");
+    let save_data_directory = tmp_dir.path().join("save");
+    std::fs::create_dir_all(&game_directory).unwrap();
+    std::fs::create_dir_all(&save_data_directory).unwrap();
+    std::fs::write(save_data_directory.join("io_test.txt"), "").unwrap();
+
+    let sandbox = Sandbox::new(game_directory, save_data_directory).unwrap();
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lua").join(script);
+    let results = run_file(&sandbox, &path);
+
+    let failures: Vec<String> = results.iter()
+        .filter(|result| !result.passed())
+        .map(|result| format!("{}: {}", result.name, result.failure.as_ref().unwrap()))
+        .collect();
+    assert!(failures.is_empty(), "{} of {} tests failed:\n\n{}", failures.len(), results.len(), failures.join("\n\n"));
+}
+
+#[test]
+#[cfg(feature = "io")]
+fn io() {
+    run("io_test.lua");
+}
+
+#[test]
+#[cfg(feature = "ftldat")]
+fn ftldat() {
+    run("ftldat_test.lua");
+}
+
+#[test]
+#[cfg(feature = "buffer")]
+fn buffer() {
+    run("buffer_test.lua");
+}
+
+#[test]
+#[cfg(feature = "compress")]
+fn compress() {
+    run("compress_test.lua");
+}
+
+#[test]
+#[cfg(feature = "encoding")]
+fn encoding() {
+    run("encoding_test.lua");
+}
+
+#[test]
+#[cfg(feature = "task")]
+fn task() {
+    run("task_test.lua");
+}
+
+#[test]
+#[cfg(feature = "mods")]
+fn mods() {
+    run("mods_test.lua");
+}
+
+#[test]
+#[cfg(feature = "semver")]
+fn semver() {
+    run("semver_test.lua");
+}
+
+#[test]
+fn error() {
+    run("error_test.lua");
+}
+
+#[test]
+fn exports() {
+    run("exports_test.lua");
+}
@@ -0,0 +1,59 @@
+//! Loads the library into Lua the way the game and `require` do, through its `luaopen_*` entry
+//! points. The library is built the way it's released, with the `module` feature, and linked
+//! against the Lua of the test, which exports its C API for that purpose.
+#![cfg(all(feature = "vendored", target_os = "linux"))]
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::OnceLock;
+
+use mlua::Lua;
+
+/// Builds the library with the `module` feature, once for all tests, and returns its path.
+fn module_library() -> &'static PathBuf {
+    static LIBRARY: OnceLock<PathBuf> = OnceLock::new();
+    LIBRARY.get_or_init(|| {
+        let target_directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("module");
+        let output = Command::new(env!("CARGO"))
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .args(["build", "--lib", "--no-default-features", "--features", "module,full", "--target-dir"])
+            .arg(&target_directory)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "Failed to build the library with the module feature:\n{}",
+            String::from_utf8_lossy(&output.stderr));
+
+        target_directory.join("debug/libitb_rs_lua.so")
+    })
+}
+
+/// Runs the script with the library's path in the `library_path` global.
+fn run(script: &str) {
+    // Loading C libraries is disabled in the safe mode `Lua::new` runs in
+    let lua = unsafe { Lua::unsafe_new() };
+    lua.globals().set("library_path", module_library().to_string_lossy().to_string()).unwrap();
+    lua.load(script).exec().unwrap();
+}
+
+#[test]
+fn loadlib_should_return_exports_and_set_global() {
+    run(r#"
+        local open = assert(package.loadlib(library_path, "luaopen_itb_rs_lua"))
+        local exports = open()
+        assert(type(exports) == "table", "expected the exports table, got " .. type(exports))
+        assert(exports == itb_rs, "expected the global to be the exports table")
+        assert(exports.version.itb_rs ~= nil)
+    "#);
+}
+
+#[test]
+fn require_should_return_exports() {
+    run(r#"
+        package.preload["itb_rs"] = assert(package.loadlib(library_path, "luaopen_itb_rs"))
+        local itb = require("itb_rs")
+        assert(type(itb) == "table", "expected the exports table, got " .. type(itb))
+        assert(require("itb_rs") == itb)
+        assert(itb.has("error.pcall"))
+        assert(itb.error.pcall(function() return itb.compress.decompress("not compressed") end) == false)
+    "#);
+}